@@ -0,0 +1,445 @@
+// audit.rs - Security checks over the vault: reused, weak and old passwords,
+// and logins missing a username or website. Findings hold positions in the
+// entry list so the Security page can link straight to each entry.
+use std::collections::{BTreeMap, HashMap};
+
+use crate::st_json::{EntryKind, PasswordDetails, TIMESTAMP_FORMAT};
+
+// Words that show up in leaked password lists again and again. A password
+// built around one of them is only as strong as the rest of its characters.
+const COMMON_WORDS: [&str; 24] = [
+    "password", "passw0rd", "qwerty", "letmein", "welcome", "admin", "login", "dragon",
+    "monkey", "iloveyou", "sunshine", "princess", "football", "baseball", "master", "shadow",
+    "summer", "winter", "spring", "autumn", "hello", "abc123", "123456", "111111",
+];
+
+// Rough guessing cost of finding a common word, in bits
+const COMMON_WORD_BITS: f64 = 8.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Strength {
+    VeryWeak,
+    Weak,
+    Fair,
+    Strong,
+    VeryStrong,
+}
+
+impl std::fmt::Display for Strength {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Strength::VeryWeak => "Very weak",
+            Strength::Weak => "Weak",
+            Strength::Fair => "Fair",
+            Strength::Strong => "Strong",
+            Strength::VeryStrong => "Very strong",
+        })
+    }
+}
+
+// Estimated bits of entropy: each character is worth log2 of the character
+// pool it could have come from, except repeats and runs like "aaa" or "1234"
+// (1 bit each) and common words (a flat few bits for the whole word).
+pub fn estimate_entropy(password: &str) -> f64 {
+    let chars: Vec<char> = password.chars().map(|c| c.to_ascii_lowercase()).collect();
+    if chars.is_empty() {
+        return 0.0;
+    }
+
+    let mut pool = 0u32;
+    if password.chars().any(|c| c.is_ascii_lowercase()) { pool += 26; }
+    if password.chars().any(|c| c.is_ascii_uppercase()) { pool += 26; }
+    if password.chars().any(|c| c.is_ascii_digit()) { pool += 10; }
+    if password.chars().any(|c| c.is_ascii_punctuation() || c == ' ') { pool += 33; }
+    if !password.is_ascii() { pool += 100; }
+    let bits_per_char = (pool.max(2) as f64).log2();
+
+    // Mark characters that belong to a common word
+    let mut in_word = vec![false; chars.len()];
+    let mut bits = 0.0;
+    for word in COMMON_WORDS {
+        let word: Vec<char> = word.chars().collect();
+        if word.len() > chars.len() {
+            continue;
+        }
+        for start in 0..=chars.len() - word.len() {
+            if chars[start..start + word.len()] == word[..] && !in_word[start] {
+                in_word[start..start + word.len()].iter_mut().for_each(|covered| *covered = true);
+                bits += COMMON_WORD_BITS;
+            }
+        }
+    }
+
+    for (i, c) in chars.iter().enumerate() {
+        if in_word[i] {
+            continue;
+        }
+        let follows_pattern = i > 0 && {
+            let step = *c as i64 - chars[i - 1] as i64;
+            step.abs() <= 1
+        };
+        bits += if follows_pattern { 1.0 } else { bits_per_char };
+    }
+    bits
+}
+
+pub fn password_strength(password: &str) -> Strength {
+    strength_for_bits(estimate_entropy(password))
+}
+
+pub fn strength_for_bits(bits: f64) -> Strength {
+    match bits {
+        bits if bits < 28.0 => Strength::VeryWeak,
+        bits if bits < 36.0 => Strength::Weak,
+        bits if bits < 60.0 => Strength::Fair,
+        bits if bits < 80.0 => Strength::Strong,
+        _ => Strength::VeryStrong,
+    }
+}
+
+// Logins, API keys and Wi-Fi networks keep a secret in `value`; other types don't
+pub fn has_password(entry: &PasswordDetails) -> bool {
+    matches!(entry.kind, EntryKind::Login | EntryKind::ApiKey { .. } | EntryKind::Wifi { .. }) && !entry.value.is_empty()
+}
+
+// Passwords whose edit distance is at most this share of the longer one's
+// length count as variants, e.g. 1 change in "Summer2023!" (11 characters)
+const SIMILARITY_RATIO: f64 = 0.25;
+// Shorter passwords are too easy to match by chance
+const MIN_SIMILAR_LEN: usize = 6;
+
+// The "idea" behind a password: drops the digits and symbols people bolt on
+// at either end, undoes common letter swaps and lower-cases it, so that
+// "Summer2023!", "summer24" and "Summ3r!" all become "summer"
+fn base_pattern(password: &str) -> String {
+    password
+        .trim_matches(|c: char| !c.is_alphabetic())
+        .chars()
+        .map(|c| match c {
+            '0' => 'o',
+            '1' | '!' => 'i',
+            '3' => 'e',
+            '4' | '@' => 'a',
+            '5' | '$' => 's',
+            '7' => 't',
+            other => other,
+        })
+        .flat_map(|c| c.to_lowercase())
+        .collect()
+}
+
+// Levenshtein distance, or None as soon as it must be more than `max`.
+// Only a band of width 2 * max + 1 around the diagonal can stay within `max`,
+// so the rest of the table is never filled in.
+fn edit_distance_within(a: &[char], b: &[char], max: usize) -> Option<usize> {
+    if a.len().abs_diff(b.len()) > max {
+        return None;
+    }
+    let too_far = max + 1;
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current = vec![too_far; b.len() + 1];
+
+    for i in 1..=a.len() {
+        let from = i.saturating_sub(max).max(1);
+        let to = (i + max).min(b.len());
+        current.iter_mut().for_each(|cell| *cell = too_far);
+        if from == 1 {
+            current[0] = i;
+        }
+        let mut row_min = current[0];
+        for j in from..=to {
+            let substitution = previous[j - 1] + usize::from(a[i - 1] != b[j - 1]);
+            let value = substitution.min(previous[j] + 1).min(current[j - 1] + 1).min(too_far);
+            current[j] = value;
+            row_min = row_min.min(value);
+        }
+        if row_min > max {
+            return None;
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+    let distance = previous[b.len()];
+    (distance <= max).then_some(distance)
+}
+
+fn find_root(parents: &mut [usize], item: usize) -> usize {
+    let mut root = item;
+    while parents[root] != root {
+        root = parents[root];
+    }
+    // Point everything on the way straight at the root for next time
+    let mut item = item;
+    while parents[item] != root {
+        let next = parents[item];
+        parents[item] = root;
+        item = next;
+    }
+    root
+}
+
+// Families of passwords that are small variations of each other. Two distinct
+// passwords are linked when they share a base pattern or are within
+// SIMILARITY_RATIO edits of each other; linked passwords form one cluster, so
+// "Summer2022!" and "Summer2024!" end up together through "Summer2023!".
+// Entries with the exact same password are one node, and clusters made of a
+// single password (plain reuse) are left to the reuse check.
+pub fn similar_clusters(entries: &[PasswordDetails]) -> Vec<Vec<usize>> {
+    // Each distinct password once, with the entries that use it
+    let mut by_value: BTreeMap<&str, Vec<usize>> = BTreeMap::new();
+    for (index, entry) in entries.iter().enumerate().filter(|(_, entry)| has_password(entry)) {
+        by_value.entry(entry.value.as_str()).or_default().push(index);
+    }
+    let mut values: Vec<(Vec<char>, Vec<usize>)> = by_value
+        .into_iter()
+        .map(|(value, indexes)| (value.to_lowercase().chars().collect(), indexes))
+        .collect();
+    // Sorted by length so each password is only compared with the few that
+    // are close enough in length to be within reach
+    values.sort_by_key(|(chars, _)| chars.len());
+
+    let mut parents: Vec<usize> = (0..values.len()).collect();
+
+    let mut by_pattern: BTreeMap<String, usize> = BTreeMap::new();
+    for (node, (chars, _)) in values.iter().enumerate() {
+        let pattern = base_pattern(&chars.iter().collect::<String>());
+        if pattern.chars().count() < 4 {
+            continue;
+        }
+        match by_pattern.get(&pattern) {
+            Some(&other) => {
+                let (a, b) = (find_root(&mut parents, node), find_root(&mut parents, other));
+                parents[a] = b;
+            }
+            None => {
+                by_pattern.insert(pattern, node);
+            }
+        }
+    }
+
+    // Comparing every pair is too slow for big vaults, so candidates come from
+    // shared bigrams first. One edit changes at most two bigrams, so passwords
+    // within k edits share at least (longer length - 1 - 2k) of them; pairs
+    // below that can't be similar and are never compared.
+    let bigrams: Vec<HashMap<(char, char), usize>> = values
+        .iter()
+        .map(|(chars, _)| {
+            let mut counts = HashMap::new();
+            for pair in chars.windows(2) {
+                *counts.entry((pair[0], pair[1])).or_insert(0) += 1;
+            }
+            counts
+        })
+        .collect();
+    let mut postings: HashMap<(char, char), Vec<(usize, usize)>> = HashMap::new();
+    for (node, counts) in bigrams.iter().enumerate() {
+        if values[node].0.len() >= MIN_SIMILAR_LEN {
+            for (bigram, count) in counts {
+                postings.entry(*bigram).or_default().push((node, *count));
+            }
+        }
+    }
+
+    // Shared bigram counts for the password being checked; `touched` lists the
+    // non-zero ones so they can be reset without clearing the whole list
+    let mut shared = vec![0usize; values.len()];
+    let mut touched = Vec::new();
+    for first in 0..values.len() {
+        if values[first].0.len() < MIN_SIMILAR_LEN {
+            continue;
+        }
+        for (bigram, count) in &bigrams[first] {
+            for &(second, other_count) in &postings[bigram] {
+                if second > first {
+                    if shared[second] == 0 {
+                        touched.push(second);
+                    }
+                    shared[second] += (*count).min(other_count);
+                }
+            }
+        }
+
+        for second in touched.drain(..) {
+            let shared_count = std::mem::take(&mut shared[second]);
+            let longer = values[second].0.len();
+            let max_distance = (longer as f64 * SIMILARITY_RATIO) as usize;
+            if longer - values[first].0.len() > max_distance || shared_count + 1 + 2 * max_distance < longer {
+                continue;
+            }
+            if find_root(&mut parents, first) == find_root(&mut parents, second) {
+                continue;
+            }
+            if edit_distance_within(&values[first].0, &values[second].0, max_distance).is_some() {
+                let (a, b) = (find_root(&mut parents, first), find_root(&mut parents, second));
+                parents[a] = b;
+            }
+        }
+    }
+
+    let mut clusters: BTreeMap<usize, (usize, Vec<usize>)> = BTreeMap::new();
+    for (node, (_, indexes)) in values.iter().enumerate() {
+        let root = find_root(&mut parents, node);
+        let cluster = clusters.entry(root).or_default();
+        cluster.0 += 1;
+        cluster.1.extend(indexes);
+    }
+    let mut clusters: Vec<Vec<usize>> = clusters
+        .into_values()
+        .filter(|(distinct, _)| *distinct > 1)
+        .map(|(_, mut indexes)| {
+            indexes.sort();
+            indexes
+        })
+        .collect();
+    clusters.sort_by_key(|cluster| cluster[0]);
+    clusters
+}
+
+// When the password was last set: updated_at, or created_at if never edited
+pub fn last_changed(entry: &PasswordDetails) -> Option<chrono::NaiveDateTime> {
+    [&entry.updated_at, &entry.created_at]
+        .into_iter()
+        .find_map(|timestamp| chrono::NaiveDateTime::parse_from_str(timestamp, TIMESTAMP_FORMAT).ok())
+}
+
+// Entries show as due this many days before their rotation date
+pub const DUE_SOON_DAYS: i64 = 7;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RotationStatus {
+    // No rotation interval on the entry
+    NotSet,
+    UpToDate,
+    // Days left until the password should be changed
+    DueSoon(i64),
+    // Days since it should have been changed
+    Overdue(i64),
+}
+
+impl RotationStatus {
+    pub fn needs_attention(&self) -> bool {
+        matches!(self, RotationStatus::DueSoon(_) | RotationStatus::Overdue(_))
+    }
+}
+
+impl std::fmt::Display for RotationStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RotationStatus::NotSet | RotationStatus::UpToDate => Ok(()),
+            RotationStatus::DueSoon(0) => write!(f, "Rotation due today"),
+            RotationStatus::DueSoon(days) => write!(f, "Rotation due in {} days", days),
+            RotationStatus::Overdue(0) => write!(f, "Rotation overdue"),
+            RotationStatus::Overdue(days) => write!(f, "Rotation overdue by {} days", days),
+        }
+    }
+}
+
+// Where an entry stands against its rotation interval, counted from when it
+// was last changed. An entry with no readable date is treated as overdue.
+pub fn rotation_status(entry: &PasswordDetails, now: chrono::NaiveDateTime) -> RotationStatus {
+    if entry.rotation_days == 0 {
+        return RotationStatus::NotSet;
+    }
+    let Some(changed) = last_changed(entry) else {
+        return RotationStatus::Overdue(0);
+    };
+    let days_left = entry.rotation_days as i64 - (now - changed).num_days();
+    if days_left < 0 {
+        RotationStatus::Overdue(-days_left)
+    } else if days_left <= DUE_SOON_DAYS {
+        RotationStatus::DueSoon(days_left)
+    } else {
+        RotationStatus::UpToDate
+    }
+}
+
+// Names of overdue entries, for the notice shown at startup
+pub fn overdue_names(entries: &[PasswordDetails]) -> Vec<String> {
+    let now = chrono::Utc::now().naive_utc();
+    entries
+        .iter()
+        .filter(|entry| matches!(rotation_status(entry, now), RotationStatus::Overdue(_)))
+        .map(|entry| entry.name.clone())
+        .collect()
+}
+
+#[derive(Debug, Default)]
+pub struct AuditReport {
+    // Groups of entries that share exactly the same password
+    pub reused: Vec<Vec<usize>>,
+    // Clusters of passwords that are small variations of each other
+    pub similar: Vec<Vec<usize>>,
+    pub weak: Vec<(usize, Strength)>,
+    // Entries whose password is older than the limit, with its age in months
+    pub old: Vec<(usize, i64)>,
+    // Entries past their own rotation interval, with days overdue
+    pub overdue: Vec<(usize, i64)>,
+    pub missing_username: Vec<usize>,
+    pub missing_website: Vec<usize>,
+    // Entries whose password is in the Pwned Passwords list, with its breach count.
+    // Filled in separately, only when a list has been set up in Settings.
+    pub breached: Vec<(usize, u64)>,
+}
+
+impl AuditReport {
+    pub fn is_clean(&self) -> bool {
+        self.reused.is_empty()
+            && self.similar.is_empty()
+            && self.weak.is_empty()
+            && self.old.is_empty()
+            && self.overdue.is_empty()
+            && self.missing_username.is_empty()
+            && self.missing_website.is_empty()
+            && self.breached.is_empty()
+    }
+}
+
+// Groups entry positions by `key`, keeping only groups with more than one entry
+fn groups_by<F: Fn(&PasswordDetails) -> String>(entries: &[PasswordDetails], key: F) -> Vec<Vec<usize>> {
+    let mut groups: BTreeMap<String, Vec<usize>> = BTreeMap::new();
+    for (index, entry) in entries.iter().enumerate().filter(|(_, entry)| has_password(entry)) {
+        groups.entry(key(entry)).or_default().push(index);
+    }
+    let mut groups: Vec<Vec<usize>> = groups.into_values().filter(|group| group.len() > 1).collect();
+    groups.sort_by_key(|group| group[0]);
+    groups
+}
+
+// Runs every check. `max_age_months` of 0 turns the age check off.
+pub fn audit(entries: &[PasswordDetails], max_age_months: u32) -> AuditReport {
+    let mut report = AuditReport {
+        reused: groups_by(entries, |entry| entry.value.clone()),
+        ..Default::default()
+    };
+
+    report.similar = similar_clusters(entries);
+
+    let now = chrono::Utc::now().naive_utc();
+    for (index, entry) in entries.iter().enumerate() {
+        if has_password(entry) {
+            let strength = password_strength(&entry.value);
+            if strength <= Strength::Weak {
+                report.weak.push((index, strength));
+            }
+            if max_age_months > 0
+                && let Some(changed) = last_changed(entry)
+            {
+                let days = (now - changed).num_days();
+                if days > max_age_months as i64 * 30 {
+                    report.old.push((index, days / 30));
+                }
+            }
+        }
+        if let RotationStatus::Overdue(days) = rotation_status(entry, now) {
+            report.overdue.push((index, days));
+        }
+        if entry.kind == EntryKind::Login {
+            if entry.username.trim().is_empty() {
+                report.missing_username.push(index);
+            }
+            if entry.website.trim().is_empty() {
+                report.missing_website.push(index);
+            }
+        }
+    }
+    report
+}
@@ -0,0 +1,223 @@
+// backup.rs - Password-protected backup archives (.saltrbak) and restoring them.
+//
+// File layout (integers are little-endian):
+//   "SALTRBAK"                 magic, 8 bytes
+//   format version             u16
+//   KDF id                     u8 (1 = Argon2id)
+//   memory KiB, iterations, parallelism    u32 each
+//   salt                       16 bytes
+//   nonce + ciphertext         ChaCha20-Poly1305 over the gzipped JSON contents,
+//                              with everything before it as associated data
+//
+// Saltr has no attachments yet, so a backup holds only the vault and settings.
+// Attachments would go in BackupContents once entries can have them.
+use serde::{Deserialize, Serialize};
+use std::io::{Read, Write};
+
+use crate::crypto::{self, KdfParams};
+use crate::history_key;
+use crate::settings::{self, Settings};
+use crate::st_json::{self, PasswordDatabase};
+
+pub const MAGIC: &[u8; 8] = b"SALTRBAK";
+pub const FORMAT_VERSION: u16 = 1;
+const KDF_ARGON2ID: u8 = 1;
+const HEADER_LEN: usize = 8 + 2 + 1 + 12 + crypto::SALT_LEN;
+
+// Refuse settings that would need more than 2 GiB of memory, or hours of
+// work, to open; a damaged or crafted file could ask for anything
+const MAX_MEMORY_KIB: u32 = 2 * 1024 * 1024;
+const MAX_ITERATIONS: u32 = 100;
+const MAX_PARALLELISM: u32 = 64;
+
+// What goes into the archive
+#[derive(Serialize, Deserialize)]
+pub struct BackupContents {
+    pub created_at: String,
+    pub vault: PasswordDatabase,
+    pub settings: Settings,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RestoreMode {
+    // Add the backup's entries to the current vault, skipping duplicates
+    Merge,
+    // Make the backup the vault again, settings included
+    Replace,
+}
+
+// Writes the vault and settings to `path`, encrypted with `password`.
+// Returns the number of entries saved.
+pub fn create_backup(path: &str, password: &str, vault_path: &str, settings_path: &str) -> Result<usize, String> {
+    let contents = BackupContents {
+        created_at: st_json::timestamp_now(),
+        vault: st_json::load_password_database(vault_path).map_err(|e| format!("Error loading passwords: {}", e))?,
+        settings: settings::load_settings(settings_path).map_err(|e| format!("Error loading settings: {}", e))?,
+    };
+    let json = serde_json::to_vec(&contents).map_err(|e| format!("Could not build backup: {}", e))?;
+
+    let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+    encoder.write_all(&json).map_err(|e| format!("Could not compress backup: {}", e))?;
+    let compressed = encoder.finish().map_err(|e| format!("Could not compress backup: {}", e))?;
+
+    let params = KdfParams::DEFAULT;
+    let salt = crypto::random_bytes::<{ crypto::SALT_LEN }>();
+    let mut out = Vec::with_capacity(HEADER_LEN + compressed.len() + 32);
+    out.extend_from_slice(MAGIC);
+    out.extend_from_slice(&FORMAT_VERSION.to_le_bytes());
+    out.push(KDF_ARGON2ID);
+    out.extend_from_slice(&params.memory_kib.to_le_bytes());
+    out.extend_from_slice(&params.iterations.to_le_bytes());
+    out.extend_from_slice(&params.parallelism.to_le_bytes());
+    out.extend_from_slice(&salt);
+
+    let key = crypto::derive_key(password, &salt, params)?;
+    let sealed = crypto::encrypt(&key, &compressed, &out);
+    out.extend_from_slice(&sealed);
+
+    std::fs::write(path.trim(), out).map_err(|e| format!("Could not write backup: {}", e))?;
+    Ok(contents.vault.passwords.len())
+}
+
+pub fn read_backup(path: &str, password: &str) -> Result<BackupContents, String> {
+    let bytes = std::fs::read(path.trim()).map_err(|e| format!("Could not open backup: {}", e))?;
+    if bytes.len() < HEADER_LEN || &bytes[..8] != MAGIC {
+        return Err("Not a Saltr backup file".to_string());
+    }
+    let (header, sealed) = bytes.split_at(HEADER_LEN);
+
+    let u32_at = |offset: usize| u32::from_le_bytes([header[offset], header[offset + 1], header[offset + 2], header[offset + 3]]);
+    let version = u16::from_le_bytes([header[8], header[9]]);
+    if version > FORMAT_VERSION {
+        return Err(format!("This backup was made by a newer version of Saltr (format {})", version));
+    }
+    if header[10] != KDF_ARGON2ID {
+        return Err("Unsupported key derivation in backup".to_string());
+    }
+    let params = KdfParams { memory_kib: u32_at(11), iterations: u32_at(15), parallelism: u32_at(19) };
+    if params.memory_kib > MAX_MEMORY_KIB {
+        return Err("Backup asks for too much memory to open".to_string());
+    }
+    if params.iterations > MAX_ITERATIONS {
+        return Err(format!("Backup asks for {} Argon2 iterations; the limit is {}", params.iterations, MAX_ITERATIONS));
+    }
+    if params.parallelism > MAX_PARALLELISM {
+        return Err(format!("Backup asks for {} Argon2 lanes; the limit is {}", params.parallelism, MAX_PARALLELISM));
+    }
+    let salt = &header[23..23 + crypto::SALT_LEN];
+
+    let key = crypto::derive_key(password, salt, params)?;
+    let compressed = crypto::decrypt(&key, sealed, header)?;
+
+    let mut json = Vec::new();
+    flate2::read::GzDecoder::new(compressed.as_slice())
+        .read_to_end(&mut json)
+        .map_err(|e| format!("Could not decompress backup: {}", e))?;
+    serde_json::from_slice(&json).map_err(|e| format!("Backup contents are invalid: {}", e))
+}
+
+// Restores a backup into the vault at `vault_path`. Replace keeps a copy of the
+// current vault next to it (<vault>.before-restore) before overwriting it.
+// Returns (entries added, duplicates skipped).
+pub fn restore_backup(
+    contents: &BackupContents,
+    mode: RestoreMode,
+    vault_path: &str,
+    settings_path: &str,
+) -> Result<(usize, usize), std::io::Error> {
+    match mode {
+        RestoreMode::Merge => {
+            // Old passwords in the backup are sealed with its history password; they
+            // can only join a vault that uses the same one, or none yet
+            let mut database = st_json::load_password_database(vault_path)?;
+            if history_key::count_sealed(&contents.vault) > 0 {
+                match &database.history_lock {
+                    None => {
+                        database.history_lock = contents.vault.history_lock.clone();
+                        st_json::save_password_database(&database, vault_path)?;
+                    }
+                    Some(lock) if Some(lock) != contents.vault.history_lock.as_ref() => {
+                        return Err(std::io::Error::new(
+                            std::io::ErrorKind::InvalidData,
+                            "the backup's password history uses a different history password; restore it with Replace instead",
+                        ));
+                    }
+                    Some(_) => {}
+                }
+            }
+            let counts = st_json::import_entries(contents.vault.passwords.clone(), vault_path)?;
+            st_json::import_trash(contents.vault.trash.clone(), vault_path)?;
+            Ok(counts)
+        }
+        RestoreMode::Replace => {
+            if std::path::Path::new(vault_path).exists() {
+                std::fs::copy(vault_path, format!("{}.before-restore", vault_path))?;
+            }
+            st_json::save_password_database(&contents.vault, vault_path)?;
+            settings::save_settings(&contents.settings, settings_path)?;
+            Ok((contents.vault.passwords.len(), 0))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_path(name: &str) -> String {
+        std::env::temp_dir()
+            .join(format!("saltr-backup-test-{}-{}", std::process::id(), name))
+            .to_string_lossy()
+            .to_string()
+    }
+
+    fn vault_with(names: &[&str], path: &str) {
+        let database = PasswordDatabase {
+            passwords: names.iter().map(|name| st_json::create_password_details(name, "secret", "", "", "")).collect(),
+            ..PasswordDatabase::default()
+        };
+        st_json::save_password_database(&database, path).unwrap();
+    }
+
+    fn names(path: &str) -> Vec<String> {
+        st_json::load_password_database(path).unwrap().passwords.into_iter().map(|p| p.name).collect()
+    }
+
+    #[test]
+    fn backup_reads_back_and_restores() {
+        let (vault, settings_path, backup) = (temp_path("vault.json"), temp_path("settings.json"), temp_path("backup.saltrbak"));
+        vault_with(&["Mail", "Bank"], &vault);
+        let settings = Settings { max_password_history: 3, ..Settings::default() };
+        settings::save_settings(&settings, &settings_path).unwrap();
+
+        assert_eq!(create_backup(&backup, "backup password", &vault, &settings_path).unwrap(), 2);
+        let contents = read_backup(&backup, "backup password").unwrap();
+        assert_eq!(contents.vault.passwords.len(), 2);
+        assert_eq!(contents.settings.max_password_history, 3);
+        assert!(read_backup(&backup, "wrong password").is_err());
+
+        // The header is associated data: changing the version (still a valid one)
+        // must make the backup fail to open
+        let mut bytes = std::fs::read(&backup).unwrap();
+        bytes[8..10].copy_from_slice(&0u16.to_le_bytes());
+        let tampered = temp_path("tampered.saltrbak");
+        std::fs::write(&tampered, bytes).unwrap();
+        assert!(read_backup(&tampered, "backup password").is_err());
+
+        // Merge adds what's missing and skips duplicates
+        vault_with(&["Mail", "Shop"], &vault);
+        assert_eq!(restore_backup(&contents, RestoreMode::Merge, &vault, &settings_path).unwrap(), (1, 1));
+        assert_eq!(names(&vault), ["Mail", "Shop", "Bank"]);
+
+        // Replace puts the backup back as it was, settings included, and keeps the old vault
+        settings::save_settings(&Settings::default(), &settings_path).unwrap();
+        assert_eq!(restore_backup(&contents, RestoreMode::Replace, &vault, &settings_path).unwrap(), (2, 0));
+        assert_eq!(names(&vault), ["Mail", "Bank"]);
+        assert_eq!(names(&format!("{}.before-restore", vault)), ["Mail", "Shop", "Bank"]);
+        assert_eq!(settings::load_settings(&settings_path).unwrap().max_password_history, 3);
+
+        for path in [vault.clone(), format!("{}.before-restore", vault), settings_path, backup, tampered] {
+            std::fs::remove_file(path).unwrap();
+        }
+    }
+}
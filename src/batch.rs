@@ -0,0 +1,237 @@
+// batch.rs - Many passwords at once, for setting up a lot of accounts in one go.
+// Passwords follow one policy (any generator except derived, which needs a
+// site per password) and get names from a template like "svc-{n}". They go to
+// CSV (file, clipboard or stdout) and can also be saved as vault entries.
+use rand::Rng;
+use std::io::Write;
+
+use crate::genr;
+use crate::presets;
+use crate::st_json;
+
+pub const MAX_COUNT: usize = 10_000;
+pub const DEFAULT_TEMPLATE: &str = "account-{n}";
+
+const CLI_USAGE: &str = "Usage: pass_gen batch --count N [options]
+  --count N            how many passwords to make (1-10000)
+  --mode MODE          random (default), pattern, pronounceable or passphrase
+  --length N           length for random and pronounceable passwords
+  --pattern PATTERN    pattern for --mode pattern, e.g. \"[A-Z]{4}-\\d{4}\"
+  --digits N           digits in pronounceable passwords
+  --symbols N          symbols in pronounceable passwords
+  --words N            words per passphrase (default 5)
+  --separator TEXT     between passphrase words (default \"-\")
+  --names TEMPLATE     entry names; {n} is the number (default \"account-{n}\")
+  --output FILE        CSV file to write, or - for stdout (default)
+  --save               also save them as entries in passwords.json";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum BatchKind {
+    #[default]
+    Random,
+    Pattern,
+    Pronounceable,
+    Passphrase,
+}
+
+impl BatchKind {
+    pub const ALL: [BatchKind; 4] = [
+        BatchKind::Random,
+        BatchKind::Pattern,
+        BatchKind::Pronounceable,
+        BatchKind::Passphrase,
+    ];
+}
+
+impl std::fmt::Display for BatchKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            BatchKind::Random => "Random",
+            BatchKind::Pattern => "Pattern",
+            BatchKind::Pronounceable => "Pronounceable",
+            BatchKind::Passphrase => "Passphrase",
+        })
+    }
+}
+
+// What every password in the batch is made with
+#[derive(Debug, Clone, PartialEq)]
+pub enum BatchPolicy {
+    Random { length: usize },
+    Pattern(genr::Pattern),
+    Pronounceable(genr::PronounceableOptions),
+    Passphrase { words: usize, separator: String },
+}
+
+impl BatchPolicy {
+    fn generate<R: Rng + ?Sized>(&self, rng: &mut R) -> Result<String, String> {
+        match self {
+            BatchPolicy::Random { length } => Ok(genr::random_password(rng, *length)),
+            BatchPolicy::Pattern(pattern) => Ok(genr::generate_from_pattern(rng, pattern)),
+            BatchPolicy::Pronounceable(options) => genr::generate_pronounceable(rng, options),
+            BatchPolicy::Passphrase { words, separator } => genr::generate_passphrase(rng, *words, separator),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct BatchItem {
+    pub name: String,
+    pub password: String,
+}
+
+// "svc-{n}" -> "svc-7"; a template without {n} gets "-{n}" added so names differ
+pub fn expand_name(template: &str, number: usize) -> String {
+    let template = template.trim();
+    let template = if template.is_empty() { DEFAULT_TEMPLATE } else { template };
+    if template.contains("{n}") {
+        template.replace("{n}", &number.to_string())
+    } else {
+        format!("{}-{}", template, number)
+    }
+}
+
+pub fn generate_batch<R: Rng + ?Sized>(rng: &mut R, policy: &BatchPolicy, count: usize, template: &str) -> Result<Vec<BatchItem>, String> {
+    if !(1..=MAX_COUNT).contains(&count) {
+        return Err(format!("Count must be between 1 and {}", MAX_COUNT));
+    }
+    // Same limits as the generator page, so a huge length can't use up all memory
+    match policy {
+        BatchPolicy::Random { length } if !(1..=presets::MAX_RANDOM_LENGTH).contains(length) => {
+            return Err(format!("Length must be between 1 and {}", presets::MAX_RANDOM_LENGTH));
+        }
+        BatchPolicy::Pronounceable(options) => options.check()?,
+        _ => {}
+    }
+    (1..=count)
+        .map(|number| {
+            Ok(BatchItem {
+                name: expand_name(template, number),
+                password: policy.generate(rng)?,
+            })
+        })
+        .collect()
+}
+
+// "name,password" rows with a header
+pub fn to_csv(items: &[BatchItem]) -> Result<String, String> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    writer
+        .write_record(["name", "password"])
+        .map_err(|e| format!("Could not write CSV: {}", e))?;
+    for item in items {
+        writer
+            .write_record([&item.name, &item.password])
+            .map_err(|e| format!("Could not write CSV: {}", e))?;
+    }
+    let bytes = writer.into_inner().map_err(|e| format!("Could not write CSV: {}", e))?;
+    String::from_utf8(bytes).map_err(|e| format!("Could not write CSV: {}", e))
+}
+
+// Adds the batch to the vault as logins. Returns (added, duplicates).
+pub fn save_as_entries(items: &[BatchItem], vault_path: &str) -> Result<(usize, usize), String> {
+    let entries = items
+        .iter()
+        .map(|item| st_json::create_password_details(&item.name, &item.password, "", "", ""))
+        .collect();
+    st_json::import_entries(entries, vault_path).map_err(|e| format!("Error saving entries: {}", e))
+}
+
+pub fn run_cli(args: &[String]) -> Result<(), String> {
+    let mut count = None;
+    let mut kind = BatchKind::Random;
+    let mut length = None;
+    let mut pattern = String::new();
+    let mut pronounceable = genr::PronounceableOptions::default();
+    let mut words = 5;
+    let mut separator = "-".to_string();
+    let mut template = DEFAULT_TEMPLATE.to_string();
+    let mut output = "-".to_string();
+    let mut save = false;
+
+    let mut args = args.iter();
+    let value = |name: &str, args: &mut std::slice::Iter<String>| {
+        args.next().cloned().ok_or_else(|| format!("{} needs a value\n{}", name, CLI_USAGE))
+    };
+    let number = |name: &str, text: String| {
+        text.parse::<usize>().map_err(|_| format!("{} must be a whole number\n{}", name, CLI_USAGE))
+    };
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--count" => count = Some(number("--count", value("--count", &mut args)?)?),
+            "--mode" => {
+                kind = match value("--mode", &mut args)?.to_ascii_lowercase().as_str() {
+                    "random" => BatchKind::Random,
+                    "pattern" => BatchKind::Pattern,
+                    "pronounceable" => BatchKind::Pronounceable,
+                    "passphrase" => BatchKind::Passphrase,
+                    _ => return Err(format!("--mode must be random, pattern, pronounceable or passphrase\n{}", CLI_USAGE)),
+                }
+            }
+            "--length" => length = Some(number("--length", value("--length", &mut args)?)?),
+            "--pattern" => pattern = value("--pattern", &mut args)?,
+            "--digits" => pronounceable.digits = number("--digits", value("--digits", &mut args)?)?,
+            "--symbols" => pronounceable.symbols = number("--symbols", value("--symbols", &mut args)?)?,
+            "--words" => words = number("--words", value("--words", &mut args)?)?,
+            "--separator" => separator = value("--separator", &mut args)?,
+            "--names" => template = value("--names", &mut args)?,
+            "--output" => output = value("--output", &mut args)?,
+            "--save" => save = true,
+            "--help" | "-h" => {
+                println!("{}", CLI_USAGE);
+                return Ok(());
+            }
+            other => return Err(format!("Unknown option '{}'\n{}", other, CLI_USAGE)),
+        }
+    }
+    let count = count.ok_or_else(|| format!("--count is required\n{}", CLI_USAGE))?;
+
+    let policy = match kind {
+        BatchKind::Random => BatchPolicy::Random { length: length.unwrap_or(16) },
+        BatchKind::Pattern => BatchPolicy::Pattern(genr::parse_pattern(&pattern)?),
+        BatchKind::Pronounceable => {
+            pronounceable.length = length.unwrap_or(pronounceable.length);
+            pronounceable.check()?;
+            BatchPolicy::Pronounceable(pronounceable)
+        }
+        BatchKind::Passphrase => BatchPolicy::Passphrase { words, separator },
+    };
+    let items = generate_batch(&mut rand::rng(), &policy, count, &template)?;
+    let csv_text = to_csv(&items)?;
+
+    if output == "-" {
+        std::io::stdout()
+            .lock()
+            .write_all(csv_text.as_bytes())
+            .map_err(|e| format!("Could not write passwords: {}", e))?;
+    } else {
+        std::fs::write(&output, csv_text).map_err(|e| format!("Could not write {}: {}", output, e))?;
+        eprintln!("Wrote {} passwords to {}", items.len(), output);
+    }
+    if save {
+        let (added, duplicates) = save_as_entries(&items, "passwords.json")?;
+        eprintln!("Saved {} entries ({} already there)", added, duplicates);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::genr::tests::seeded;
+
+    #[test]
+    fn lengths_are_limited() {
+        let mut rng = seeded(45);
+        let too_long = BatchPolicy::Random { length: presets::MAX_RANDOM_LENGTH + 1 };
+        assert!(generate_batch(&mut rng, &too_long, 1, "").is_err());
+        assert!(generate_batch(&mut rng, &BatchPolicy::Random { length: 0 }, 1, "").is_err());
+        let options = genr::PronounceableOptions { length: 100_000_000, digits: 0, symbols: 0 };
+        assert!(generate_batch(&mut rng, &BatchPolicy::Pronounceable(options), 1, "").is_err());
+
+        let items = generate_batch(&mut rng, &BatchPolicy::Random { length: presets::MAX_RANDOM_LENGTH }, 3, "svc-{n}").unwrap();
+        assert_eq!(items.len(), 3);
+        assert_eq!(items[2].name, "svc-3");
+        assert_eq!(items[0].password.len(), presets::MAX_RANDOM_LENGTH);
+    }
+}
@@ -0,0 +1,832 @@
+// bitwarden.rs - Import and export Bitwarden's JSON export format, both the
+// plain file and the password-protected ("encrypted": true) variant.
+//
+// Password-protected exports derive a key from the file password (PBKDF2-SHA256
+// or Argon2id), stretch it with HKDF-Expand into an encryption key and a MAC key,
+// and store the plain export as an EncString: "2.<iv>|<ciphertext>|<mac>", i.e.
+// AES-256-CBC with an HMAC-SHA256 over iv + ciphertext.
+//
+// Anything Saltr has no field for (extra URIs, card brand, identity details,
+// passkeys...) is kept in the entry's notes rather than dropped.
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use hmac::{Hmac, Mac};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+
+use crate::otp;
+use crate::st_json::{
+    self, CustomField, EntryKind, PasswordDatabase, PasswordDetails, PasswordHistoryEntry, TIMESTAMP_FORMAT,
+    TrashedEntry,
+};
+
+// Item types
+const TYPE_LOGIN: u32 = 1;
+const TYPE_SECURE_NOTE: u32 = 2;
+const TYPE_CARD: u32 = 3;
+const TYPE_IDENTITY: u32 = 4;
+const TYPE_SSH_KEY: u32 = 5;
+
+// Custom field types; boolean fields (2) import as text "true"/"false"
+const FIELD_TEXT: u32 = 0;
+const FIELD_HIDDEN: u32 = 1;
+const FIELD_LINKED: u32 = 3;
+
+// KDF types for password-protected exports
+const KDF_PBKDF2: u32 = 0;
+const KDF_ARGON2ID: u32 = 1;
+
+// Bitwarden's default for new exports
+const EXPORT_PBKDF2_ITERATIONS: u32 = 600_000;
+
+// Limits on the key derivation settings read from an export, so a crafted
+// file can't make opening it use all memory or run for hours. Bitwarden's own
+// maximums (2,000,000 PBKDF2 iterations; Argon2 1 GiB, 10 iterations, 16 lanes)
+// are well inside these.
+const MAX_PBKDF2_ITERATIONS: u32 = 10_000_000;
+const MAX_ARGON2_MEMORY_MIB: u32 = 2048;
+const MAX_ARGON2_ITERATIONS: u32 = 100;
+const MAX_ARGON2_PARALLELISM: u32 = 64;
+
+// Saltr entry types Bitwarden has no item type for are exported as logins with this field
+const TYPE_FIELD: &str = "Saltr Type";
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct EncryptedExport {
+    encrypted: bool,
+    password_protected: bool,
+    salt: String,
+    kdf_type: u32,
+    kdf_iterations: u32,
+    #[serde(default)]
+    kdf_memory: Option<u32>,
+    #[serde(default)]
+    kdf_parallelism: Option<u32>,
+    #[serde(rename = "encKeyValidation_DO_NOT_EDIT")]
+    enc_key_validation: String,
+    data: String,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+struct PlainExport {
+    #[serde(default)]
+    encrypted: bool,
+    #[serde(default)]
+    folders: Vec<Folder>,
+    #[serde(default)]
+    items: Vec<Item>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Folder {
+    id: String,
+    name: String,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct Item {
+    #[serde(default)]
+    password_history: Option<Vec<HistoryItem>>,
+    #[serde(default)]
+    revision_date: Option<String>,
+    #[serde(default)]
+    creation_date: Option<String>,
+    #[serde(default)]
+    deleted_date: Option<String>,
+    #[serde(default)]
+    id: String,
+    #[serde(default)]
+    organization_id: Option<String>,
+    #[serde(default)]
+    folder_id: Option<String>,
+    #[serde(rename = "type")]
+    item_type: u32,
+    #[serde(default)]
+    reprompt: u32,
+    #[serde(default)]
+    name: String,
+    #[serde(default)]
+    notes: Option<String>,
+    #[serde(default)]
+    favorite: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    fields: Option<Vec<Field>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    login: Option<Login>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    secure_note: Option<SecureNote>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    card: Option<Card>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    identity: Option<Identity>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    ssh_key: Option<SshKey>,
+    #[serde(default)]
+    collection_ids: Option<Vec<String>>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct HistoryItem {
+    last_used_date: String,
+    password: String,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Field {
+    #[serde(default)]
+    name: Option<String>,
+    #[serde(default)]
+    value: Option<String>,
+    #[serde(rename = "type", default)]
+    field_type: u32,
+    #[serde(default)]
+    linked_id: Option<u32>,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct Login {
+    #[serde(default)]
+    fido2_credentials: Option<Vec<serde_json::Value>>,
+    #[serde(default)]
+    uris: Option<Vec<Uri>>,
+    #[serde(default)]
+    username: Option<String>,
+    #[serde(default)]
+    password: Option<String>,
+    #[serde(default)]
+    totp: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Uri {
+    #[serde(rename = "match", default)]
+    match_type: Option<u32>,
+    #[serde(default)]
+    uri: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SecureNote {
+    #[serde(rename = "type", default)]
+    note_type: u32,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct Card {
+    #[serde(default)]
+    cardholder_name: Option<String>,
+    #[serde(default)]
+    brand: Option<String>,
+    #[serde(default)]
+    number: Option<String>,
+    #[serde(default)]
+    exp_month: Option<String>,
+    #[serde(default)]
+    exp_year: Option<String>,
+    #[serde(default)]
+    code: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct Identity {
+    #[serde(default)]
+    title: Option<String>,
+    #[serde(default)]
+    first_name: Option<String>,
+    #[serde(default)]
+    middle_name: Option<String>,
+    #[serde(default)]
+    last_name: Option<String>,
+    #[serde(default)]
+    address1: Option<String>,
+    #[serde(default)]
+    address2: Option<String>,
+    #[serde(default)]
+    address3: Option<String>,
+    #[serde(default)]
+    city: Option<String>,
+    #[serde(default)]
+    state: Option<String>,
+    #[serde(default)]
+    postal_code: Option<String>,
+    #[serde(default)]
+    country: Option<String>,
+    #[serde(default)]
+    company: Option<String>,
+    #[serde(default)]
+    email: Option<String>,
+    #[serde(default)]
+    phone: Option<String>,
+    #[serde(default)]
+    ssn: Option<String>,
+    #[serde(default)]
+    username: Option<String>,
+    #[serde(default)]
+    passport_number: Option<String>,
+    #[serde(default)]
+    license_number: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct SshKey {
+    #[serde(default)]
+    private_key: Option<String>,
+    #[serde(default)]
+    public_key: Option<String>,
+    #[serde(default)]
+    key_fingerprint: Option<String>,
+}
+
+// ---------------------------------------------------------------------------
+// Import
+// ---------------------------------------------------------------------------
+
+// Reads a Bitwarden JSON export. `password` is only needed for password-protected
+// exports. Items Bitwarden had in its trash are returned as trash.
+pub fn read_export(path: &str, password: &str) -> Result<PasswordDatabase, String> {
+    let contents = std::fs::read_to_string(path.trim()).map_err(|e| format!("Could not open export: {}", e))?;
+    let json: serde_json::Value =
+        serde_json::from_str(&contents).map_err(|e| format!("Not a Bitwarden JSON export: {}", e))?;
+
+    let plain: PlainExport = if json.get("encrypted").and_then(|v| v.as_bool()) == Some(true) {
+        if json.get("passwordProtected").and_then(|v| v.as_bool()) != Some(true) {
+            return Err(
+                "This export is encrypted with a Bitwarden account key; export it again with a file password"
+                    .to_string(),
+            );
+        }
+        let export: EncryptedExport =
+            serde_json::from_value(json).map_err(|e| format!("Invalid encrypted export: {}", e))?;
+        let data = decrypt_export(&export, password)?;
+        serde_json::from_slice(&data).map_err(|e| format!("Invalid export contents: {}", e))?
+    } else {
+        serde_json::from_value(json).map_err(|e| format!("Invalid Bitwarden export: {}", e))?
+    };
+
+    let folders: BTreeMap<&str, &str> = plain.folders.iter().map(|f| (f.id.as_str(), f.name.as_str())).collect();
+    let mut database = PasswordDatabase::default();
+    for item in &plain.items {
+        let folder = item.folder_id.as_deref().and_then(|id| folders.get(id)).unwrap_or(&"");
+        let entry = entry_from_item(item, folder);
+        match &item.deleted_date {
+            Some(deleted) => database.trash.push(TrashedEntry {
+                entry,
+                deleted_at: decode_time(deleted).unwrap_or_else(st_json::timestamp_now),
+            }),
+            None => database.passwords.push(entry),
+        }
+    }
+    Ok(database)
+}
+
+fn entry_from_item(item: &Item, folder: &str) -> PasswordDetails {
+    let text = |value: &Option<String>| value.clone().unwrap_or_default();
+    let name = if item.name.trim().is_empty() { "(untitled)" } else { item.name.as_str() };
+
+    let mut entry = st_json::create_password_details(name, "", "", "", &text(&item.notes));
+    entry.folder = folder.to_string();
+    if let Some(created) = item.creation_date.as_deref().and_then(decode_time) {
+        entry.created_at = created;
+    }
+    entry.updated_at = item.revision_date.as_deref().and_then(decode_time).unwrap_or_default();
+
+    // Lines that end up in the notes because Saltr has no field for them
+    let mut extra: Vec<String> = Vec::new();
+
+    for field in item.fields.iter().flatten() {
+        let name = text(&field.name);
+        match field.field_type {
+            FIELD_LINKED => extra.push(format!("Linked field: {}", name)),
+            field_type => entry.custom_fields.push(CustomField {
+                name,
+                value: text(&field.value),
+                protected: field_type == FIELD_HIDDEN,
+            }),
+        }
+    }
+    entry.derivation = crate::derive::take_profile(&mut entry.custom_fields);
+    entry.rotation_days = st_json::take_rotation_days(&mut entry.custom_fields);
+
+    match item.item_type {
+        TYPE_LOGIN => {
+            let login = item.login.as_ref();
+            entry.username = login.map(|l| text(&l.username)).unwrap_or_default();
+            entry.value = login.map(|l| text(&l.password)).unwrap_or_default();
+
+            let mut uris = login
+                .and_then(|l| l.uris.as_ref())
+                .into_iter()
+                .flatten()
+                .filter_map(|u| u.uri.clone())
+                .filter(|u| !u.trim().is_empty());
+            entry.website = uris.next().unwrap_or_default();
+            for uri in uris {
+                extra.push(format!("Website: {}", uri));
+            }
+
+            let totp = login.map(|l| text(&l.totp)).unwrap_or_default();
+            if !totp.trim().is_empty() {
+                match otp::parse_otp_input(&totp) {
+                    Ok(config) => entry.otp = Some(config),
+                    Err(_) => extra.push(format!("One-time code secret: {}", totp)),
+                }
+            }
+            let passkeys = login.and_then(|l| l.fido2_credentials.as_ref()).map(Vec::len).unwrap_or(0);
+            if passkeys > 0 {
+                extra.push(format!("Had {} passkey(s) in Bitwarden, which could not be imported", passkeys));
+            }
+
+            // A Saltr type exported as a Bitwarden login comes back as that type
+            if let Some(position) = entry.custom_fields.iter().position(|f| f.name == TYPE_FIELD) {
+                let kind = entry.custom_fields.remove(position).value;
+                let mut take = |name: &str| match entry.custom_fields.iter().position(|f| f.name == name) {
+                    Some(position) => entry.custom_fields.remove(position).value,
+                    None => String::new(),
+                };
+                if kind == "api_key" {
+                    entry.kind = EntryKind::ApiKey { expires_at: take("Expires") };
+                } else if kind == "wifi" {
+                    entry.kind = EntryKind::Wifi {
+                        ssid: take("SSID"),
+                        security: crate::wifi::WifiSecurity::from_name(&take("Security")),
+                        hidden: take("Hidden") == "true",
+                    };
+                }
+            }
+        }
+        TYPE_SECURE_NOTE => entry.kind = EntryKind::SecureNote,
+        TYPE_CARD => {
+            let card = item.card.as_ref();
+            let month = card.map(|c| text(&c.exp_month)).unwrap_or_default();
+            let year = card.map(|c| text(&c.exp_year)).unwrap_or_default();
+            let expiry = match (month.trim(), year.trim()) {
+                ("", "") => String::new(),
+                (month, year) => format!(
+                    "{:0>2}/{}",
+                    month,
+                    year.get(year.len().saturating_sub(2)..).unwrap_or(year)
+                ),
+            };
+            entry.kind = EntryKind::Card {
+                cardholder: card.map(|c| text(&c.cardholder_name)).unwrap_or_default(),
+                number: card.map(|c| text(&c.number)).unwrap_or_default(),
+                expiry,
+                cvv: card.map(|c| text(&c.code)).unwrap_or_default(),
+            };
+            if let Some(brand) = card.and_then(|c| c.brand.clone()).filter(|b| !b.is_empty()) {
+                extra.push(format!("Brand: {}", brand));
+            }
+        }
+        TYPE_IDENTITY => {
+            let identity = item.identity.as_ref();
+            let get = |f: fn(&Identity) -> &Option<String>| {
+                identity.and_then(|i| f(i).clone()).unwrap_or_default().trim().to_string()
+            };
+            let join = |parts: &[String], separator: &str| {
+                parts.iter().filter(|p| !p.is_empty()).cloned().collect::<Vec<_>>().join(separator)
+            };
+
+            let full_name = join(
+                &[get(|i| &i.title), get(|i| &i.first_name), get(|i| &i.middle_name), get(|i| &i.last_name)],
+                " ",
+            );
+            let city_line = join(&[get(|i| &i.city), get(|i| &i.state), get(|i| &i.postal_code)], " ");
+            let address = join(
+                &[
+                    get(|i| &i.address1),
+                    get(|i| &i.address2),
+                    get(|i| &i.address3),
+                    city_line,
+                    get(|i| &i.country),
+                ],
+                ", ",
+            );
+            entry.kind = EntryKind::Identity {
+                full_name,
+                address,
+                phone: get(|i| &i.phone),
+                email: get(|i| &i.email),
+            };
+
+            let others: [(&str, String); 5] = [
+                ("Company", get(|i| &i.company)),
+                ("Username", get(|i| &i.username)),
+                ("SSN", get(|i| &i.ssn)),
+                ("Passport number", get(|i| &i.passport_number)),
+                ("License number", get(|i| &i.license_number)),
+            ];
+            for (label, value) in others {
+                if !value.is_empty() {
+                    extra.push(format!("{}: {}", label, value));
+                }
+            }
+        }
+        TYPE_SSH_KEY => {
+            // Stored as a secure note with the private key as a hidden field
+            entry.kind = EntryKind::SecureNote;
+            let key = item.ssh_key.as_ref();
+            if let Some(private_key) = key.and_then(|k| k.private_key.clone()) {
+                entry.custom_fields.push(CustomField { name: "Private Key".to_string(), value: private_key, protected: true });
+            }
+            if let Some(public_key) = key.and_then(|k| k.public_key.clone()) {
+                extra.push(format!("SSH public key: {}", public_key));
+            }
+            if let Some(fingerprint) = key.and_then(|k| k.key_fingerprint.clone()) {
+                extra.push(format!("SSH key fingerprint: {}", fingerprint));
+            }
+        }
+        other => {
+            entry.kind = EntryKind::SecureNote;
+            extra.push(format!("Unsupported Bitwarden item type {}", other));
+        }
+    }
+
+    if !extra.is_empty() {
+        if !entry.notes.is_empty() {
+            entry.notes.push_str("\n\n");
+        }
+        entry.notes.push_str("Imported from Bitwarden:\n");
+        entry.notes.push_str(&extra.join("\n"));
+    }
+
+    // Bitwarden lists previous passwords newest first, like Saltr
+    let mut history: Vec<&HistoryItem> = item.password_history.iter().flatten().collect();
+    history.sort_by(|a, b| b.last_used_date.cmp(&a.last_used_date));
+    entry.history = history
+        .into_iter()
+        .map(|old| PasswordHistoryEntry {
+            sealed: String::new(),
+            value: old.password.clone(),
+            changed_at: decode_time(&old.last_used_date).unwrap_or_default(),
+        })
+        .collect();
+
+    entry
+}
+
+// "2024-01-31T12:00:00.000Z" -> vault timestamp
+fn decode_time(text: &str) -> Option<String> {
+    chrono::DateTime::parse_from_rfc3339(text.trim())
+        .ok()
+        .map(|time| time.naive_utc().format(TIMESTAMP_FORMAT).to_string())
+}
+
+// Vault timestamp -> "2024-01-31T12:00:00.000Z"
+fn encode_time(timestamp: &str) -> String {
+    let time = chrono::NaiveDateTime::parse_from_str(timestamp, TIMESTAMP_FORMAT)
+        .unwrap_or_else(|_| chrono::Utc::now().naive_utc());
+    time.format("%Y-%m-%dT%H:%M:%S%.3fZ").to_string()
+}
+
+// ---------------------------------------------------------------------------
+// Export
+// ---------------------------------------------------------------------------
+
+// Writes the vault as a Bitwarden JSON export. With a password the file is the
+// password-protected variant (PBKDF2-SHA256, 600,000 iterations). Trash is left out.
+pub fn write_export(database: &PasswordDatabase, path: &str, password: &str) -> Result<(), String> {
+    let plain = export_from_database(database);
+    let json = if password.is_empty() {
+        serde_json::to_string_pretty(&plain)
+    } else {
+        let data = serde_json::to_string(&plain).map_err(|e| format!("Could not build export: {}", e))?;
+        serde_json::to_string_pretty(&encrypt_export(data.as_bytes(), password)?)
+    }
+    .map_err(|e| format!("Could not build export: {}", e))?;
+
+    std::fs::write(path.trim(), json).map_err(|e| format!("Could not write export: {}", e))
+}
+
+fn new_uuid() -> String {
+    let mut bytes = [0u8; 16];
+    rand::rng().fill_bytes(&mut bytes);
+    // Version 4, RFC 4122 variant
+    bytes[6] = (bytes[6] & 0x0f) | 0x40;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+    let hex: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+    format!("{}-{}-{}-{}-{}", &hex[0..8], &hex[8..12], &hex[12..16], &hex[16..20], &hex[20..32])
+}
+
+fn export_from_database(database: &PasswordDatabase) -> PlainExport {
+    let mut folder_ids: BTreeMap<&str, String> = BTreeMap::new();
+    for entry in &database.passwords {
+        if !entry.folder.is_empty() {
+            folder_ids.entry(entry.folder.as_str()).or_insert_with(new_uuid);
+        }
+    }
+
+    let items = database
+        .passwords
+        .iter()
+        .map(|entry| item_from_entry(entry, folder_ids.get(entry.folder.as_str()).cloned()))
+        .collect();
+    let folders = folder_ids
+        .iter()
+        .map(|(name, id)| Folder { id: id.clone(), name: name.to_string() })
+        .collect();
+
+    PlainExport { encrypted: false, folders, items }
+}
+
+fn item_from_entry(entry: &PasswordDetails, folder_id: Option<String>) -> Item {
+    let optional = |value: &str| if value.is_empty() { None } else { Some(value.to_string()) };
+    let field = |name: &str, value: &str, field_type: u32| Field {
+        name: Some(name.to_string()),
+        value: Some(value.to_string()),
+        field_type,
+        linked_id: None,
+    };
+
+    let mut fields: Vec<Field> = entry
+        .custom_fields
+        .iter()
+        .map(|f| field(&f.name, &f.value, if f.protected { FIELD_HIDDEN } else { FIELD_TEXT }))
+        .collect();
+    if let Some(profile) = &entry.derivation {
+        let profile = crate::derive::profile_field(profile);
+        fields.push(field(&profile.name, &profile.value, FIELD_TEXT));
+    }
+    if entry.rotation_days > 0 {
+        let rotation = st_json::rotation_field(entry.rotation_days);
+        fields.push(field(&rotation.name, &rotation.value, FIELD_TEXT));
+    }
+
+    let mut item = Item {
+        revision_date: Some(encode_time(if entry.updated_at.is_empty() { &entry.created_at } else { &entry.updated_at })),
+        creation_date: Some(encode_time(&entry.created_at)),
+        id: new_uuid(),
+        folder_id,
+        name: entry.name.clone(),
+        notes: optional(&entry.notes),
+        ..Item::default()
+    };
+
+    let login = || Login {
+        fido2_credentials: Some(Vec::new()),
+        uris: if entry.website.is_empty() {
+            None
+        } else {
+            Some(vec![Uri { match_type: None, uri: Some(entry.website.clone()) }])
+        },
+        username: optional(&entry.username),
+        password: optional(&entry.value),
+        totp: entry.otp.as_ref().map(otp::to_otpauth_uri),
+    };
+
+    match &entry.kind {
+        EntryKind::Login => {
+            item.item_type = TYPE_LOGIN;
+            item.login = Some(login());
+        }
+        EntryKind::ApiKey { expires_at } => {
+            item.item_type = TYPE_LOGIN;
+            item.login = Some(login());
+            fields.push(field(TYPE_FIELD, "api_key", FIELD_TEXT));
+            fields.push(field("Expires", expires_at, FIELD_TEXT));
+        }
+        EntryKind::Wifi { ssid, security, hidden } => {
+            item.item_type = TYPE_LOGIN;
+            item.login = Some(login());
+            fields.push(field(TYPE_FIELD, "wifi", FIELD_TEXT));
+            fields.push(field("SSID", ssid, FIELD_TEXT));
+            fields.push(field("Security", security.name(), FIELD_TEXT));
+            fields.push(field("Hidden", if *hidden { "true" } else { "false" }, FIELD_TEXT));
+        }
+        EntryKind::SecureNote => {
+            item.item_type = TYPE_SECURE_NOTE;
+            item.secure_note = Some(SecureNote { note_type: 0 });
+        }
+        EntryKind::Card { cardholder, number, expiry, cvv } => {
+            item.item_type = TYPE_CARD;
+            let (month, year) = expiry.split_once('/').unwrap_or(("", ""));
+            item.card = Some(Card {
+                cardholder_name: optional(cardholder),
+                brand: None,
+                number: optional(number),
+                exp_month: optional(month.trim().trim_start_matches('0')),
+                exp_year: optional(&format!("20{}", year.trim())).filter(|_| !year.trim().is_empty()),
+                code: optional(cvv),
+            });
+        }
+        EntryKind::Identity { full_name, address, phone, email } => {
+            item.item_type = TYPE_IDENTITY;
+            // Bitwarden splits names; the last word is taken as the last name
+            let (first, last) = match full_name.trim().rsplit_once(' ') {
+                Some((first, last)) => (first.to_string(), last.to_string()),
+                None => (full_name.trim().to_string(), String::new()),
+            };
+            item.identity = Some(Identity {
+                first_name: optional(&first),
+                last_name: optional(&last),
+                address1: optional(address),
+                phone: optional(phone),
+                email: optional(email),
+                ..Identity::default()
+            });
+        }
+    }
+
+    // Types without a login can't hold a one-time code; keep it as a hidden field
+    if item.login.is_none() && let Some(config) = &entry.otp {
+        fields.push(field("One-time code", &otp::to_otpauth_uri(config), FIELD_HIDDEN));
+    }
+    if !fields.is_empty() {
+        item.fields = Some(fields);
+    }
+    if !entry.history.is_empty() {
+        item.password_history = Some(
+            entry
+                .history
+                .iter()
+                .map(|old| HistoryItem { last_used_date: encode_time(&old.changed_at), password: old.value.clone() })
+                .collect(),
+        );
+    }
+    item
+}
+
+// ---------------------------------------------------------------------------
+// Password-protected exports
+// ---------------------------------------------------------------------------
+
+struct Keys {
+    encryption: [u8; 32],
+    mac: [u8; 32],
+}
+
+fn derive_keys(password: &str, salt: &str, kdf_type: u32, iterations: u32, memory_mib: u32, parallelism: u32) -> Result<Keys, String> {
+    let mut key = [0u8; 32];
+    match kdf_type {
+        KDF_PBKDF2 => {
+            if iterations > MAX_PBKDF2_ITERATIONS {
+                return Err(format!("Export asks for {} PBKDF2 iterations; the limit is {}", iterations, MAX_PBKDF2_ITERATIONS));
+            }
+            pbkdf2::pbkdf2_hmac::<Sha256>(password.as_bytes(), salt.as_bytes(), iterations, &mut key);
+        }
+        KDF_ARGON2ID => {
+            if memory_mib > MAX_ARGON2_MEMORY_MIB {
+                return Err(format!("Export asks for {} MiB of memory to open; the limit is {} MiB", memory_mib, MAX_ARGON2_MEMORY_MIB));
+            }
+            if iterations > MAX_ARGON2_ITERATIONS {
+                return Err(format!("Export asks for {} Argon2 iterations; the limit is {}", iterations, MAX_ARGON2_ITERATIONS));
+            }
+            if parallelism > MAX_ARGON2_PARALLELISM {
+                return Err(format!("Export asks for {} Argon2 lanes; the limit is {}", parallelism, MAX_ARGON2_PARALLELISM));
+            }
+            // Bitwarden hashes the salt first for Argon2
+            let salt = Sha256::digest(salt.as_bytes());
+            let params = argon2::Params::new(memory_mib * 1024, iterations, parallelism, Some(32))
+                .map_err(|e| format!("Invalid Argon2 settings: {}", e))?;
+            argon2::Argon2::new(argon2::Algorithm::Argon2id, argon2::Version::V0x13, params)
+                .hash_password_into(password.as_bytes(), &salt, &mut key)
+                .map_err(|e| format!("Key derivation failed: {}", e))?;
+        }
+        other => return Err(format!("Unsupported key derivation type {}", other)),
+    }
+
+    // Stretch into separate encryption and MAC keys (HKDF-Expand only)
+    let hkdf = hkdf::Hkdf::<Sha256>::from_prk(&key).map_err(|_| "Invalid derived key".to_string())?;
+    let mut keys = Keys { encryption: [0u8; 32], mac: [0u8; 32] };
+    hkdf.expand(b"enc", &mut keys.encryption).map_err(|_| "Invalid derived key".to_string())?;
+    hkdf.expand(b"mac", &mut keys.mac).map_err(|_| "Invalid derived key".to_string())?;
+    Ok(keys)
+}
+
+fn decrypt_export(export: &EncryptedExport, password: &str) -> Result<Vec<u8>, String> {
+    let keys = derive_keys(
+        password,
+        &export.salt,
+        export.kdf_type,
+        export.kdf_iterations,
+        export.kdf_memory.unwrap_or(64),
+        export.kdf_parallelism.unwrap_or(4),
+    )?;
+    decrypt_enc_string(&export.enc_key_validation, &keys).map_err(|_| "Wrong password for this export".to_string())?;
+    decrypt_enc_string(&export.data, &keys)
+}
+
+fn encrypt_export(data: &[u8], password: &str) -> Result<EncryptedExport, String> {
+    let mut salt = [0u8; 16];
+    rand::rng().fill_bytes(&mut salt);
+    let salt = BASE64.encode(salt);
+    let keys = derive_keys(password, &salt, KDF_PBKDF2, EXPORT_PBKDF2_ITERATIONS, 0, 0)?;
+
+    Ok(EncryptedExport {
+        encrypted: true,
+        password_protected: true,
+        salt,
+        kdf_type: KDF_PBKDF2,
+        kdf_iterations: EXPORT_PBKDF2_ITERATIONS,
+        kdf_memory: None,
+        kdf_parallelism: None,
+        enc_key_validation: encrypt_enc_string(new_uuid().as_bytes(), &keys),
+        data: encrypt_enc_string(data, &keys),
+    })
+}
+
+// "2.<iv>|<ciphertext>|<mac>" (AES-256-CBC + HMAC-SHA256, all base64)
+fn decrypt_enc_string(enc_string: &str, keys: &Keys) -> Result<Vec<u8>, String> {
+    use cbc::cipher::{BlockDecryptMut, KeyIvInit, block_padding::Pkcs7};
+
+    let body = enc_string
+        .strip_prefix("2.")
+        .ok_or_else(|| "Unsupported encryption type in export".to_string())?;
+    let parts: Vec<Vec<u8>> = body
+        .split('|')
+        .map(|part| BASE64.decode(part))
+        .collect::<Result<_, _>>()
+        .map_err(|_| "Invalid encrypted data in export".to_string())?;
+    let [iv, ciphertext, tag] = parts.as_slice() else {
+        return Err("Invalid encrypted data in export".to_string());
+    };
+
+    let mut mac = Hmac::<Sha256>::new_from_slice(&keys.mac).expect("HMAC accepts any key length");
+    mac.update(iv);
+    mac.update(ciphertext);
+    mac.verify_slice(tag).map_err(|_| "Wrong password, or the export is corrupted".to_string())?;
+
+    cbc::Decryptor::<aes::Aes256>::new_from_slices(&keys.encryption, iv)
+        .map_err(|_| "Invalid encrypted data in export".to_string())?
+        .decrypt_padded_vec_mut::<Pkcs7>(ciphertext)
+        .map_err(|_| "Wrong password, or the export is corrupted".to_string())
+}
+
+fn encrypt_enc_string(data: &[u8], keys: &Keys) -> String {
+    use cbc::cipher::{BlockEncryptMut, KeyIvInit, block_padding::Pkcs7};
+
+    let mut iv = [0u8; 16];
+    rand::rng().fill_bytes(&mut iv);
+    let ciphertext = cbc::Encryptor::<aes::Aes256>::new(&keys.encryption.into(), &iv.into())
+        .encrypt_padded_vec_mut::<Pkcs7>(data);
+
+    let mut mac = Hmac::<Sha256>::new_from_slice(&keys.mac).expect("HMAC accepts any key length");
+    mac.update(&iv);
+    mac.update(&ciphertext);
+    format!(
+        "2.{}|{}|{}",
+        BASE64.encode(iv),
+        BASE64.encode(&ciphertext),
+        BASE64.encode(mac.finalize().into_bytes())
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn written_export_reads_back() {
+        let mut login = st_json::create_password_details("Mail", "hunter2-correct", "mail.example.com", "me@example.com", "");
+        login.custom_fields.push(CustomField { name: "Recovery".to_string(), value: "1234 5678".to_string(), protected: true });
+        login.derivation = Some(crate::derive::DerivationProfile {
+            site: "mail.example.com".to_string(),
+            login: "me@example.com".to_string(),
+            counter: 3,
+            ..Default::default()
+        });
+        login.rotation_days = 90;
+        let mut key = st_json::create_password_details("Deploy key", "sk-123", "", "", "");
+        key.kind = EntryKind::ApiKey { expires_at: "2030-01-01".to_string() };
+        let database = PasswordDatabase { passwords: vec![login, key], trash: Vec::new(), history_lock: None };
+
+        for password in ["", "correct horse"] {
+            let path = std::env::temp_dir().join(format!("saltr-bitwarden-test-{}-{}.json", std::process::id(), password.len()));
+            let path = path.to_string_lossy().to_string();
+            write_export(&database, &path, password).unwrap();
+            let read_back = read_export(&path, password);
+            std::fs::remove_file(&path).unwrap();
+
+            let read_back = read_back.unwrap();
+            assert_eq!(read_back.passwords.len(), database.passwords.len());
+            for original in &database.passwords {
+                let entry = read_back.passwords.iter().find(|p| p.name == original.name).unwrap();
+                assert_eq!(entry.value, original.value, "{}", original.name);
+                assert_eq!(entry.kind, original.kind, "{}", original.name);
+                assert_eq!(entry.custom_fields, original.custom_fields, "{}", original.name);
+                assert_eq!(entry.derivation, original.derivation, "{}", original.name);
+                assert_eq!(entry.rotation_days, original.rotation_days, "{}", original.name);
+            }
+        }
+    }
+
+    #[test]
+    fn oversized_key_derivation_settings_are_refused() {
+        let derive = |kdf_type, iterations, memory_mib, parallelism| {
+            derive_keys("password", "salt", kdf_type, iterations, memory_mib, parallelism).err().unwrap_or_default()
+        };
+        assert!(derive(KDF_PBKDF2, MAX_PBKDF2_ITERATIONS + 1, 0, 0).contains("iterations"));
+        assert!(derive(KDF_ARGON2ID, 3, MAX_ARGON2_MEMORY_MIB + 1, 4).contains("memory"));
+        assert!(derive(KDF_ARGON2ID, MAX_ARGON2_ITERATIONS + 1, 64, 4).contains("iterations"));
+        assert!(derive(KDF_ARGON2ID, 3, 64, MAX_ARGON2_PARALLELISM + 1).contains("lanes"));
+    }
+}
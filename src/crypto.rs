@@ -0,0 +1,62 @@
+// crypto.rs - Password-based encryption used by Saltr's own file formats.
+// Keys come from Argon2id; data is sealed with ChaCha20-Poly1305, which also
+// authenticates it (a wrong password or any tampering fails to decrypt).
+use chacha20poly1305::aead::{Aead, KeyInit, Payload};
+use chacha20poly1305::{ChaCha20Poly1305, Nonce};
+use rand::RngCore;
+
+pub const KEY_LEN: usize = 32;
+pub const SALT_LEN: usize = 16;
+pub const NONCE_LEN: usize = 12;
+
+// Argon2id cost settings; stored alongside the data so they can change later
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KdfParams {
+    pub memory_kib: u32,
+    pub iterations: u32,
+    pub parallelism: u32,
+}
+
+impl KdfParams {
+    // 64 MiB, 3 passes, 4 lanes
+    pub const DEFAULT: KdfParams = KdfParams { memory_kib: 64 * 1024, iterations: 3, parallelism: 4 };
+}
+
+pub fn random_bytes<const N: usize>() -> [u8; N] {
+    let mut bytes = [0u8; N];
+    rand::rng().fill_bytes(&mut bytes);
+    bytes
+}
+
+pub fn derive_key(password: &str, salt: &[u8], params: KdfParams) -> Result<[u8; KEY_LEN], String> {
+    let argon2_params = argon2::Params::new(params.memory_kib, params.iterations, params.parallelism, Some(KEY_LEN))
+        .map_err(|e| format!("Invalid key derivation settings: {}", e))?;
+    let mut key = [0u8; KEY_LEN];
+    argon2::Argon2::new(argon2::Algorithm::Argon2id, argon2::Version::V0x13, argon2_params)
+        .hash_password_into(password.as_bytes(), salt, &mut key)
+        .map_err(|e| format!("Key derivation failed: {}", e))?;
+    Ok(key)
+}
+
+// Returns nonce + ciphertext. `associated_data` isn't encrypted but is
+// authenticated, so it can't be changed without decryption failing.
+pub fn encrypt(key: &[u8; KEY_LEN], plaintext: &[u8], associated_data: &[u8]) -> Vec<u8> {
+    let nonce = random_bytes::<NONCE_LEN>();
+    let ciphertext = ChaCha20Poly1305::new(key.into())
+        .encrypt(Nonce::from_slice(&nonce), Payload { msg: plaintext, aad: associated_data })
+        .expect("ChaCha20-Poly1305 encryption cannot fail for in-memory data");
+
+    let mut out = nonce.to_vec();
+    out.extend_from_slice(&ciphertext);
+    out
+}
+
+pub fn decrypt(key: &[u8; KEY_LEN], data: &[u8], associated_data: &[u8]) -> Result<Vec<u8>, String> {
+    if data.len() < NONCE_LEN {
+        return Err("Encrypted data is truncated".to_string());
+    }
+    let (nonce, ciphertext) = data.split_at(NONCE_LEN);
+    ChaCha20Poly1305::new(key.into())
+        .decrypt(Nonce::from_slice(nonce), Payload { msg: ciphertext, aad: associated_data })
+        .map_err(|_| "Wrong password, or the data is corrupted".to_string())
+}
@@ -0,0 +1,236 @@
+// derive.rs - Stateless passwords, worked out from a master password and the
+// site, login and counter each time instead of being stored. Uses the LessPass
+// (v2) algorithm, so the same inputs give the same password as LessPass:
+//   entropy = PBKDF2-HMAC-SHA256(master, site + login + hex(counter), 100000, 32 bytes)
+// and the entropy, read as one big number, picks the characters.
+use serde::{Serialize, Deserialize};
+use sha2::Sha256;
+
+const ITERATIONS: u32 = 100_000;
+const ENTROPY_LEN: usize = 32;
+// LessPass's limits; 256 bits of entropy don't stretch much further
+pub const MIN_LENGTH: usize = 5;
+pub const MAX_LENGTH: usize = 35;
+
+const LOWERCASE: &str = "abcdefghijklmnopqrstuvwxyz";
+const UPPERCASE: &str = "ABCDEFGHIJKLMNOPQRSTUVWXYZ";
+const DIGITS: &str = "0123456789";
+const SYMBOLS: &str = "!\"#$%&'()*+,-./:;<=>?@[\\]^_`{|}~";
+
+// Everything needed to derive a password again, except the master password.
+// This is what gets saved in the entry instead of the password itself.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct DerivationProfile {
+    pub site: String,
+    pub login: String,
+    // Bump to get a new password for the same site and login
+    pub counter: u32,
+    pub length: usize,
+    pub lowercase: bool,
+    pub uppercase: bool,
+    pub digits: bool,
+    pub symbols: bool,
+}
+
+impl Default for DerivationProfile {
+    fn default() -> Self {
+        Self {
+            site: String::new(),
+            login: String::new(),
+            counter: 1,
+            length: 16,
+            lowercase: true,
+            uppercase: true,
+            digits: true,
+            symbols: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CharacterClass {
+    Lowercase,
+    Uppercase,
+    Digits,
+    Symbols,
+}
+
+impl CharacterClass {
+    pub const ALL: [CharacterClass; 4] = [
+        CharacterClass::Lowercase,
+        CharacterClass::Uppercase,
+        CharacterClass::Digits,
+        CharacterClass::Symbols,
+    ];
+
+    fn characters(self) -> &'static str {
+        match self {
+            CharacterClass::Lowercase => LOWERCASE,
+            CharacterClass::Uppercase => UPPERCASE,
+            CharacterClass::Digits => DIGITS,
+            CharacterClass::Symbols => SYMBOLS,
+        }
+    }
+}
+
+impl std::fmt::Display for CharacterClass {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            CharacterClass::Lowercase => "a-z",
+            CharacterClass::Uppercase => "A-Z",
+            CharacterClass::Digits => "0-9",
+            CharacterClass::Symbols => "%!@",
+        })
+    }
+}
+
+impl DerivationProfile {
+    pub fn uses(&self, class: CharacterClass) -> bool {
+        match class {
+            CharacterClass::Lowercase => self.lowercase,
+            CharacterClass::Uppercase => self.uppercase,
+            CharacterClass::Digits => self.digits,
+            CharacterClass::Symbols => self.symbols,
+        }
+    }
+
+    pub fn set_uses(&mut self, class: CharacterClass, used: bool) {
+        match class {
+            CharacterClass::Lowercase => self.lowercase = used,
+            CharacterClass::Uppercase => self.uppercase = used,
+            CharacterClass::Digits => self.digits = used,
+            CharacterClass::Symbols => self.symbols = used,
+        }
+    }
+
+    // Enabled classes, in the order LessPass uses them
+    fn classes(&self) -> Vec<CharacterClass> {
+        CharacterClass::ALL.into_iter().filter(|class| self.uses(*class)).collect()
+    }
+
+    // e.g. "example.org / contact@example.org, #1, 16 characters (a-z A-Z 0-9 %!@)"
+    pub fn describe(&self) -> String {
+        let classes: Vec<String> = self.classes().iter().map(|class| class.to_string()).collect();
+        format!(
+            "{} / {}, #{}, {} characters ({})",
+            self.site,
+            self.login,
+            self.counter,
+            self.length,
+            classes.join(" ")
+        )
+    }
+}
+
+// A big unsigned number as 32-bit limbs, most significant first. Only the
+// one operation the algorithm needs: divide by a small number, keep the remainder.
+struct Entropy(Vec<u32>);
+
+impl Entropy {
+    fn from_bytes(bytes: &[u8]) -> Self {
+        Entropy(
+            bytes
+                .chunks(4)
+                .map(|chunk| chunk.iter().fold(0u32, |limb, byte| (limb << 8) | *byte as u32))
+                .collect(),
+        )
+    }
+
+    // Divides in place by `divisor` and returns the remainder
+    fn divmod(&mut self, divisor: u32) -> u32 {
+        let mut remainder = 0u64;
+        for limb in self.0.iter_mut() {
+            let current = (remainder << 32) | *limb as u64;
+            *limb = (current / divisor as u64) as u32;
+            remainder = current % divisor as u64;
+        }
+        remainder as u32
+    }
+
+    fn pick(&mut self, characters: &[char]) -> char {
+        characters[self.divmod(characters.len() as u32) as usize]
+    }
+}
+
+// Works out the password for a profile. The master password is only used here
+// and never stored.
+pub fn derive_password(master_password: &str, profile: &DerivationProfile) -> Result<String, String> {
+    if master_password.is_empty() {
+        return Err("Enter your master password".to_string());
+    }
+    if profile.site.trim().is_empty() {
+        return Err("Enter the site to derive a password for".to_string());
+    }
+    let classes = profile.classes();
+    if classes.is_empty() {
+        return Err("Pick at least one kind of character".to_string());
+    }
+    if !(MIN_LENGTH..=MAX_LENGTH).contains(&profile.length) {
+        return Err(format!("Length must be between {} and {}", MIN_LENGTH, MAX_LENGTH));
+    }
+    if profile.counter == 0 {
+        return Err("Counter must be 1 or more".to_string());
+    }
+
+    let salt = format!("{}{}{:x}", profile.site, profile.login, profile.counter);
+    let mut entropy = [0u8; ENTROPY_LEN];
+    pbkdf2::pbkdf2_hmac::<Sha256>(master_password.as_bytes(), salt.as_bytes(), ITERATIONS, &mut entropy);
+    let mut entropy = Entropy::from_bytes(&entropy);
+
+    // Fill all but one character per class from every enabled class together...
+    let all_characters: Vec<char> = classes.iter().flat_map(|class| class.characters().chars()).collect();
+    let mut password: Vec<char> = (0..profile.length - classes.len())
+        .map(|_| entropy.pick(&all_characters))
+        .collect();
+
+    // ...then make sure each class shows up by adding one of each at a
+    // position picked from what's left of the entropy
+    let one_per_class: Vec<char> = classes
+        .iter()
+        .map(|class| entropy.pick(&class.characters().chars().collect::<Vec<char>>()))
+        .collect();
+    for c in one_per_class {
+        let position = entropy.divmod(password.len() as u32) as usize;
+        password.insert(position, c);
+    }
+    Ok(password.into_iter().collect())
+}
+
+// KeePass and Bitwarden exports keep the profile in a custom field with this
+// name, as JSON, so importing the file gives back a derived entry
+pub const PROFILE_FIELD: &str = "Saltr Derivation";
+
+pub fn profile_field(profile: &DerivationProfile) -> crate::st_json::CustomField {
+    crate::st_json::CustomField {
+        name: PROFILE_FIELD.to_string(),
+        value: serde_json::to_string(profile).unwrap_or_default(),
+        protected: false,
+    }
+}
+
+// Takes the profile field back out of imported custom fields. One that can't be
+// read is left as an ordinary field so nothing is lost.
+pub fn take_profile(fields: &mut Vec<crate::st_json::CustomField>) -> Option<DerivationProfile> {
+    let position = fields.iter().position(|f| f.name == PROFILE_FIELD)?;
+    let profile = serde_json::from_str(&fields[position].value).ok()?;
+    fields.remove(position);
+    Some(profile)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_lesspass() {
+        // The example from LessPass's own tests
+        let profile = DerivationProfile {
+            site: "example.org".to_string(),
+            login: "contact@example.org".to_string(),
+            counter: 1,
+            length: 16,
+            ..Default::default()
+        };
+        assert_eq!(derive_password("password", &profile).unwrap(), "WHLpUL)e00[iHR+w");
+    }
+}
@@ -0,0 +1,270 @@
+// dice.rs - Passwords from physical dice rolls or card draws instead of the
+// computer's random number generator, for master passwords and other secrets
+// where you'd rather not trust the OS.
+//
+// A d6 has 6 sides and a word list has 2048 words, so rolls can't be mapped to
+// words one by one without favouring some of them. Instead every roll is added
+// to one big number (base 6 or 52), and a word is only taken out of it when the
+// number's range is a whole multiple of the word list; the few values past the
+// last whole multiple are thrown away and more rolls are asked for. Each word or
+// character therefore comes out exactly as likely as any other.
+use crate::genr;
+
+// Words from genr's word list, or printable ASCII characters
+const CHARACTERS: &str = "!\"#$%&'()*+,-./0123456789:;<=>?@ABCDEFGHIJKLMNOPQRSTUVWXYZ[\\]^_`abcdefghijklmnopqrstuvwxyz{|}~";
+const WORD_SEPARATOR: &str = "-";
+pub const MAX_LENGTH: usize = 64;
+
+const CARD_RANKS: [&str; 13] = ["A", "2", "3", "4", "5", "6", "7", "8", "9", "10", "J", "Q", "K"];
+const CARD_SUITS: [char; 4] = ['S', 'H', 'D', 'C'];
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DiceSource {
+    // Six-sided dice, entered as digits 1-6
+    #[default]
+    Dice,
+    // A full 52-card deck, shuffled again before every draw, entered like "AS 10H QD"
+    Cards,
+}
+
+impl DiceSource {
+    pub const ALL: [DiceSource; 2] = [DiceSource::Dice, DiceSource::Cards];
+
+    fn sides(self) -> u64 {
+        match self {
+            DiceSource::Dice => 6,
+            DiceSource::Cards => 52,
+        }
+    }
+
+    // The value (from 0) of every roll or card in the text
+    fn parse(self, input: &str) -> Result<Vec<u64>, String> {
+        match self {
+            DiceSource::Dice => input
+                .chars()
+                .filter(|c| !c.is_whitespace() && *c != ',')
+                .map(|c| match c.to_digit(10) {
+                    Some(roll @ 1..=6) => Ok(roll as u64 - 1),
+                    _ => Err(format!("'{}' is not a die roll; enter the numbers 1 to 6", c)),
+                })
+                .collect(),
+            DiceSource::Cards => input
+                .split(|c: char| c.is_whitespace() || c == ',')
+                .filter(|card| !card.is_empty())
+                .map(parse_card)
+                .collect(),
+        }
+    }
+}
+
+impl std::fmt::Display for DiceSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            DiceSource::Dice => "Dice (d6)",
+            DiceSource::Cards => "Playing cards",
+        })
+    }
+}
+
+// "AS", "10h", "Td" or "Q♥" -> 0..52
+fn parse_card(card: &str) -> Result<u64, String> {
+    let error = || format!("'{}' is not a card; write the rank then the suit, e.g. AS, 10H or QD", card);
+    let mut upper = card.to_uppercase();
+    let suit = upper.pop().ok_or_else(error)?;
+    let suit = match suit {
+        '♠' => 'S',
+        '♥' => 'H',
+        '♦' => 'D',
+        '♣' => 'C',
+        other => other,
+    };
+    let rank = if upper == "T" { "10" } else { upper.as_str() };
+    let rank = CARD_RANKS.iter().position(|r| *r == rank).ok_or_else(error)?;
+    let suit = CARD_SUITS.iter().position(|s| *s == suit).ok_or_else(error)?;
+    Ok((rank * CARD_SUITS.len() + suit) as u64)
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DiceOutput {
+    #[default]
+    Words,
+    Characters,
+}
+
+impl DiceOutput {
+    pub const ALL: [DiceOutput; 2] = [DiceOutput::Words, DiceOutput::Characters];
+
+    pub fn default_length(self) -> usize {
+        match self {
+            DiceOutput::Words => 6,
+            DiceOutput::Characters => 20,
+        }
+    }
+
+    fn choices(self) -> u64 {
+        match self {
+            DiceOutput::Words => genr::wordlist().len() as u64,
+            DiceOutput::Characters => CHARACTERS.len() as u64,
+        }
+    }
+}
+
+impl std::fmt::Display for DiceOutput {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            DiceOutput::Words => "Words",
+            DiceOutput::Characters => "Characters",
+        })
+    }
+}
+
+// The rolls so far as one number: `value` is uniform over 0..`range`
+struct Accumulator {
+    value: u64,
+    range: u64,
+}
+
+impl Accumulator {
+    fn add(&mut self, roll: u64, sides: u64) {
+        self.value = self.value * sides + roll;
+        self.range *= sides;
+    }
+
+    // A uniform number below `choices`, or None if the rolls so far can't give
+    // one without bias. Either way what's left stays uniform for the next try.
+    fn take(&mut self, choices: u64) -> Option<u64> {
+        if self.range < choices {
+            return None;
+        }
+        let usable = self.range - self.range % choices;
+        if self.value < usable {
+            let picked = self.value % choices;
+            self.value /= choices;
+            self.range = usable / choices;
+            Some(picked)
+        } else {
+            // Landed in the leftover part: keep it, it's still uniform over what's left
+            self.value -= usable;
+            self.range -= usable;
+            None
+        }
+    }
+}
+
+// How far the rolls entered so far get towards the password
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiceProgress {
+    // Only the words or characters finished so far
+    pub password: String,
+    pub produced: usize,
+    pub wanted: usize,
+    pub rolls: usize,
+    // Rolls after the password was finished, which weren't needed
+    pub unused_rolls: usize,
+    pub collected_bits: f64,
+    pub password_bits: f64,
+    // The fewest more rolls that could finish it; thrown away values can mean more
+    pub rolls_needed: usize,
+}
+
+impl DiceProgress {
+    pub fn is_complete(&self) -> bool {
+        self.produced == self.wanted
+    }
+}
+
+// Turns the rolls or cards typed in so far into as much of the password as they cover
+pub fn from_rolls(source: DiceSource, output: DiceOutput, length: usize, input: &str) -> Result<DiceProgress, String> {
+    if !(1..=MAX_LENGTH).contains(&length) {
+        return Err(format!("Length must be between 1 and {}", MAX_LENGTH));
+    }
+    let rolls = source.parse(input)?;
+    let sides = source.sides();
+    let choices = output.choices();
+
+    let mut accumulator = Accumulator { value: 0, range: 1 };
+    let mut picked = Vec::new();
+    let mut used = 0;
+    for roll in &rolls {
+        if picked.len() == length {
+            break;
+        }
+        accumulator.add(*roll, sides);
+        used += 1;
+        while picked.len() < length
+            && let Some(index) = accumulator.take(choices)
+        {
+            picked.push(index as usize);
+        }
+    }
+
+    let password = match output {
+        DiceOutput::Words => picked
+            .iter()
+            .map(|index| genr::wordlist()[*index])
+            .collect::<Vec<&str>>()
+            .join(WORD_SEPARATOR),
+        DiceOutput::Characters => picked.iter().map(|index| CHARACTERS.as_bytes()[*index] as char).collect(),
+    };
+    let bits_per_roll = (sides as f64).log2();
+    let bits_per_choice = (choices as f64).log2();
+    let missing_bits = (length - picked.len()) as f64 * bits_per_choice - (accumulator.range as f64).log2();
+    Ok(DiceProgress {
+        password,
+        produced: picked.len(),
+        wanted: length,
+        rolls: rolls.len(),
+        unused_rolls: rolls.len() - used,
+        collected_bits: used as f64 * bits_per_roll,
+        password_bits: picked.len() as f64 * bits_per_choice,
+        rolls_needed: if picked.len() == length { 0 } else { (missing_bits / bits_per_roll).ceil().max(1.0) as usize },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::genr::tests::{assert_uniform, seeded};
+    use rand::Rng;
+
+    #[test]
+    fn rolls_map_to_words_and_characters_without_bias() {
+        let mut rng = seeded(48);
+        for (source, output) in [
+            (DiceSource::Dice, DiceOutput::Words),
+            (DiceSource::Dice, DiceOutput::Characters),
+            (DiceSource::Cards, DiceOutput::Characters),
+        ] {
+            let mut counts = vec![0u64; output.choices() as usize];
+            let mut accumulator = Accumulator { value: 0, range: 1 };
+            let mut samples = 0;
+            while samples < counts.len() * 20 {
+                accumulator.add(rng.random_range(0..source.sides()), source.sides());
+                while let Some(index) = accumulator.take(output.choices()) {
+                    counts[index as usize] += 1;
+                    samples += 1;
+                }
+            }
+            assert_uniform(&counts, &format!("{} from {}", output, source));
+        }
+    }
+
+    #[test]
+    fn progress_follows_the_rolls() {
+        let progress = from_rolls(DiceSource::Dice, DiceOutput::Words, 2, "1 2 3").unwrap();
+        assert!(!progress.is_complete());
+        assert!(progress.rolls_needed >= 1);
+
+        let rolls = "3".repeat(40);
+        let progress = from_rolls(DiceSource::Dice, DiceOutput::Words, 2, &rolls).unwrap();
+        assert!(progress.is_complete());
+        assert_eq!(progress.password.split(WORD_SEPARATOR).count(), 2);
+        assert!(progress.unused_rolls > 0);
+        assert_eq!(progress.rolls_needed, 0);
+
+        let cards = from_rolls(DiceSource::Cards, DiceOutput::Characters, 1, "as 10h Td Q♥").unwrap();
+        assert_eq!(cards.rolls, 4);
+        assert!(from_rolls(DiceSource::Dice, DiceOutput::Words, 2, "1 7").is_err());
+        assert!(from_rolls(DiceSource::Cards, DiceOutput::Words, 2, "1S ZZ").is_err());
+    }
+}
@@ -0,0 +1,381 @@
+// export.rs - Unencrypted CSV and JSON exports, for moving your data out of Saltr.
+// Anyone who gets hold of the file can read every password in it, so both the
+// Export page and the command line ask for a typed confirmation first.
+// Saltr has no master password to ask for again, so typing EXPORT stands in
+// for re-entering it.
+use std::io::{BufRead, Write};
+
+use crate::otp;
+use crate::st_json::{self, EntryKind, PasswordDetails};
+
+// What the user has to type to confirm an unencrypted export
+pub const CONFIRMATION_PHRASE: &str = "EXPORT";
+pub const CONFIRMATION_PROMPT: &str = "Type EXPORT to confirm";
+
+pub const WARNING: &str = "This export is NOT encrypted. Anyone who can read the file can read every password in it. \
+Delete it as soon as you have finished with it.";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ExportFormat {
+    #[default]
+    Csv,
+    Json,
+}
+
+impl ExportFormat {
+    pub const ALL: [ExportFormat; 2] = [ExportFormat::Csv, ExportFormat::Json];
+}
+
+impl std::fmt::Display for ExportFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            ExportFormat::Csv => "CSV",
+            ExportFormat::Json => "JSON",
+        })
+    }
+}
+
+// A column (CSV) or key (JSON) that can be included in the export
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportField {
+    Name,
+    Type,
+    Password,
+    Username,
+    Website,
+    Notes,
+    Folder,
+    Details,
+    OneTimeCode,
+    Derivation,
+    CustomFields,
+    CreatedAt,
+    UpdatedAt,
+}
+
+impl ExportField {
+    // Also the order the columns are written in
+    pub const ALL: [ExportField; 13] = [
+        ExportField::Name,
+        ExportField::Type,
+        ExportField::Password,
+        ExportField::Username,
+        ExportField::Website,
+        ExportField::Notes,
+        ExportField::Folder,
+        ExportField::Details,
+        ExportField::OneTimeCode,
+        ExportField::Derivation,
+        ExportField::CustomFields,
+        ExportField::CreatedAt,
+        ExportField::UpdatedAt,
+    ];
+
+    // Selected when the Export page is first opened
+    pub const DEFAULT: [ExportField; 9] = [
+        ExportField::Name,
+        ExportField::Type,
+        ExportField::Password,
+        ExportField::Username,
+        ExportField::Website,
+        ExportField::Notes,
+        ExportField::Folder,
+        ExportField::OneTimeCode,
+        ExportField::Derivation,
+    ];
+
+    // Column header / JSON key, also what --fields accepts on the command line
+    pub fn key(&self) -> &'static str {
+        match self {
+            ExportField::Name => "name",
+            ExportField::Type => "type",
+            ExportField::Password => "password",
+            ExportField::Username => "username",
+            ExportField::Website => "website",
+            ExportField::Notes => "notes",
+            ExportField::Folder => "folder",
+            ExportField::Details => "details",
+            ExportField::OneTimeCode => "otp",
+            ExportField::Derivation => "derivation",
+            ExportField::CustomFields => "custom_fields",
+            ExportField::CreatedAt => "created_at",
+            ExportField::UpdatedAt => "updated_at",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<ExportField> {
+        ExportField::ALL.into_iter().find(|field| field.key() == key.trim().to_ascii_lowercase())
+    }
+}
+
+impl std::fmt::Display for ExportField {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            ExportField::Name => "Name",
+            ExportField::Type => "Type",
+            ExportField::Password => "Password",
+            ExportField::Username => "Username",
+            ExportField::Website => "Website",
+            ExportField::Notes => "Notes",
+            ExportField::Folder => "Folder",
+            ExportField::Details => "Card / identity details",
+            ExportField::OneTimeCode => "One-time code (otpauth URI)",
+            ExportField::Derivation => "Derived password settings",
+            ExportField::CustomFields => "Custom fields",
+            ExportField::CreatedAt => "Created",
+            ExportField::UpdatedAt => "Last updated",
+        })
+    }
+}
+
+// The type-specific fields of an entry as label/value pairs
+fn kind_details(kind: &EntryKind) -> Vec<(&'static str, &str)> {
+    match kind {
+        EntryKind::Login | EntryKind::SecureNote => Vec::new(),
+        EntryKind::Card { cardholder, number, expiry, cvv } => vec![
+            ("cardholder", cardholder.as_str()),
+            ("number", number.as_str()),
+            ("expiry", expiry.as_str()),
+            ("cvv", cvv.as_str()),
+        ],
+        EntryKind::Identity { full_name, address, phone, email } => vec![
+            ("full_name", full_name.as_str()),
+            ("address", address.as_str()),
+            ("phone", phone.as_str()),
+            ("email", email.as_str()),
+        ],
+        EntryKind::ApiKey { expires_at } => vec![("expires_at", expires_at.as_str())],
+        EntryKind::Wifi { ssid, security, hidden } => vec![
+            ("ssid", ssid.as_str()),
+            ("security", security.name()),
+            ("hidden", if *hidden { "true" } else { "false" }),
+        ],
+    }
+}
+
+// "label: value" lines, used for the multi-value fields in CSV cells
+fn as_lines<'a>(pairs: impl Iterator<Item = (&'a str, &'a str)>) -> String {
+    pairs
+        .map(|(label, value)| format!("{}: {}", label, value))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn json_object<'a>(pairs: impl Iterator<Item = (&'a str, &'a str)>) -> serde_json::Value {
+    serde_json::Value::Object(
+        pairs
+            .map(|(label, value)| (label.to_string(), serde_json::Value::String(value.to_string())))
+            .collect(),
+    )
+}
+
+fn csv_value(entry: &PasswordDetails, field: ExportField) -> String {
+    match field {
+        ExportField::Name => entry.name.clone(),
+        ExportField::Type => entry.kind.label().to_string(),
+        ExportField::Password => entry.value.clone(),
+        ExportField::Username => entry.username.clone(),
+        ExportField::Website => entry.website.clone(),
+        ExportField::Notes => entry.notes.clone(),
+        ExportField::Folder => entry.folder.clone(),
+        ExportField::Details => as_lines(kind_details(&entry.kind).into_iter()),
+        ExportField::OneTimeCode => entry.otp.as_ref().map(otp::to_otpauth_uri).unwrap_or_default(),
+        ExportField::Derivation => entry
+            .derivation
+            .as_ref()
+            .and_then(|profile| serde_json::to_string(profile).ok())
+            .unwrap_or_default(),
+        ExportField::CustomFields => as_lines(entry.custom_fields.iter().map(|f| (f.name.as_str(), f.value.as_str()))),
+        ExportField::CreatedAt => entry.created_at.clone(),
+        ExportField::UpdatedAt => entry.updated_at.clone(),
+    }
+}
+
+fn json_value(entry: &PasswordDetails, field: ExportField) -> serde_json::Value {
+    match field {
+        ExportField::Details => json_object(kind_details(&entry.kind).into_iter()),
+        ExportField::Derivation => serde_json::to_value(&entry.derivation).unwrap_or_default(),
+        ExportField::CustomFields => json_object(entry.custom_fields.iter().map(|f| (f.name.as_str(), f.value.as_str()))),
+        other => serde_json::Value::String(csv_value(entry, other)),
+    }
+}
+
+// Builds the export text. Fields always come out in ExportField::ALL order,
+// whatever order they were selected in.
+pub fn export_entries(entries: &[PasswordDetails], fields: &[ExportField], format: ExportFormat) -> Result<String, String> {
+    let fields: Vec<ExportField> = ExportField::ALL.into_iter().filter(|field| fields.contains(field)).collect();
+    if fields.is_empty() {
+        return Err("Please select at least one field to export".to_string());
+    }
+
+    match format {
+        ExportFormat::Csv => {
+            let mut writer = csv::Writer::from_writer(Vec::new());
+            writer
+                .write_record(fields.iter().map(|field| field.key()))
+                .map_err(|e| format!("Could not write CSV: {}", e))?;
+            for entry in entries {
+                writer
+                    .write_record(fields.iter().map(|field| csv_value(entry, *field)))
+                    .map_err(|e| format!("Could not write CSV: {}", e))?;
+            }
+            let bytes = writer.into_inner().map_err(|e| format!("Could not write CSV: {}", e))?;
+            String::from_utf8(bytes).map_err(|e| format!("Could not write CSV: {}", e))
+        }
+        ExportFormat::Json => {
+            let rows: Vec<serde_json::Value> = entries
+                .iter()
+                .map(|entry| {
+                    serde_json::Value::Object(
+                        fields.iter().map(|field| (field.key().to_string(), json_value(entry, *field))).collect(),
+                    )
+                })
+                .collect();
+            serde_json::to_string_pretty(&serde_json::json!({ "passwords": rows }))
+                .map(|json| json + "\n")
+                .map_err(|e| format!("Could not write JSON: {}", e))
+        }
+    }
+}
+
+// Exports the vault at `vault_path` to `path`. Returns the number of entries written.
+pub fn export_to_file(vault_path: &str, path: &str, fields: &[ExportField], format: ExportFormat) -> Result<usize, String> {
+    let database = st_json::load_password_database(vault_path).map_err(|e| format!("Error loading passwords: {}", e))?;
+    let output = export_entries(&database.passwords, fields, format)?;
+    std::fs::write(path.trim(), output).map_err(|e| format!("Could not write export: {}", e))?;
+    Ok(database.passwords.len())
+}
+
+const CLI_USAGE: &str = "Usage: pass_gen export [--format csv|json] [--fields name,password,...] [--output FILE]
+Writes an UNENCRYPTED export of passwords.json. Without --output (or with --output -)
+the export is written to stdout.
+Fields: name, type, password, username, website, notes, folder, details, otp, derivation, custom_fields, created_at, updated_at";
+
+// `pass_gen export ...`: the warning and confirmation prompt go to stderr so
+// stdout only carries the export and can be piped into another tool
+pub fn run_cli(args: &[String]) -> Result<(), String> {
+    let mut format = ExportFormat::Csv;
+    let mut fields = ExportField::DEFAULT.to_vec();
+    let mut output = "-".to_string();
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--format" => {
+                format = match args.next().map(|value| value.to_ascii_lowercase()).as_deref() {
+                    Some("csv") => ExportFormat::Csv,
+                    Some("json") => ExportFormat::Json,
+                    _ => return Err(format!("--format must be csv or json\n{}", CLI_USAGE)),
+                }
+            }
+            "--fields" => {
+                let list = args.next().ok_or_else(|| format!("--fields needs a list\n{}", CLI_USAGE))?;
+                fields = list
+                    .split(',')
+                    .filter(|key| !key.trim().is_empty())
+                    .map(|key| ExportField::from_key(key).ok_or_else(|| format!("Unknown field '{}'\n{}", key.trim(), CLI_USAGE)))
+                    .collect::<Result<_, _>>()?;
+            }
+            "--output" => {
+                output = args.next().ok_or_else(|| format!("--output needs a file name\n{}", CLI_USAGE))?.clone();
+            }
+            "--help" | "-h" => {
+                println!("{}", CLI_USAGE);
+                return Ok(());
+            }
+            other => return Err(format!("Unknown option '{}'\n{}", other, CLI_USAGE)),
+        }
+    }
+
+    eprintln!("WARNING: {}", WARNING);
+    eprint!("{}: ", CONFIRMATION_PROMPT);
+    let mut answer = String::new();
+    std::io::stdin()
+        .lock()
+        .read_line(&mut answer)
+        .map_err(|e| format!("Could not read confirmation: {}", e))?;
+    if answer.trim() != CONFIRMATION_PHRASE {
+        return Err("Export cancelled".to_string());
+    }
+
+    if output == "-" {
+        let database = st_json::load_password_database("passwords.json").map_err(|e| format!("Error loading passwords: {}", e))?;
+        let text = export_entries(&database.passwords, &fields, format)?;
+        let mut stdout = std::io::stdout().lock();
+        stdout
+            .write_all(text.as_bytes())
+            .map_err(|e| format!("Could not write export: {}", e))?;
+        eprintln!("Exported {} entries", database.passwords.len());
+    } else {
+        let count = export_to_file("passwords.json", &output, &fields, format)?;
+        eprintln!("Exported {} entries to {}", count, output);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::st_json::{create_password_details, CustomField};
+
+    fn entries() -> Vec<PasswordDetails> {
+        let mut card = create_password_details("Visa, personal", "", "", "", "line one\nline \"two\"");
+        card.kind = EntryKind::Card {
+            cardholder: "Jo Doe".to_string(),
+            number: "4111 1111 1111 1111".to_string(),
+            expiry: "12/30".to_string(),
+            cvv: "123".to_string(),
+        };
+        card.custom_fields.push(CustomField { name: "PIN".to_string(), value: "0000".to_string(), protected: true });
+        card.custom_fields.push(CustomField { name: "Bank".to_string(), value: "Example".to_string(), protected: false });
+        vec![create_password_details("Mail", "hunter2", "mail.example.com", "me", ""), card]
+    }
+
+    #[test]
+    fn csv_has_selected_fields_in_order_and_quotes_multi_line_cells() {
+        // Picked out of order; written in ExportField::ALL order
+        let fields = [ExportField::CustomFields, ExportField::Name, ExportField::Details, ExportField::Notes];
+        let csv_text = export_entries(&entries(), &fields, ExportFormat::Csv).unwrap();
+
+        let mut reader = csv::Reader::from_reader(csv_text.as_bytes());
+        assert_eq!(reader.headers().unwrap(), vec!["name", "notes", "details", "custom_fields"]);
+        let rows: Vec<csv::StringRecord> = reader.records().map(Result::unwrap).collect();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(&rows[0], vec!["Mail", "", "", ""]);
+        assert_eq!(&rows[1][0], "Visa, personal");
+        assert_eq!(&rows[1][1], "line one\nline \"two\"");
+        assert_eq!(&rows[1][2], "cardholder: Jo Doe\nnumber: 4111 1111 1111 1111\nexpiry: 12/30\ncvv: 123");
+        assert_eq!(&rows[1][3], "PIN: 0000\nBank: Example");
+        assert!(csv_text.contains("\"PIN: 0000\nBank: Example\""));
+
+        assert!(export_entries(&entries(), &[], ExportFormat::Csv).is_err());
+    }
+
+    #[test]
+    fn json_nests_details_and_custom_fields() {
+        let fields = [ExportField::Name, ExportField::Type, ExportField::Password, ExportField::Details, ExportField::CustomFields];
+        let json_text = export_entries(&entries(), &fields, ExportFormat::Json).unwrap();
+        let json: serde_json::Value = serde_json::from_str(&json_text).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "passwords": [
+                    {
+                        "name": "Mail",
+                        "type": "Login",
+                        "password": "hunter2",
+                        "details": {},
+                        "custom_fields": {},
+                    },
+                    {
+                        "name": "Visa, personal",
+                        "type": "Payment Card",
+                        "password": "",
+                        "details": { "cardholder": "Jo Doe", "number": "4111 1111 1111 1111", "expiry": "12/30", "cvv": "123" },
+                        "custom_fields": { "PIN": "0000", "Bank": "Example" },
+                    },
+                ]
+            })
+        );
+    }
+}
@@ -1,19 +1,597 @@
-use rand::{rng, Rng};
+use rand::Rng;
 use rand::distr::Alphanumeric;
 
+// Every generator takes the random number generator to use. The app passes
+// rand::rng(), a CSPRNG seeded from the operating system; tests pass a seeded
+// one so their results can be repeated.
 
-pub fn generate_password(password: &mut String, length: usize) -> String{
-    let rng = rng();
-    let generated: String = rng
-        .sample_iter(&Alphanumeric)
-        .take(length)
-        .map(char::from)
-        .collect();
+pub fn generate_password<R: Rng + ?Sized>(rng: &mut R, password: &mut String, length: usize) -> String{
+    let generated = random_password(rng, length);
     
     *password = generated.clone();
-    println!("Generated password: {}", password);
     //Return Generated 
     generated
 }
 
+// Letters and digits
+pub fn random_password<R: Rng + ?Sized>(rng: &mut R, length: usize) -> String {
+    (0..length).map(|_| char::from(rng.sample(Alphanumeric))).collect()
+}
+
+// One character of an ASCII set
+fn pick<R: Rng + ?Sized>(rng: &mut R, set: &str) -> char {
+    let set = set.as_bytes();
+    set[rng.random_range(0..set.len())] as char
+}
+
+// Passwords from a pattern, for systems with a fixed format (licence keys,
+// PINs, Wi-Fi keys). Mixes regex-like and KeePass-style syntax:
+//   [A-Z] [a-f0-9] [-_]  a set of characters; ranges work, "\" escapes
+//   \d  \w               a digit, a letter/digit/underscore
+//   u l L d a A U h H    KeePass placeholders (upper, lower, any letter, digit, ...)
+//   {4} {2,5}            repeat the previous part exactly 4, or 2 to 5, times
+//   \W                   any other character after "\" is used as it is
+// Other letters are an error, so a typo doesn't silently end up in every
+// password; write them as [x] or \x. Other characters such as "-" are copied.
+const MAX_PATTERN_LENGTH: usize = 1024;
+
+const LOWER: &str = "abcdefghijklmnopqrstuvwxyz";
+const UPPER: &str = "ABCDEFGHIJKLMNOPQRSTUVWXYZ";
+const DIGITS: &str = "0123456789";
+const SPECIAL: &str = "!\"#$%&'()*+,-./:;<=>?@[\\]^_`{|}~";
+
+#[derive(Debug, Clone, PartialEq)]
+struct PatternPart {
+    characters: Vec<char>,
+    min: usize,
+    max: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Pattern {
+    parts: Vec<PatternPart>,
+}
+
+// Characters for a KeePass placeholder letter
+fn placeholder(letter: char) -> Option<String> {
+    let consonants = "bcdfghjklmnpqrstvwxyz";
+    let vowels = "aeiou";
+    Some(match letter {
+        'a' => format!("{}{}", LOWER, DIGITS),
+        'A' => format!("{}{}{}", LOWER, UPPER, DIGITS),
+        'U' => format!("{}{}", UPPER, DIGITS),
+        'c' => consonants.to_string(),
+        'C' => format!("{}{}", consonants, consonants.to_uppercase()),
+        'z' => consonants.to_uppercase(),
+        'd' => DIGITS.to_string(),
+        'h' => "0123456789abcdef".to_string(),
+        'H' => "0123456789ABCDEF".to_string(),
+        'l' => LOWER.to_string(),
+        'L' => format!("{}{}", LOWER, UPPER),
+        'u' => UPPER.to_string(),
+        'p' => ",.;:".to_string(),
+        'b' => "()[]{}<>".to_string(),
+        's' => SPECIAL.to_string(),
+        'S' => format!("{}{}{}{}", LOWER, UPPER, DIGITS, SPECIAL),
+        'v' => vowels.to_string(),
+        'V' => format!("{}{}", vowels, vowels.to_uppercase()),
+        'Z' => vowels.to_uppercase(),
+        _ => return None,
+    })
+}
+
+// "\d" and "\w" are classes like in regexes; anything else after "\" is itself
+fn escaped(c: char) -> String {
+    match c {
+        'd' => DIGITS.to_string(),
+        'w' => format!("{}{}{}_", LOWER, UPPER, DIGITS),
+        other => other.to_string(),
+    }
+}
+
+// The inside of [...], with `chars` just past the "["
+fn parse_set(chars: &mut std::iter::Peekable<std::str::Chars>) -> Result<Vec<char>, String> {
+    let mut set = Vec::new();
+    loop {
+        let c = match chars.next() {
+            None => return Err("Missing ']' to close a [...] set".to_string()),
+            Some(']') => break,
+            Some('\\') => {
+                let next = chars.next().ok_or("Pattern ends with a lone '\\'")?;
+                let class = escaped(next);
+                if class.chars().count() > 1 {
+                    set.extend(class.chars());
+                    continue;
+                }
+                next
+            }
+            Some(c) => c,
+        };
+        // A range like a-z; a "-" at the end of the set is just a dash
+        let mut lookahead = chars.clone();
+        if lookahead.next() == Some('-')
+            && let Some(&end) = lookahead.peek()
+            && end != ']'
+        {
+            chars.next();
+            chars.next();
+            if end < c {
+                return Err(format!("Range {}-{} is backwards", c, end));
+            }
+            set.extend(c..=end);
+        } else {
+            set.push(c);
+        }
+    }
+    if set.is_empty() {
+        return Err("Empty [] set".to_string());
+    }
+    Ok(set)
+}
+
+// The inside of {n} or {n,m}, with `chars` just past the "{"
+fn parse_repeat(chars: &mut std::iter::Peekable<std::str::Chars>) -> Result<(usize, usize), String> {
+    let mut inside = String::new();
+    loop {
+        match chars.next() {
+            None => return Err("Missing '}' to close a {...} repeat".to_string()),
+            Some('}') => break,
+            Some(c) => inside.push(c),
+        }
+    }
+    let number = |text: &str| {
+        text.trim().parse::<usize>().map_err(|_| format!("'{{{}}}' is not a repeat count", inside))
+    };
+    let (min, max) = match inside.split_once(',') {
+        Some((min, max)) => (number(min)?, number(max)?),
+        None => (number(&inside)?, number(&inside)?),
+    };
+    if max < min {
+        return Err(format!("Repeat {{{}}} has the larger number first", inside));
+    }
+    Ok((min, max))
+}
+
+pub fn parse_pattern(pattern: &str) -> Result<Pattern, String> {
+    let mut parts: Vec<PatternPart> = Vec::new();
+    let mut chars = pattern.chars().peekable();
+    while let Some(c) = chars.next() {
+        let characters: Vec<char> = match c {
+            '[' => parse_set(&mut chars)?,
+            '\\' => escaped(chars.next().ok_or("Pattern ends with a lone '\\'")?).chars().collect(),
+            '{' => {
+                let (min, max) = parse_repeat(&mut chars)?;
+                let Some(last) = parts.last_mut() else {
+                    return Err("A {...} repeat needs something before it".to_string());
+                };
+                // "d{4}" means four digits, not one digit and three more
+                if last.min != 1 || last.max != 1 {
+                    return Err("Only one {...} repeat per part".to_string());
+                }
+                last.min = min;
+                last.max = max;
+                continue;
+            }
+            c if c.is_ascii_alphabetic() => match placeholder(c) {
+                Some(characters) => characters.chars().collect(),
+                None => return Err(format!("'{}' is not a placeholder; write [{}] for the letter itself", c, c)),
+            },
+            c => vec![c],
+        };
+        let mut characters = characters;
+        characters.sort();
+        characters.dedup();
+        parts.push(PatternPart { characters, min: 1, max: 1 });
+    }
 
+    if parts.is_empty() {
+        return Err("Enter a pattern".to_string());
+    }
+    if parts.iter().map(|part| part.max).sum::<usize>() > MAX_PATTERN_LENGTH {
+        return Err(format!("Patterns can make at most {} characters", MAX_PATTERN_LENGTH));
+    }
+    Ok(Pattern { parts })
+}
+
+impl Pattern {
+    // Bits of entropy of a password made from this pattern. Each character
+    // is worth log2 of its set's size; a {n,m} repeat also adds the choice of
+    // how many, since every count is equally likely.
+    pub fn entropy_bits(&self) -> f64 {
+        self.parts
+            .iter()
+            .map(|part| {
+                let counts = (part.max - part.min + 1) as f64;
+                let average_count = (part.min + part.max) as f64 / 2.0;
+                counts.log2() + average_count * (part.characters.len() as f64).log2()
+            })
+            .sum()
+    }
+}
+
+// A random password that matches the pattern
+pub fn generate_from_pattern<R: Rng + ?Sized>(rng: &mut R, pattern: &Pattern) -> String {
+    let mut password = String::new();
+    for part in &pattern.parts {
+        let count = rng.random_range(part.min..=part.max);
+        for _ in 0..count {
+            password.push(part.characters[rng.random_range(0..part.characters.len())]);
+        }
+    }
+    password
+}
+
+// Pronounceable passwords that can be read out over the phone, like
+// "bakotimu4". Letters alternate between a consonant and a vowel, and the
+// requested digits and symbols go in at random positions. Every password can
+// only be made one way, so the entropy below is exact rather than a guess:
+// letters, digits and symbols are told apart by class, and the letters always
+// alternate starting with a consonant.
+const CONSONANTS: &str = "bdfghjklmnprstvz";
+const VOWELS: &str = "aeiou";
+// Symbols with short, unambiguous names
+const SPOKEN_SYMBOLS: &str = "!@#$%&*?-+=";
+pub const MIN_PRONOUNCEABLE_LENGTH: usize = 4;
+pub const MAX_PRONOUNCEABLE_LENGTH: usize = 64;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PronounceableOptions {
+    pub length: usize,
+    pub digits: usize,
+    pub symbols: usize,
+}
+
+impl Default for PronounceableOptions {
+    fn default() -> Self {
+        Self { length: 12, digits: 1, symbols: 0 }
+    }
+}
+
+impl PronounceableOptions {
+    pub fn check(&self) -> Result<(), String> {
+        if !(MIN_PRONOUNCEABLE_LENGTH..=MAX_PRONOUNCEABLE_LENGTH).contains(&self.length) {
+            return Err(format!(
+                "Length must be between {} and {}",
+                MIN_PRONOUNCEABLE_LENGTH, MAX_PRONOUNCEABLE_LENGTH
+            ));
+        }
+        // Keep at least half of it letters, or it stops being pronounceable
+        if (self.digits + self.symbols) * 2 > self.length {
+            return Err("Digits and symbols can be at most half the length".to_string());
+        }
+        Ok(())
+    }
+
+    // log2 of the number of different passwords these options can give
+    pub fn entropy_bits(&self) -> f64 {
+        let letters = self.length - self.digits - self.symbols;
+        let consonants = letters.div_ceil(2) as f64;
+        let vowels = (letters / 2) as f64;
+        // Where the digits go, then where the symbols go among the rest
+        let positions = log2_choose(self.length, self.digits) + log2_choose(self.length - self.digits, self.symbols);
+        consonants * (CONSONANTS.len() as f64).log2()
+            + vowels * (VOWELS.len() as f64).log2()
+            + self.digits as f64 * (DIGITS.len() as f64).log2()
+            + self.symbols as f64 * (SPOKEN_SYMBOLS.len() as f64).log2()
+            + positions
+    }
+}
+
+// log2 of n choose k
+fn log2_choose(n: usize, k: usize) -> f64 {
+    (0..k).map(|i| ((n - i) as f64).log2() - ((i + 1) as f64).log2()).sum()
+}
+
+pub fn generate_pronounceable<R: Rng + ?Sized>(rng: &mut R, options: &PronounceableOptions) -> Result<String, String> {
+    options.check()?;
+
+    let letters = options.length - options.digits - options.symbols;
+    let mut password: Vec<char> = (0..letters)
+        .map(|i| pick(rng, if i % 2 == 0 { CONSONANTS } else { VOWELS }))
+        .collect();
+    // Each insert picks uniformly among all places it could go, which spreads
+    // the digits and symbols evenly over every possible set of positions
+    for _ in 0..options.digits {
+        let position = rng.random_range(0..=password.len());
+        password.insert(position, pick(rng, DIGITS));
+    }
+    for _ in 0..options.symbols {
+        let position = rng.random_range(0..=password.len());
+        password.insert(position, pick(rng, SPOKEN_SYMBOLS));
+    }
+    Ok(password.into_iter().collect())
+}
+
+// Words for usernames and passphrases: the 2048-word BIP39 English list of
+// short, common words that are easy to spell
+const WORDLIST: &str = include_str!("../assets/wordlist.txt");
+
+pub fn wordlist() -> &'static [&'static str] {
+    static WORDS: std::sync::OnceLock<Vec<&'static str>> = std::sync::OnceLock::new();
+    WORDS.get_or_init(|| WORDLIST.lines().map(str::trim).filter(|word| !word.is_empty()).collect())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UsernameStyle {
+    // Two words and two digits, e.g. "orbit_canyon42"
+    Words,
+    // A letter and nine random letters or digits, e.g. "k3x9mq2v7p"
+    Handle,
+}
+
+impl std::fmt::Display for UsernameStyle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            UsernameStyle::Words => "Word username",
+            UsernameStyle::Handle => "Random handle",
+        })
+    }
+}
+
+pub fn generate_username<R: Rng + ?Sized>(rng: &mut R, style: UsernameStyle) -> String {
+    match style {
+        UsernameStyle::Words => {
+            let words = wordlist();
+            format!(
+                "{}_{}{:02}",
+                words[rng.random_range(0..words.len())],
+                words[rng.random_range(0..words.len())],
+                rng.random_range(0..100)
+            )
+        }
+        UsernameStyle::Handle => {
+            // Many sites want usernames to start with a letter
+            let first = pick(rng, LOWER);
+            let rest = format!("{}{}", LOWER, DIGITS);
+            std::iter::once(first)
+                .chain((0..9).map(|_| pick(rng, &rest)))
+                .collect()
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AliasKind {
+    // "me+tag@example.com", delivered to me@example.com
+    PlusAddress,
+    // "anything@catch-all.example", for domains that accept any address
+    CatchAll,
+}
+
+impl std::fmt::Display for AliasKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            AliasKind::PlusAddress => "Plus alias",
+            AliasKind::CatchAll => "Catch-all alias",
+        })
+    }
+}
+
+// Checks the Settings email used for plus aliases
+pub fn check_alias_email(email: &str) -> Result<(), String> {
+    match email.split_once('@') {
+        Some((local, domain)) if !local.is_empty() && domain.contains('.') && !domain.contains('@') && !email.contains(' ') => Ok(()),
+        _ => Err(format!("'{}' is not an email address", email)),
+    }
+}
+
+// Checks the Settings catch-all domain
+pub fn check_catch_all_domain(domain: &str) -> Result<(), String> {
+    let valid = domain.contains('.')
+        && !domain.starts_with('.')
+        && !domain.ends_with('.')
+        && domain.chars().all(|c| c.is_ascii_alphanumeric() || c == '.' || c == '-');
+    if valid {
+        Ok(())
+    } else {
+        Err(format!("'{}' is not a domain name", domain))
+    }
+}
+
+// A new email alias: a random word tag on `email` for plus addressing, or a
+// random word-based address at a catch-all `domain`
+pub fn generate_email_alias<R: Rng + ?Sized>(rng: &mut R, kind: AliasKind, email: &str, domain: &str) -> Result<String, String> {
+    let words = wordlist();
+    let tag = format!("{}{:03}", words[rng.random_range(0..words.len())], rng.random_range(0..1000));
+    match kind {
+        AliasKind::PlusAddress => {
+            check_alias_email(email)?;
+            let (local, domain) = email.split_once('@').unwrap_or_default();
+            // "me+old@example.com" still delivers to "me"
+            let local = local.split('+').next().unwrap_or(local);
+            Ok(format!("{}+{}@{}", local, tag, domain))
+        }
+        AliasKind::CatchAll => {
+            check_catch_all_domain(domain)?;
+            Ok(format!("{}.{}@{}", words[rng.random_range(0..words.len())], tag, domain))
+        }
+    }
+}
+
+// Passphrases of random words from the word list, e.g. "orbit-canyon-fancy-whisper".
+// Each word is worth exactly 11 bits, as the list has 2048 words.
+pub const MIN_PASSPHRASE_WORDS: usize = 3;
+pub const MAX_PASSPHRASE_WORDS: usize = 20;
+
+pub fn passphrase_entropy(words: usize) -> f64 {
+    words as f64 * (wordlist().len() as f64).log2()
+}
+
+pub fn generate_passphrase<R: Rng + ?Sized>(rng: &mut R, words: usize, separator: &str) -> Result<String, String> {
+    if !(MIN_PASSPHRASE_WORDS..=MAX_PASSPHRASE_WORDS).contains(&words) {
+        return Err(format!(
+            "Passphrases need between {} and {} words",
+            MIN_PASSPHRASE_WORDS, MAX_PASSPHRASE_WORDS
+        ));
+    }
+    let list = wordlist();
+    let chosen: Vec<&str> = (0..words).map(|_| list[rng.random_range(0..list.len())]).collect();
+    Ok(chosen.join(separator))
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    pub(crate) fn seeded(seed: u64) -> StdRng {
+        StdRng::seed_from_u64(seed)
+    }
+
+    // Pearson's chi-squared test that every bucket was hit about equally
+    // often. The limit is the 99.99th percentile (Wilson-Hilferty
+    // approximation), so a fair generator fails about once in 10,000 seeds.
+    pub(crate) fn assert_uniform(counts: &[u64], what: &str) {
+        let total: u64 = counts.iter().sum();
+        let expected = total as f64 / counts.len() as f64;
+        assert!(expected >= 5.0, "{}: too few samples for a chi-squared test", what);
+        let statistic: f64 = counts
+            .iter()
+            .map(|&count| (count as f64 - expected).powi(2) / expected)
+            .sum();
+        let k = (counts.len() - 1) as f64;
+        let z = 3.719;
+        let limit = k * (1.0 - 2.0 / (9.0 * k) + z * (2.0 / (9.0 * k)).sqrt()).powi(3);
+        assert!(
+            statistic < limit,
+            "{} is not uniform: chi-squared {:.1} over {} buckets (limit {:.1})",
+            what,
+            statistic,
+            counts.len(),
+            limit
+        );
+    }
+
+    // Counts of each character of `set` in `text`; anything else fails the test
+    pub(crate) fn count_chars(text: &str, set: &str, counts: &mut [u64]) {
+        for c in text.chars() {
+            let index = set.find(c).unwrap_or_else(|| panic!("unexpected character {:?}", c));
+            counts[index] += 1;
+        }
+    }
+
+    #[test]
+    fn same_seed_gives_same_output() {
+        let pattern = parse_pattern(r"[A-Z]{4}-\d{2,4}").unwrap();
+        let options = PronounceableOptions { length: 14, digits: 2, symbols: 1 };
+        let run = |seed| {
+            let mut rng = seeded(seed);
+            vec![
+                random_password(&mut rng, 20),
+                generate_from_pattern(&mut rng, &pattern),
+                generate_pronounceable(&mut rng, &options).unwrap(),
+                generate_passphrase(&mut rng, 5, " ").unwrap(),
+                generate_username(&mut rng, UsernameStyle::Words),
+                generate_username(&mut rng, UsernameStyle::Handle),
+                generate_email_alias(&mut rng, AliasKind::PlusAddress, "me@example.com", "").unwrap(),
+            ]
+        };
+        assert_eq!(run(7), run(7));
+        assert_ne!(run(7), run(8));
+    }
+
+    #[test]
+    fn random_passwords_use_every_character_equally() {
+        let alphabet = format!("{}{}{}", UPPER, LOWER, DIGITS);
+        let mut rng = seeded(1);
+        let mut counts = vec![0; alphabet.len()];
+        for _ in 0..2_000 {
+            let password = random_password(&mut rng, 32);
+            assert_eq!(password.len(), 32);
+            count_chars(&password, &alphabet, &mut counts);
+        }
+        assert_uniform(&counts, "random password characters");
+    }
+
+    #[test]
+    fn pattern_passwords_follow_the_pattern() {
+        let pattern = parse_pattern(r"[A-Z]{4}-\d{4}-[a-f]{2,5}").unwrap();
+        let mut rng = seeded(2);
+        let mut upper = vec![0; 26];
+        let mut digits = vec![0; 10];
+        let mut tail_lengths = vec![0; 4];
+        for _ in 0..5_000 {
+            let password = generate_from_pattern(&mut rng, &pattern);
+            let parts: Vec<&str> = password.split('-').collect();
+            assert_eq!(parts.len(), 3, "{}", password);
+            assert_eq!(parts[0].len(), 4, "{}", password);
+            assert_eq!(parts[1].len(), 4, "{}", password);
+            assert!((2..=5).contains(&parts[2].len()), "{}", password);
+            count_chars(parts[0], UPPER, &mut upper);
+            count_chars(parts[1], DIGITS, &mut digits);
+            count_chars(parts[2], "abcdef", &mut [0; 6]);
+            tail_lengths[parts[2].len() - 2] += 1;
+        }
+        assert_uniform(&upper, "[A-Z] characters");
+        assert_uniform(&digits, "\\d characters");
+        assert_uniform(&tail_lengths, "{2,5} repeat counts");
+    }
+
+    #[test]
+    fn pronounceable_passwords_follow_the_options() {
+        let options = PronounceableOptions { length: 12, digits: 2, symbols: 1 };
+        let mut rng = seeded(3);
+        let mut digit_positions = vec![0; options.length];
+        let mut consonants = vec![0; CONSONANTS.len()];
+        for _ in 0..10_000 {
+            let password = generate_pronounceable(&mut rng, &options).unwrap();
+            assert_eq!(password.chars().count(), options.length);
+            assert_eq!(password.chars().filter(|c| c.is_ascii_digit()).count(), options.digits);
+            assert_eq!(password.chars().filter(|c| SPOKEN_SYMBOLS.contains(*c)).count(), options.symbols);
+            for (i, c) in password.chars().enumerate() {
+                if c.is_ascii_digit() {
+                    digit_positions[i] += 1;
+                }
+            }
+            // With the digits and symbols taken out, letters alternate starting with a consonant
+            let letters: String = password.chars().filter(|c| c.is_ascii_lowercase()).collect();
+            for (i, c) in letters.chars().enumerate() {
+                let set = if i % 2 == 0 { CONSONANTS } else { VOWELS };
+                assert!(set.contains(c), "{} breaks the consonant-vowel order", password);
+            }
+            count_chars(&letters.chars().step_by(2).collect::<String>(), CONSONANTS, &mut consonants);
+        }
+        assert_uniform(&digit_positions, "digit positions");
+        assert_uniform(&consonants, "consonants");
+    }
+
+    #[test]
+    fn passphrases_pick_every_word_equally() {
+        let list = wordlist();
+        assert_eq!(list.len(), 2048);
+        let mut rng = seeded(4);
+        let mut counts = vec![0; list.len()];
+        for _ in 0..20_000 {
+            let passphrase = generate_passphrase(&mut rng, 6, " ").unwrap();
+            let words: Vec<&str> = passphrase.split(' ').collect();
+            assert_eq!(words.len(), 6);
+            for word in words {
+                counts[list.binary_search(&word).expect("word from the list")] += 1;
+            }
+        }
+        assert_uniform(&counts, "passphrase words");
+        assert!(generate_passphrase(&mut rng, 2, " ").is_err());
+    }
+
+    #[test]
+    fn usernames_and_aliases_have_the_expected_shape() {
+        let mut rng = seeded(5);
+        for _ in 0..1_000 {
+            let handle = generate_username(&mut rng, UsernameStyle::Handle);
+            assert_eq!(handle.len(), 10);
+            assert!(handle.starts_with(|c: char| c.is_ascii_lowercase()), "{}", handle);
+            assert!(handle.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit()), "{}", handle);
+
+            let name = generate_username(&mut rng, UsernameStyle::Words);
+            let (first, rest) = name.split_once('_').unwrap();
+            assert!(wordlist().contains(&first), "{}", name);
+            assert!(rest.ends_with(|c: char| c.is_ascii_digit()), "{}", name);
+
+            let alias = generate_email_alias(&mut rng, AliasKind::PlusAddress, "me+old@example.com", "").unwrap();
+            assert!(alias.starts_with("me+") && alias.ends_with("@example.com"), "{}", alias);
+            let alias = generate_email_alias(&mut rng, AliasKind::CatchAll, "", "example.net").unwrap();
+            assert!(alias.ends_with("@example.net") && !alias.contains('+'), "{}", alias);
+        }
+    }
+}
@@ -0,0 +1,251 @@
+// hibp.rs - Offline breached-password checks against a downloaded copy of
+// Have I Been Pwned's Pwned Passwords list. Nothing is sent over the network.
+//
+// Two layouts from the HIBP downloader are supported:
+// - one big file with a "HASH:COUNT" line per hash, sorted by hash
+// - a folder of range files named after the first 5 hex digits of the hash
+//   (e.g. "21BD1.txt"), each holding "SUFFIX:COUNT" lines sorted by suffix
+// Either can hold SHA-1 or NTLM hashes; which one is worked out from the lines.
+use sha1::{Digest, Sha1};
+use std::fs::File;
+use std::io::{BufRead, BufReader, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+use crate::audit;
+use crate::st_json::PasswordDetails;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HashKind {
+    Sha1,
+    Ntlm,
+}
+
+impl std::fmt::Display for HashKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            HashKind::Sha1 => "SHA-1",
+            HashKind::Ntlm => "NTLM",
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PwnedPasswords {
+    path: PathBuf,
+    kind: HashKind,
+    // True for a folder of range files
+    ranges: bool,
+}
+
+const PREFIX_LEN: usize = 5;
+
+// Upper-case hex of the password hash the list is keyed by
+pub fn password_hash(password: &str, kind: HashKind) -> String {
+    let digest = match kind {
+        HashKind::Sha1 => Sha1::digest(password.as_bytes()).to_vec(),
+        // NTLM is MD4 over the UTF-16LE encoded password
+        HashKind::Ntlm => {
+            let utf16: Vec<u8> = password.encode_utf16().flat_map(|unit| unit.to_le_bytes()).collect();
+            md4(&utf16).to_vec()
+        }
+    };
+    digest.iter().map(|byte| format!("{:02X}", byte)).collect()
+}
+
+impl PwnedPasswords {
+    pub fn open(path: &str) -> Result<Self, String> {
+        let path = PathBuf::from(path.trim());
+        let ranges = path.is_dir();
+        let sample = if ranges {
+            std::fs::read_dir(&path)
+                .map_err(|e| format!("Could not open the Pwned Passwords folder: {}", e))?
+                .filter_map(|entry| entry.ok())
+                .map(|entry| entry.path())
+                .find(|file| is_range_file(file))
+                .ok_or_else(|| "No range files (like 21BD1.txt) in the Pwned Passwords folder".to_string())?
+        } else {
+            path.clone()
+        };
+
+        let mut first_line = String::new();
+        BufReader::new(File::open(&sample).map_err(|e| format!("Could not open the Pwned Passwords file: {}", e))?)
+            .read_line(&mut first_line)
+            .map_err(|e| format!("Could not read the Pwned Passwords file: {}", e))?;
+        let hash_len = first_line.split(':').next().unwrap_or("").trim().len() + if ranges { PREFIX_LEN } else { 0 };
+
+        let kind = match hash_len {
+            40 => HashKind::Sha1,
+            32 => HashKind::Ntlm,
+            _ => return Err("This doesn't look like a Pwned Passwords SHA-1 or NTLM file".to_string()),
+        };
+        Ok(Self { path, kind, ranges })
+    }
+
+    // How many times the password appears in breaches; 0 if it doesn't
+    pub fn breach_count(&self, password: &str) -> Result<u64, String> {
+        let hash = password_hash(password, self.kind);
+        let (file, target) = if self.ranges {
+            let file = self.path.join(format!("{}.txt", &hash[..PREFIX_LEN]));
+            if !file.exists() {
+                return Ok(0);
+            }
+            (file, &hash[PREFIX_LEN..])
+        } else {
+            (self.path.clone(), &hash[..])
+        };
+        search_file(&file, target).map_err(|e| format!("Could not read the Pwned Passwords file: {}", e))
+    }
+
+    // Entries whose password is in the list, with how often it was seen
+    pub fn breached_entries(&self, entries: &[PasswordDetails]) -> Result<Vec<(usize, u64)>, String> {
+        let mut breached = Vec::new();
+        for (index, entry) in entries.iter().enumerate().filter(|(_, entry)| audit::has_password(entry)) {
+            let count = self.breach_count(&entry.value)?;
+            if count > 0 {
+                breached.push((index, count));
+            }
+        }
+        Ok(breached)
+    }
+}
+
+// Range files are named by 5 hex digits, e.g. "21BD1.txt" or just "21BD1"
+fn is_range_file(file: &Path) -> bool {
+    let stem = file.file_stem().and_then(|stem| stem.to_str()).unwrap_or("");
+    stem.len() == PREFIX_LEN && stem.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+// The first whole line starting at or after `pos`, with its start offset
+fn line_from(reader: &mut BufReader<File>, pos: u64) -> std::io::Result<Option<(u64, String)>> {
+    let mut start = pos;
+    if pos > 0 {
+        // Skip the rest of the line `pos` falls in (or just its newline)
+        reader.seek(SeekFrom::Start(pos - 1))?;
+        let mut skipped = Vec::new();
+        start = pos - 1 + reader.read_until(b'\n', &mut skipped)? as u64;
+    } else {
+        reader.seek(SeekFrom::Start(0))?;
+    }
+    let mut line = String::new();
+    if reader.read_line(&mut line)? == 0 {
+        return Ok(None);
+    }
+    Ok(Some((start, line)))
+}
+
+// Binary search over byte offsets of a file of sorted "HASH:COUNT" lines.
+// Lines have different lengths, so each probe reads the first line after the
+// probe point instead of indexing lines directly.
+fn search_file(path: &Path, target: &str) -> std::io::Result<u64> {
+    let file = File::open(path)?;
+    let mut low = 0;
+    let mut high = file.metadata()?.len();
+    let mut reader = BufReader::new(file);
+
+    while low < high {
+        let middle = low + (high - low) / 2;
+        let Some((start, line)) = line_from(&mut reader, middle)? else {
+            high = middle;
+            continue;
+        };
+        let (hash, count) = line.trim().split_once(':').unwrap_or((line.trim(), "1"));
+        match hash.to_ascii_uppercase().as_str().cmp(target) {
+            std::cmp::Ordering::Equal => return Ok(count.trim().parse().unwrap_or(1)),
+            std::cmp::Ordering::Less => low = start + line.len() as u64,
+            std::cmp::Ordering::Greater => high = middle,
+        }
+    }
+    Ok(0)
+}
+
+// MD4 (RFC 1320), only needed for NTLM hashes
+fn md4(message: &[u8]) -> [u8; 16] {
+    let mut state: [u32; 4] = [0x67452301, 0xefcdab89, 0x98badcfe, 0x10325476];
+
+    let mut padded = message.to_vec();
+    padded.push(0x80);
+    while padded.len() % 64 != 56 {
+        padded.push(0);
+    }
+    padded.extend_from_slice(&((message.len() as u64).wrapping_mul(8)).to_le_bytes());
+
+    for block in padded.chunks(64) {
+        let x: Vec<u32> = block
+            .chunks(4)
+            .map(|word| u32::from_le_bytes([word[0], word[1], word[2], word[3]]))
+            .collect();
+        let [mut a, mut b, mut c, mut d] = state;
+
+        let f = |x: u32, y: u32, z: u32| (x & y) | (!x & z);
+        let g = |x: u32, y: u32, z: u32| (x & y) | (x & z) | (y & z);
+        let h = |x: u32, y: u32, z: u32| x ^ y ^ z;
+
+        for &i in &[0, 4, 8, 12] {
+            a = a.wrapping_add(f(b, c, d)).wrapping_add(x[i]).rotate_left(3);
+            d = d.wrapping_add(f(a, b, c)).wrapping_add(x[i + 1]).rotate_left(7);
+            c = c.wrapping_add(f(d, a, b)).wrapping_add(x[i + 2]).rotate_left(11);
+            b = b.wrapping_add(f(c, d, a)).wrapping_add(x[i + 3]).rotate_left(19);
+        }
+        for &i in &[0, 1, 2, 3] {
+            a = a.wrapping_add(g(b, c, d)).wrapping_add(x[i]).wrapping_add(0x5a827999).rotate_left(3);
+            d = d.wrapping_add(g(a, b, c)).wrapping_add(x[i + 4]).wrapping_add(0x5a827999).rotate_left(5);
+            c = c.wrapping_add(g(d, a, b)).wrapping_add(x[i + 8]).wrapping_add(0x5a827999).rotate_left(9);
+            b = b.wrapping_add(g(c, d, a)).wrapping_add(x[i + 12]).wrapping_add(0x5a827999).rotate_left(13);
+        }
+        for &i in &[0, 2, 1, 3] {
+            a = a.wrapping_add(h(b, c, d)).wrapping_add(x[i]).wrapping_add(0x6ed9eba1).rotate_left(3);
+            d = d.wrapping_add(h(a, b, c)).wrapping_add(x[i + 8]).wrapping_add(0x6ed9eba1).rotate_left(9);
+            c = c.wrapping_add(h(d, a, b)).wrapping_add(x[i + 4]).wrapping_add(0x6ed9eba1).rotate_left(11);
+            b = b.wrapping_add(h(c, d, a)).wrapping_add(x[i + 12]).wrapping_add(0x6ed9eba1).rotate_left(15);
+        }
+
+        state[0] = state[0].wrapping_add(a);
+        state[1] = state[1].wrapping_add(b);
+        state[2] = state[2].wrapping_add(c);
+        state[3] = state[3].wrapping_add(d);
+    }
+
+    let mut digest = [0u8; 16];
+    for (chunk, word) in digest.chunks_mut(4).zip(state) {
+        chunk.copy_from_slice(&word.to_le_bytes());
+    }
+    digest
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ntlm_matches_known_hash() {
+        assert_eq!(password_hash("password", HashKind::Ntlm), "8846F7EAEE8FB117AD06BDD830B7586C");
+        assert_eq!(password_hash("password", HashKind::Sha1), "5BAA61E4C9B93F3F0682250B6CF8331B7EE68FD8");
+    }
+
+    #[test]
+    fn search_finds_every_line_of_a_sorted_file() {
+        // Counts of different widths give lines of different lengths
+        let mut lines: Vec<(String, u64)> = (0..200u64)
+            .map(|i| (password_hash(&format!("password{}", i), HashKind::Sha1), 10u64.pow((i % 7) as u32) + i))
+            .collect();
+        lines.sort();
+        let contents: String = lines.iter().map(|(hash, count)| format!("{}:{}\r\n", hash, count)).collect();
+        let path = std::env::temp_dir().join(format!("saltr-hibp-test-{}.txt", std::process::id()));
+        std::fs::write(&path, contents).unwrap();
+
+        let pwned = PwnedPasswords::open(&path.to_string_lossy()).unwrap();
+        let results: Vec<u64> = (0..200u64).map(|i| pwned.breach_count(&format!("password{}", i)).unwrap()).collect();
+        let missing = [
+            search_file(&path, "0000000000000000000000000000000000000000").unwrap(),
+            search_file(&path, "FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFF").unwrap(),
+            pwned.breach_count("not in the list").unwrap(),
+        ];
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(pwned.kind, HashKind::Sha1);
+        for (i, count) in results.iter().enumerate() {
+            assert_eq!(*count, 10u64.pow((i % 7) as u32) + i as u64, "password{}", i);
+        }
+        assert_eq!(missing, [0, 0, 0]);
+    }
+}
@@ -0,0 +1,94 @@
+// history.rs - Recently generated passwords, so one that was used on a site
+// but not saved yet isn't lost when the generator makes the next one.
+// The history only lives in memory for this session. Each password is sealed
+// with a key made when the app starts, so the history never holds plain copies
+// of them. Old entries are dropped once they pass the age set in Settings.
+use crate::crypto;
+
+pub struct GeneratedPassword {
+    pub created_at: chrono::NaiveDateTime,
+    // Website it was made for, if known
+    pub website: String,
+    // What made it, e.g. "Random" or "Pattern"
+    pub source: String,
+    sealed: Vec<u8>,
+}
+
+impl GeneratedPassword {
+    // Ties the sealed password to its details, so they can't be swapped around
+    fn associated_data(created_at: chrono::NaiveDateTime, website: &str, source: &str) -> Vec<u8> {
+        format!("{}\n{}\n{}", created_at.and_utc().timestamp_micros(), website, source).into_bytes()
+    }
+}
+
+pub struct GeneratorHistory {
+    key: [u8; crypto::KEY_LEN],
+    // Newest first
+    entries: Vec<GeneratedPassword>,
+}
+
+impl Default for GeneratorHistory {
+    fn default() -> Self {
+        Self {
+            key: crypto::random_bytes::<{ crypto::KEY_LEN }>(),
+            entries: Vec::new(),
+        }
+    }
+}
+
+impl GeneratorHistory {
+    // Adds a password, keeping at most `max_entries`; 0 keeps none
+    pub fn record(&mut self, password: &str, website: &str, source: &str, max_entries: usize) {
+        if password.is_empty() || max_entries == 0 {
+            return;
+        }
+        // Clicking around can produce the same password twice in a row
+        if self.reveal(0).is_ok_and(|newest| newest == password) {
+            return;
+        }
+        let created_at = chrono::Utc::now().naive_utc();
+        let associated_data = GeneratedPassword::associated_data(created_at, website, source);
+        self.entries.insert(0, GeneratedPassword {
+            created_at,
+            website: website.to_string(),
+            source: source.to_string(),
+            sealed: crypto::encrypt(&self.key, password.as_bytes(), &associated_data),
+        });
+        self.entries.truncate(max_entries);
+    }
+
+    // Drops entries older than `max_age_minutes`; 0 keeps them until the app closes
+    pub fn expire(&mut self, now: chrono::NaiveDateTime, max_age_minutes: u32) {
+        if max_age_minutes == 0 {
+            return;
+        }
+        let max_age = chrono::Duration::minutes(max_age_minutes as i64);
+        self.entries.retain(|entry| now - entry.created_at <= max_age);
+    }
+
+    pub fn entries(&self) -> &[GeneratedPassword] {
+        &self.entries
+    }
+
+    pub fn reveal(&self, index: usize) -> Result<String, String> {
+        let entry = self.entries.get(index).ok_or("That password is no longer in the history")?;
+        let associated_data = GeneratedPassword::associated_data(entry.created_at, &entry.website, &entry.source);
+        let plaintext = crypto::decrypt(&self.key, &entry.sealed, &associated_data)?;
+        String::from_utf8(plaintext).map_err(|e| format!("History entry is damaged: {}", e))
+    }
+
+    // For when the size limit is lowered in Settings
+    pub fn truncate(&mut self, max_entries: usize) {
+        self.entries.truncate(max_entries);
+    }
+
+    pub fn remove(&mut self, index: usize) {
+        if index < self.entries.len() {
+            self.entries.remove(index);
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
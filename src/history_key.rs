@@ -0,0 +1,207 @@
+// history_key.rs - Encryption for the old passwords kept in each entry's history.
+// The key comes from a history password the user picks, through Argon2id. The
+// vault only stores the salt, the cost settings and a sealed check value that
+// tells a wrong password from the right one. The key is kept in memory while
+// Saltr runs; the password itself never is.
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use serde::{Serialize, Deserialize};
+
+use crate::crypto::{self, KdfParams};
+use crate::st_json::{PasswordDatabase, PasswordDetails, PasswordHistoryEntry};
+
+pub const MIN_PASSWORD_LENGTH: usize = 8;
+// Sealed with the key when the lock is made; opening it again proves the password
+const CHECK_TEXT: &[u8] = b"Saltr password history";
+
+// Refuse settings from the file that would take too much memory or time to open
+const MAX_MEMORY_KIB: u32 = 2 * 1024 * 1024;
+const MAX_ITERATIONS: u32 = 100;
+const MAX_PARALLELISM: u32 = 64;
+
+// What the vault stores about the history password, in passwords.json
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct HistoryLock {
+    salt: String,
+    memory_kib: u32,
+    iterations: u32,
+    parallelism: u32,
+    check: String,
+}
+
+#[derive(Clone)]
+pub struct HistoryKey([u8; crypto::KEY_LEN]);
+
+// Makes a new lock for `password`, with the key it opens
+pub fn create_lock(password: &str) -> Result<(HistoryLock, HistoryKey), String> {
+    create_lock_with(password, KdfParams::DEFAULT)
+}
+
+fn create_lock_with(password: &str, params: KdfParams) -> Result<(HistoryLock, HistoryKey), String> {
+    if password.chars().count() < MIN_PASSWORD_LENGTH {
+        return Err(format!("The history password must be at least {} characters", MIN_PASSWORD_LENGTH));
+    }
+    let salt = crypto::random_bytes::<{ crypto::SALT_LEN }>();
+    let key = crypto::derive_key(password, &salt, params)?;
+    let lock = HistoryLock {
+        salt: BASE64.encode(salt),
+        memory_kib: params.memory_kib,
+        iterations: params.iterations,
+        parallelism: params.parallelism,
+        check: BASE64.encode(crypto::encrypt(&key, CHECK_TEXT, &[])),
+    };
+    Ok((lock, HistoryKey(key)))
+}
+
+pub fn unlock(lock: &HistoryLock, password: &str) -> Result<HistoryKey, String> {
+    if lock.memory_kib > MAX_MEMORY_KIB || lock.iterations > MAX_ITERATIONS || lock.parallelism > MAX_PARALLELISM {
+        return Err("The vault's history key settings are too large; the file may be damaged".to_string());
+    }
+    let salt = BASE64.decode(&lock.salt).map_err(|_| "The vault's history salt is damaged".to_string())?;
+    let params = KdfParams { memory_kib: lock.memory_kib, iterations: lock.iterations, parallelism: lock.parallelism };
+    let key = crypto::derive_key(password, &salt, params)?;
+    let check = BASE64.decode(&lock.check).map_err(|_| "The vault's history check value is damaged".to_string())?;
+    crypto::decrypt(&key, &check, &[]).map_err(|_| "Wrong history password".to_string())?;
+    Ok(HistoryKey(key))
+}
+
+impl HistoryKey {
+    // The time it was replaced is bound to the sealed value, so old passwords
+    // can't be swapped around in the file
+    pub fn seal(&self, value: &str, changed_at: &str) -> PasswordHistoryEntry {
+        PasswordHistoryEntry {
+            sealed: BASE64.encode(crypto::encrypt(&self.0, value.as_bytes(), changed_at.as_bytes())),
+            value: String::new(),
+            changed_at: changed_at.to_string(),
+        }
+    }
+
+    fn open(&self, old: &PasswordHistoryEntry) -> Result<String, String> {
+        let sealed = BASE64.decode(&old.sealed).map_err(|_| "This old password is damaged".to_string())?;
+        let plaintext = crypto::decrypt(&self.0, &sealed, old.changed_at.as_bytes())
+            .map_err(|_| "This old password can't be opened with the history password".to_string())?;
+        String::from_utf8(plaintext).map_err(|_| "This old password is damaged".to_string())
+    }
+}
+
+// An old password in plain text. Ones in vaults saved before the history was
+// encrypted are still plain until the history is unlocked.
+pub fn reveal(key: Option<&HistoryKey>, old: &PasswordHistoryEntry) -> Result<String, String> {
+    if old.sealed.is_empty() {
+        return Ok(old.value.clone());
+    }
+    key.ok_or_else(|| "Unlock the password history first".to_string())?.open(old)
+}
+
+fn entries_mut(database: &mut PasswordDatabase) -> impl Iterator<Item = &mut PasswordDetails> {
+    database.passwords.iter_mut().chain(database.trash.iter_mut().map(|trashed| &mut trashed.entry))
+}
+
+fn entries(database: &PasswordDatabase) -> impl Iterator<Item = &PasswordDetails> {
+    database.passwords.iter().chain(database.trash.iter().map(|trashed| &trashed.entry))
+}
+
+// Old passwords that are still plain text, from imports or older vaults
+pub fn count_plain(database: &PasswordDatabase) -> usize {
+    entries(database).flat_map(|entry| &entry.history).filter(|old| old.sealed.is_empty()).count()
+}
+
+pub fn count_sealed(database: &PasswordDatabase) -> usize {
+    entries(database).flat_map(|entry| &entry.history).filter(|old| !old.sealed.is_empty()).count()
+}
+
+// Seals every plain old password. Returns how many there were.
+pub fn seal_database(database: &mut PasswordDatabase, key: &HistoryKey) -> usize {
+    let mut sealed = 0;
+    for old in entries_mut(database).flat_map(|entry| entry.history.iter_mut()) {
+        if old.sealed.is_empty() {
+            *old = key.seal(&old.value, &old.changed_at);
+            sealed += 1;
+        }
+    }
+    sealed
+}
+
+// Opens every sealed old password, for exporting to other apps
+pub fn open_database(database: &mut PasswordDatabase, key: &HistoryKey) -> Result<(), String> {
+    for old in entries_mut(database).flat_map(|entry| entry.history.iter_mut()) {
+        if !old.sealed.is_empty() {
+            old.value = key.open(old)?;
+            old.sealed.clear();
+        }
+    }
+    Ok(())
+}
+
+// Seals the old passwords in an imported file before they go into the vault
+pub fn seal_import(database: &mut PasswordDatabase, key: Option<&HistoryKey>) -> Result<(), String> {
+    if count_plain(database) == 0 {
+        return Ok(());
+    }
+    let key = key.ok_or_else(|| {
+        "This file has password history; unlock the password history in Settings first so it can be encrypted".to_string()
+    })?;
+    seal_database(database, key);
+    Ok(())
+}
+
+// Opens the old passwords for an export to another app
+pub fn open_for_export(database: &mut PasswordDatabase, key: Option<&HistoryKey>) -> Result<(), String> {
+    if count_sealed(database) == 0 {
+        return Ok(());
+    }
+    let key = key.ok_or_else(|| {
+        "The vault has password history; unlock the password history in Settings first so it can be exported".to_string()
+    })?;
+    open_database(database, key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::st_json::{self, TrashedEntry};
+
+    // Cheap settings so the tests run quickly
+    const TEST_PARAMS: KdfParams = KdfParams { memory_kib: 1024, iterations: 1, parallelism: 1 };
+
+    #[test]
+    fn history_is_sealed_and_opened_again() {
+        assert!(create_lock_with("short", TEST_PARAMS).is_err());
+        let (lock, key) = create_lock_with("history password", TEST_PARAMS).unwrap();
+        assert!(unlock(&lock, "wrong password").is_err());
+        let key_again = unlock(&lock, "history password").unwrap();
+
+        let mut entry = st_json::create_password_details("Mail", "first", "", "", "");
+        assert!(st_json::change_password(&mut entry, "second", 10, None).is_err());
+        assert!(st_json::change_password(&mut entry, "second", 10, Some(&key)).unwrap());
+        assert_eq!(entry.history[0].value, "");
+        assert!(!serde_json::to_string(&entry).unwrap().contains("first"));
+        assert_eq!(reveal(Some(&key_again), &entry.history[0]).unwrap(), "first");
+        assert!(reveal(None, &entry.history[0]).is_err());
+
+        // Moving a sealed value to another time makes it fail to open
+        let mut moved = entry.history[0].clone();
+        moved.changed_at = "2000-01-01 00:00:00".to_string();
+        assert!(reveal(Some(&key), &moved).is_err());
+
+        // Plain old passwords, like ones from an import, get sealed
+        let mut imported = st_json::create_password_details("Bank", "new", "", "", "");
+        imported.history.push(PasswordHistoryEntry {
+            sealed: String::new(),
+            value: "old".to_string(),
+            changed_at: st_json::timestamp_now(),
+        });
+        let mut database = PasswordDatabase {
+            passwords: vec![entry],
+            trash: vec![TrashedEntry { entry: imported, deleted_at: st_json::timestamp_now() }],
+            history_lock: Some(lock),
+        };
+        assert_eq!(count_plain(&database), 1);
+        assert_eq!(seal_database(&mut database, &key), 1);
+        assert_eq!((count_plain(&database), count_sealed(&database)), (0, 2));
+
+        open_database(&mut database, &key).unwrap();
+        assert_eq!(database.passwords[0].history[0].value, "first");
+        assert_eq!(database.trash[0].entry.history[0].value, "old");
+    }
+}
@@ -0,0 +1,1134 @@
+// kdbx.rs - Read and write KeePass KDBX 4 databases (KeePassXC, KeePass 2.x).
+//
+// A KDBX 4 file is: signature, a header of TLV fields, the header's SHA-256 and
+// HMAC, then the encrypted payload split into HMAC-protected blocks. The payload
+// (optionally gzipped) is an inner header followed by the KeePass XML, where
+// protected values (passwords) are additionally XORed with an inner stream cipher.
+//
+// Only password-protected databases are supported (no key files).
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use chrono::{NaiveDate, NaiveDateTime};
+use hmac::{Hmac, Mac};
+use rand::RngCore;
+use sha2::{Digest, Sha256, Sha512};
+use std::collections::BTreeMap;
+use std::io::{Read, Write};
+
+use crate::otp;
+use crate::st_json::{
+    self, CustomField, EntryKind, PasswordDatabase, PasswordDetails, PasswordHistoryEntry, TIMESTAMP_FORMAT,
+    TrashedEntry,
+};
+
+const SIGNATURE_1: u32 = 0x9AA2_D903;
+const SIGNATURE_2: u32 = 0xB54B_FB67;
+// We write KDBX 4.0
+const FILE_VERSION: u32 = 0x0004_0000;
+
+// Outer header field ids
+const HEADER_END: u8 = 0;
+const HEADER_CIPHER_ID: u8 = 2;
+const HEADER_COMPRESSION: u8 = 3;
+const HEADER_MASTER_SEED: u8 = 4;
+const HEADER_ENCRYPTION_IV: u8 = 7;
+const HEADER_KDF_PARAMETERS: u8 = 11;
+
+// Inner header field ids
+const INNER_END: u8 = 0;
+const INNER_STREAM_ID: u8 = 1;
+const INNER_STREAM_KEY: u8 = 2;
+
+const INNER_STREAM_SALSA20: u32 = 2;
+const INNER_STREAM_CHACHA20: u32 = 3;
+
+const CIPHER_AES256: [u8; 16] = [
+    0x31, 0xc1, 0xf2, 0xe6, 0xbf, 0x71, 0x43, 0x50, 0xbe, 0x58, 0x05, 0x21, 0x6a, 0xfc, 0x5a, 0xff,
+];
+const CIPHER_CHACHA20: [u8; 16] = [
+    0xd6, 0x03, 0x8a, 0x2b, 0x8b, 0x6f, 0x4c, 0xb5, 0xa5, 0x24, 0x33, 0x9a, 0x31, 0xdb, 0xb5, 0x9a,
+];
+const KDF_AES: [u8; 16] = [
+    0xc9, 0xd9, 0xf3, 0x9a, 0x62, 0x8a, 0x44, 0x60, 0xbf, 0x74, 0x0d, 0x08, 0xc1, 0x8a, 0x4f, 0xea,
+];
+const KDF_ARGON2D: [u8; 16] = [
+    0xef, 0x63, 0x6d, 0xdf, 0x8c, 0x29, 0x44, 0x4b, 0x91, 0xf7, 0xa9, 0xa4, 0x03, 0xe3, 0x0a, 0x0c,
+];
+const KDF_ARGON2ID: [u8; 16] = [
+    0x9e, 0x29, 0x8b, 0x19, 0x56, 0xdb, 0x47, 0x73, 0xb2, 0x3d, 0xfc, 0x3e, 0xc6, 0xf0, 0xa1, 0xe6,
+];
+
+// Argon2d settings used for exports (the KeePassXC defaults)
+const EXPORT_ARGON2_MEMORY: u64 = 64 * 1024 * 1024;
+const EXPORT_ARGON2_ITERATIONS: u64 = 10;
+const EXPORT_ARGON2_PARALLELISM: u32 = 2;
+
+// Payload block size used for exports
+const BLOCK_SIZE: usize = 1024 * 1024;
+
+// Limits on the key derivation settings read from a file, so a damaged or
+// hostile database can't make opening it use all memory or run for hours.
+// KeePass and KeePassXC defaults are far below these.
+const MAX_ARGON2_MEMORY_KIB: u64 = 2 * 1024 * 1024;
+const MAX_ARGON2_ITERATIONS: u64 = 1_000;
+const MAX_ARGON2_PARALLELISM: u64 = 64;
+const MAX_AES_ROUNDS: u64 = 100_000_000;
+
+// Saltr entry types that KeePass has no notion of are kept in custom fields
+const TYPE_FIELD: &str = "Saltr Type";
+
+// ---------------------------------------------------------------------------
+// Reading
+// ---------------------------------------------------------------------------
+
+// Reads a KDBX 4 file. Entries in the recycle bin are returned as trash.
+pub fn read_kdbx(path: &str, password: &str) -> Result<PasswordDatabase, String> {
+    let bytes = std::fs::read(path.trim()).map_err(|e| format!("Could not open database: {}", e))?;
+    let xml = decrypt_kdbx(&bytes, password)?;
+    database_from_xml(&xml.text, xml.inner_stream)
+}
+
+struct DecryptedXml {
+    text: String,
+    inner_stream: InnerStream,
+}
+
+fn decrypt_kdbx(bytes: &[u8], password: &str) -> Result<DecryptedXml, String> {
+    let mut reader = ByteReader::new(bytes);
+    if reader.u32()? != SIGNATURE_1 || reader.u32()? != SIGNATURE_2 {
+        return Err("Not a KeePass database".to_string());
+    }
+    let version = reader.u32()?;
+    if version >> 16 != 4 {
+        return Err(format!(
+            "Only KDBX 4 databases are supported (this file is version {}.{}); \
+             save it in KeePassXC as KDBX 4 first",
+            version >> 16,
+            version & 0xffff
+        ));
+    }
+
+    let mut cipher_id = None;
+    let mut compressed = false;
+    let mut master_seed = None;
+    let mut encryption_iv = None;
+    let mut kdf_parameters = None;
+    loop {
+        let id = reader.u8()?;
+        let size = reader.u32()? as usize;
+        let data = reader.take(size)?;
+        match id {
+            HEADER_END => break,
+            HEADER_CIPHER_ID => cipher_id = Some(data),
+            HEADER_COMPRESSION => compressed = data.first().copied().unwrap_or(0) == 1,
+            HEADER_MASTER_SEED => master_seed = Some(data),
+            HEADER_ENCRYPTION_IV => encryption_iv = Some(data),
+            HEADER_KDF_PARAMETERS => kdf_parameters = Some(VariantDictionary::parse(data)?),
+            _ => {}
+        }
+    }
+    let header = &bytes[..reader.position];
+    let missing = |field: &str| format!("Database header is missing the {}", field);
+    let cipher_id = cipher_id.ok_or_else(|| missing("cipher"))?;
+    let master_seed = master_seed.ok_or_else(|| missing("master seed"))?;
+    let encryption_iv = encryption_iv.ok_or_else(|| missing("encryption IV"))?;
+    let kdf_parameters = kdf_parameters.ok_or_else(|| missing("key derivation settings"))?;
+
+    let stored_hash = reader.take(32)?;
+    if Sha256::digest(header).as_slice() != stored_hash {
+        return Err("The database header is corrupted".to_string());
+    }
+
+    let keys = derive_keys(password, master_seed, &kdf_parameters)?;
+    let stored_hmac = reader.take(32)?;
+    let mut mac = block_hmac(&keys.hmac_base, u64::MAX);
+    mac.update(header);
+    mac.verify_slice(stored_hmac)
+        .map_err(|_| "Wrong password, or the database is corrupted".to_string())?;
+
+    // HMAC block stream: [hmac 32][size i32][data], ending with an empty block
+    let mut encrypted = Vec::new();
+    for block_index in 0u64.. {
+        let stored_hmac = reader.take(32)?;
+        let size_bytes = reader.take(4)?;
+        let size = u32::from_le_bytes([size_bytes[0], size_bytes[1], size_bytes[2], size_bytes[3]]) as usize;
+        let data = reader.take(size)?;
+
+        let mut mac = block_hmac(&keys.hmac_base, block_index);
+        mac.update(&block_index.to_le_bytes());
+        mac.update(size_bytes);
+        mac.update(data);
+        mac.verify_slice(stored_hmac)
+            .map_err(|_| format!("Database block {} is corrupted", block_index))?;
+
+        if size == 0 {
+            break;
+        }
+        encrypted.extend_from_slice(data);
+    }
+
+    let decrypted = if cipher_id == CIPHER_CHACHA20 {
+        chacha20_apply(&keys.master_key, encryption_iv, &encrypted)?
+    } else if cipher_id == CIPHER_AES256 {
+        aes_cbc_decrypt(&keys.master_key, encryption_iv, &encrypted)?
+    } else {
+        return Err("Unsupported database cipher (only AES-256 and ChaCha20 are supported)".to_string());
+    };
+
+    let payload = if compressed {
+        let mut out = Vec::new();
+        flate2::read::GzDecoder::new(decrypted.as_slice())
+            .read_to_end(&mut out)
+            .map_err(|e| format!("Could not decompress database: {}", e))?;
+        out
+    } else {
+        decrypted
+    };
+
+    // Inner header, then the XML document
+    let mut reader = ByteReader::new(&payload);
+    let mut stream_id = 0;
+    let mut stream_key = Vec::new();
+    loop {
+        let id = reader.u8()?;
+        let size = reader.u32()? as usize;
+        let data = reader.take(size)?;
+        match id {
+            INNER_END => break,
+            INNER_STREAM_ID if data.len() == 4 => {
+                stream_id = u32::from_le_bytes([data[0], data[1], data[2], data[3]])
+            }
+            INNER_STREAM_KEY => stream_key = data.to_vec(),
+            // Attachments (id 3) are not imported
+            _ => {}
+        }
+    }
+    let inner_stream = InnerStream::new(stream_id, &stream_key)?;
+    let text = String::from_utf8_lossy(&payload[reader.position..]).into_owned();
+
+    Ok(DecryptedXml { text, inner_stream })
+}
+
+struct ByteReader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> ByteReader<'a> {
+    fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes, position: 0 }
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], String> {
+        let end = self
+            .position
+            .checked_add(len)
+            .filter(|end| *end <= self.bytes.len())
+            .ok_or_else(|| "The database file is truncated".to_string())?;
+        let slice = &self.bytes[self.position..end];
+        self.position = end;
+        Ok(slice)
+    }
+
+    fn u8(&mut self) -> Result<u8, String> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, String> {
+        let b = self.take(2)?;
+        Ok(u16::from_le_bytes([b[0], b[1]]))
+    }
+
+    fn u32(&mut self) -> Result<u32, String> {
+        let b = self.take(4)?;
+        Ok(u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Keys and ciphers
+// ---------------------------------------------------------------------------
+
+struct Keys {
+    master_key: [u8; 32],
+    hmac_base: [u8; 64],
+}
+
+fn derive_keys(password: &str, master_seed: &[u8], kdf: &VariantDictionary) -> Result<Keys, String> {
+    // The composite key is SHA-256 over the SHA-256 of each key component
+    let composite = Sha256::digest(Sha256::digest(password.as_bytes()));
+    let transformed = transform_key(&composite, kdf)?;
+
+    let mut master_key = [0u8; 32];
+    master_key.copy_from_slice(&Sha256::new().chain_update(master_seed).chain_update(transformed).finalize());
+
+    let mut hmac_base = [0u8; 64];
+    hmac_base.copy_from_slice(
+        &Sha512::new()
+            .chain_update(master_seed)
+            .chain_update(transformed)
+            .chain_update([1u8])
+            .finalize(),
+    );
+    Ok(Keys { master_key, hmac_base })
+}
+
+fn transform_key(composite: &[u8], kdf: &VariantDictionary) -> Result<[u8; 32], String> {
+    let uuid = kdf.bytes("$UUID").ok_or("Key derivation settings have no algorithm")?;
+    let salt = kdf.bytes("S").ok_or("Key derivation settings have no salt")?;
+    let mut out = [0u8; 32];
+
+    if uuid == KDF_ARGON2D || uuid == KDF_ARGON2ID {
+        let algorithm = if uuid == KDF_ARGON2D { argon2::Algorithm::Argon2d } else { argon2::Algorithm::Argon2id };
+        let version = match kdf.uint("V") {
+            Some(0x10) => argon2::Version::V0x10,
+            _ => argon2::Version::V0x13,
+        };
+        let memory_kib = kdf.uint("M").unwrap_or(0) / 1024;
+        let iterations = kdf.uint("I").unwrap_or(0);
+        let parallelism = kdf.uint("P").unwrap_or(1);
+        if memory_kib > MAX_ARGON2_MEMORY_KIB {
+            return Err(format!("Database asks for {} MiB of memory to open; the limit is {} MiB", memory_kib / 1024, MAX_ARGON2_MEMORY_KIB / 1024));
+        }
+        if iterations > MAX_ARGON2_ITERATIONS {
+            return Err(format!("Database asks for {} Argon2 iterations; the limit is {}", iterations, MAX_ARGON2_ITERATIONS));
+        }
+        if parallelism > MAX_ARGON2_PARALLELISM {
+            return Err(format!("Database asks for {} Argon2 lanes; the limit is {}", parallelism, MAX_ARGON2_PARALLELISM));
+        }
+        let params = argon2::Params::new(
+            u32::try_from(memory_kib).map_err(|_| "Argon2 memory setting is too large")?,
+            u32::try_from(iterations).map_err(|_| "Argon2 iteration setting is too large")?,
+            u32::try_from(parallelism).map_err(|_| "Argon2 parallelism setting is too large")?,
+            Some(32),
+        )
+        .map_err(|e| format!("Invalid Argon2 settings: {}", e))?;
+        argon2::Argon2::new(algorithm, version, params)
+            .hash_password_into(composite, salt, &mut out)
+            .map_err(|e| format!("Key derivation failed: {}", e))?;
+    } else if uuid == KDF_AES {
+        use aes::cipher::{BlockEncrypt, KeyInit, generic_array::GenericArray};
+        let rounds = kdf.uint("R").ok_or("AES key derivation has no round count")?;
+        if rounds > MAX_AES_ROUNDS {
+            return Err(format!("Database asks for {} AES rounds; the limit is {}", rounds, MAX_AES_ROUNDS));
+        }
+        let cipher = aes::Aes256::new_from_slice(salt).map_err(|_| "AES key derivation seed must be 32 bytes")?;
+        out.copy_from_slice(composite);
+        for _ in 0..rounds {
+            for half in out.chunks_exact_mut(16) {
+                cipher.encrypt_block(GenericArray::from_mut_slice(half));
+            }
+        }
+        let hashed = Sha256::digest(out);
+        out.copy_from_slice(&hashed);
+    } else {
+        return Err("Unsupported key derivation function".to_string());
+    }
+    Ok(out)
+}
+
+// HMAC-SHA256 keyed for one payload block (u64::MAX is used for the header)
+fn block_hmac(hmac_base: &[u8; 64], block_index: u64) -> Hmac<Sha256> {
+    let key = Sha512::new().chain_update(block_index.to_le_bytes()).chain_update(hmac_base).finalize();
+    Hmac::<Sha256>::new_from_slice(&key).expect("HMAC accepts any key length")
+}
+
+fn chacha20_apply(key: &[u8], nonce: &[u8], data: &[u8]) -> Result<Vec<u8>, String> {
+    use chacha20::cipher::{KeyIvInit, StreamCipher};
+    let mut cipher = chacha20::ChaCha20::new_from_slices(key, nonce)
+        .map_err(|_| "Invalid ChaCha20 key or nonce".to_string())?;
+    let mut out = data.to_vec();
+    cipher.apply_keystream(&mut out);
+    Ok(out)
+}
+
+fn aes_cbc_decrypt(key: &[u8], iv: &[u8], data: &[u8]) -> Result<Vec<u8>, String> {
+    use cbc::cipher::{BlockDecryptMut, KeyIvInit, block_padding::Pkcs7};
+    cbc::Decryptor::<aes::Aes256>::new_from_slices(key, iv)
+        .map_err(|_| "Invalid AES key or IV".to_string())?
+        .decrypt_padded_vec_mut::<Pkcs7>(data)
+        .map_err(|_| "Wrong password, or the database is corrupted".to_string())
+}
+
+// The stream cipher protecting values marked Protected="True" in the XML.
+// It runs continuously over all protected values in document order.
+enum InnerStream {
+    ChaCha20(chacha20::ChaCha20),
+    Salsa20(salsa20::Salsa20),
+}
+
+impl InnerStream {
+    fn new(id: u32, key: &[u8]) -> Result<Self, String> {
+        use chacha20::cipher::KeyIvInit;
+        match id {
+            INNER_STREAM_CHACHA20 => {
+                let hash = Sha512::digest(key);
+                let cipher = chacha20::ChaCha20::new_from_slices(&hash[..32], &hash[32..44])
+                    .map_err(|_| "Invalid inner stream key".to_string())?;
+                Ok(InnerStream::ChaCha20(cipher))
+            }
+            INNER_STREAM_SALSA20 => {
+                const NONCE: [u8; 8] = [0xE8, 0x30, 0x09, 0x4B, 0x97, 0x20, 0x5D, 0x2A];
+                let cipher = salsa20::Salsa20::new_from_slices(&Sha256::digest(key), &NONCE)
+                    .map_err(|_| "Invalid inner stream key".to_string())?;
+                Ok(InnerStream::Salsa20(cipher))
+            }
+            other => Err(format!("Unsupported inner stream cipher {}", other)),
+        }
+    }
+
+    fn apply(&mut self, data: &mut [u8]) {
+        use chacha20::cipher::StreamCipher;
+        match self {
+            InnerStream::ChaCha20(cipher) => cipher.apply_keystream(data),
+            InnerStream::Salsa20(cipher) => cipher.apply_keystream(data),
+        }
+    }
+}
+
+// KeePass "VariantDictionary": typed key/value pairs used for the KDF settings
+struct VariantDictionary {
+    items: Vec<(String, VariantValue)>,
+}
+
+enum VariantValue {
+    UInt(u64),
+    Bytes(Vec<u8>),
+    Other,
+}
+
+impl VariantDictionary {
+    fn parse(data: &[u8]) -> Result<Self, String> {
+        let mut reader = ByteReader::new(data);
+        if reader.u16()? >> 8 != 1 {
+            return Err("Unsupported key derivation settings version".to_string());
+        }
+        let mut items = Vec::new();
+        loop {
+            let kind = reader.u8()?;
+            if kind == 0 {
+                break;
+            }
+            let key_len = reader.u32()? as usize;
+            let key = String::from_utf8_lossy(reader.take(key_len)?).into_owned();
+            let value_len = reader.u32()? as usize;
+            let value = reader.take(value_len)?;
+            let value = match (kind, value.len()) {
+                (0x04, 4) => VariantValue::UInt(u32::from_le_bytes([value[0], value[1], value[2], value[3]]) as u64),
+                (0x05, 8) => {
+                    let mut bytes = [0u8; 8];
+                    bytes.copy_from_slice(value);
+                    VariantValue::UInt(u64::from_le_bytes(bytes))
+                }
+                (0x42, _) => VariantValue::Bytes(value.to_vec()),
+                _ => VariantValue::Other,
+            };
+            items.push((key, value));
+        }
+        Ok(Self { items })
+    }
+
+    fn get(&self, key: &str) -> Option<&VariantValue> {
+        self.items.iter().find(|(k, _)| k == key).map(|(_, v)| v)
+    }
+
+    fn uint(&self, key: &str) -> Option<u64> {
+        match self.get(key) {
+            Some(VariantValue::UInt(value)) => Some(*value),
+            _ => None,
+        }
+    }
+
+    fn bytes(&self, key: &str) -> Option<&[u8]> {
+        match self.get(key) {
+            Some(VariantValue::Bytes(value)) => Some(value),
+            _ => None,
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// XML -> PasswordDatabase
+// ---------------------------------------------------------------------------
+
+// A minimal element tree; enough for the KeePass document
+#[derive(Default)]
+struct Node {
+    name: String,
+    attributes: Vec<(String, String)>,
+    text: String,
+    children: Vec<Node>,
+}
+
+impl Node {
+    fn child(&self, name: &str) -> Option<&Node> {
+        self.children.iter().find(|c| c.name == name)
+    }
+
+    fn children_named<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Node> + 'a {
+        self.children.iter().filter(move |c| c.name == name)
+    }
+
+    fn child_text(&self, name: &str) -> &str {
+        self.child(name).map(|c| c.text.as_str()).unwrap_or("")
+    }
+
+    fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes.iter().find(|(k, _)| k == name).map(|(_, v)| v.as_str())
+    }
+}
+
+// Parses the XML, decrypting protected values as they appear in document order
+fn parse_xml(xml: &str, inner_stream: &mut InnerStream) -> Result<Node, String> {
+    use quick_xml::events::{BytesStart, Event};
+
+    let xml_error = |e: quick_xml::Error| format!("Invalid database XML: {}", e);
+    let start_node = |e: &BytesStart| -> Result<Node, String> {
+        let mut node = Node {
+            name: String::from_utf8_lossy(e.name().as_ref()).into_owned(),
+            ..Node::default()
+        };
+        for attribute in e.attributes() {
+            let attribute = attribute.map_err(|e| format!("Invalid database XML: {}", e))?;
+            let value = attribute.unescape_value().map_err(xml_error)?.into_owned();
+            node.attributes.push((String::from_utf8_lossy(attribute.key.as_ref()).into_owned(), value));
+        }
+        Ok(node)
+    };
+
+    let mut reader = quick_xml::Reader::from_str(xml);
+    let mut stack = vec![Node::default()];
+    loop {
+        match reader.read_event().map_err(xml_error)? {
+            Event::Start(e) => stack.push(start_node(&e)?),
+            Event::Empty(e) => {
+                let node = start_node(&e)?;
+                if let Some(parent) = stack.last_mut() {
+                    parent.children.push(node);
+                }
+            }
+            Event::Text(e) => {
+                if let Some(node) = stack.last_mut() {
+                    node.text.push_str(&e.unescape().map_err(xml_error)?);
+                }
+            }
+            Event::CData(e) => {
+                if let Some(node) = stack.last_mut() {
+                    node.text.push_str(&String::from_utf8_lossy(&e.into_inner()));
+                }
+            }
+            Event::End(_) => {
+                let mut node = stack.pop().ok_or("Invalid database XML: unbalanced tags")?;
+                if node.attribute("Protected") == Some("True") {
+                    let mut bytes = BASE64
+                        .decode(node.text.trim())
+                        .map_err(|_| "Invalid protected value in database".to_string())?;
+                    inner_stream.apply(&mut bytes);
+                    node.text = String::from_utf8_lossy(&bytes).into_owned();
+                }
+                match stack.last_mut() {
+                    Some(parent) => parent.children.push(node),
+                    None => return Err("Invalid database XML: unbalanced tags".to_string()),
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    let document = stack.pop().filter(|_| stack.is_empty()).ok_or("Invalid database XML: unclosed tags")?;
+    document
+        .children
+        .into_iter()
+        .find(|c| c.name == "KeePassFile")
+        .ok_or_else(|| "Not a KeePass database document".to_string())
+}
+
+fn database_from_xml(xml: &str, mut inner_stream: InnerStream) -> Result<PasswordDatabase, String> {
+    let document = parse_xml(xml, &mut inner_stream)?;
+    let meta = document.child("Meta");
+    let recycle_bin = meta
+        .filter(|m| m.child_text("RecycleBinEnabled") != "False")
+        .map(|m| m.child_text("RecycleBinUUID").trim().to_string())
+        .filter(|uuid| !uuid.is_empty() && uuid != "AAAAAAAAAAAAAAAAAAAAAA==");
+
+    let root_group = document
+        .child("Root")
+        .and_then(|root| root.child("Group"))
+        .ok_or_else(|| "The database has no root group".to_string())?;
+
+    let mut database = PasswordDatabase::default();
+    // The root group's own name isn't part of the folder path
+    collect_group(root_group, "", recycle_bin.as_deref(), false, &mut database);
+    Ok(database)
+}
+
+fn collect_group(
+    group: &Node,
+    folder: &str,
+    recycle_bin: Option<&str>,
+    in_recycle_bin: bool,
+    database: &mut PasswordDatabase,
+) {
+    for entry in group.children_named("Entry") {
+        let details = entry_from_node(entry, folder);
+        if in_recycle_bin {
+            let deleted_at = decode_time(entry.child("Times").map(|t| t.child_text("LocationChanged")).unwrap_or(""))
+                .unwrap_or_else(crate::st_json::timestamp_now);
+            database.trash.push(TrashedEntry { entry: details, deleted_at });
+        } else {
+            database.passwords.push(details);
+        }
+    }
+
+    for child in group.children_named("Group") {
+        let is_bin = in_recycle_bin || recycle_bin == Some(child.child_text("UUID").trim());
+        let name = child.child_text("Name").trim().replace('/', "-");
+        let child_folder = if is_bin && !in_recycle_bin {
+            // Trashed entries keep no folder
+            String::new()
+        } else if folder.is_empty() {
+            name
+        } else {
+            format!("{}/{}", folder, name)
+        };
+        collect_group(child, &child_folder, recycle_bin, is_bin, database);
+    }
+}
+
+// Standard KeePass string fields; everything else becomes a custom field
+fn entry_strings(entry: &Node) -> Vec<(String, String, bool)> {
+    entry
+        .children_named("String")
+        .map(|s| {
+            let value = s.child("Value");
+            let protected = value.and_then(|v| v.attribute("Protected")) == Some("True")
+                || value.and_then(|v| v.attribute("ProtectInMemory")) == Some("True");
+            (
+                s.child_text("Key").to_string(),
+                value.map(|v| v.text.clone()).unwrap_or_default(),
+                protected,
+            )
+        })
+        .collect()
+}
+
+fn entry_from_node(entry: &Node, folder: &str) -> PasswordDetails {
+    let strings = entry_strings(entry);
+    let get = |key: &str| {
+        strings
+            .iter()
+            .find(|(k, _, _)| k == key)
+            .map(|(_, v, _)| v.clone())
+            .unwrap_or_default()
+    };
+
+    let mut name = get("Title");
+    if name.trim().is_empty() {
+        name = "(untitled)".to_string();
+    }
+    let mut details = crate::st_json::create_password_details(
+        &name,
+        &get("Password"),
+        &get("URL"),
+        &get("UserName"),
+        &get("Notes"),
+    );
+    details.folder = folder.to_string();
+
+    let times = entry.child("Times");
+    let time = |field: &str| decode_time(times.map(|t| t.child_text(field)).unwrap_or(""));
+    if let Some(created) = time("CreationTime") {
+        details.created_at = created;
+    }
+    details.updated_at = time("LastModificationTime").unwrap_or_default();
+
+    let mut custom_fields: Vec<CustomField> = strings
+        .iter()
+        .filter(|(k, _, _)| !matches!(k.as_str(), "Title" | "Password" | "URL" | "UserName" | "Notes"))
+        .map(|(k, v, protected)| CustomField { name: k.clone(), value: v.clone(), protected: *protected })
+        .collect();
+
+    // KeePassXC keeps one-time codes in an "otp" field as an otpauth:// URI
+    if let Some(position) = custom_fields.iter().position(|f| f.name == "otp")
+        && let Ok(config) = otp::parse_otp_input(&custom_fields[position].value)
+    {
+        details.otp = Some(config);
+        custom_fields.remove(position);
+    }
+
+    details.kind = take_entry_kind(&mut custom_fields);
+    details.derivation = crate::derive::take_profile(&mut custom_fields);
+    details.rotation_days = st_json::take_rotation_days(&mut custom_fields);
+    if details.kind == EntryKind::SecureNote {
+        details.value.clear();
+    }
+    details.custom_fields = custom_fields;
+    details.history = history_from_node(entry, &details);
+    details
+}
+
+// Rebuilds the Saltr entry type written by `kind_fields`, consuming its fields
+fn take_entry_kind(fields: &mut Vec<CustomField>) -> EntryKind {
+    let Some(position) = fields.iter().position(|f| f.name == TYPE_FIELD) else {
+        return EntryKind::Login;
+    };
+    let kind_name = fields.remove(position).value;
+    let mut take = |name: &str| match fields.iter().position(|f| f.name == name) {
+        Some(position) => fields.remove(position).value,
+        None => String::new(),
+    };
+
+    match kind_name.as_str() {
+        "secure_note" => EntryKind::SecureNote,
+        "card" => EntryKind::Card {
+            cardholder: take("Cardholder"),
+            number: take("Card Number"),
+            expiry: take("Expiry"),
+            cvv: take("CVV"),
+        },
+        "identity" => EntryKind::Identity {
+            full_name: take("Full Name"),
+            address: take("Address"),
+            phone: take("Phone"),
+            email: take("Email"),
+        },
+        "api_key" => EntryKind::ApiKey { expires_at: take("Expires") },
+        "wifi" => EntryKind::Wifi {
+            ssid: take("SSID"),
+            security: crate::wifi::WifiSecurity::from_name(&take("Security")),
+            hidden: take("Hidden") == "true",
+        },
+        _ => EntryKind::Login,
+    }
+}
+
+// KeePass history holds full snapshots of earlier versions, oldest first. Saltr
+// keeps only previous passwords, newest first, each stamped with the time it
+// was replaced (the modification time of the version that followed it).
+fn history_from_node(entry: &Node, current: &PasswordDetails) -> Vec<PasswordHistoryEntry> {
+    let Some(history) = entry.child("History") else {
+        return Vec::new();
+    };
+    let versions: Vec<(String, String)> = history
+        .children_named("Entry")
+        .map(|version| {
+            let password = entry_strings(version)
+                .into_iter()
+                .find(|(k, _, _)| k == "Password")
+                .map(|(_, v, _)| v)
+                .unwrap_or_default();
+            let modified = decode_time(version.child("Times").map(|t| t.child_text("LastModificationTime")).unwrap_or(""))
+                .unwrap_or_default();
+            (password, modified)
+        })
+        .collect();
+
+    let mut previous = Vec::new();
+    for (i, (password, _)) in versions.iter().enumerate() {
+        let (next_password, replaced_at) = match versions.get(i + 1) {
+            Some((next_password, next_modified)) => (next_password, next_modified),
+            None => (&current.value, &current.updated_at),
+        };
+        if password != next_password && !password.is_empty() {
+            previous.push(PasswordHistoryEntry {
+                sealed: String::new(),
+                value: password.clone(),
+                changed_at: replaced_at.clone(),
+            });
+        }
+    }
+    previous.reverse();
+    previous
+}
+
+fn keepass_epoch() -> NaiveDateTime {
+    NaiveDate::from_ymd_opt(1, 1, 1)
+        .and_then(|date| date.and_hms_opt(0, 0, 0))
+        .expect("0001-01-01 is a valid date")
+}
+
+// KDBX 4 stores times as base64 of a little-endian i64 counting seconds since
+// 0001-01-01; older files use ISO 8601 text
+fn decode_time(text: &str) -> Option<String> {
+    let text = text.trim();
+    if text.is_empty() {
+        return None;
+    }
+    if let Ok(bytes) = BASE64.decode(text)
+        && let Ok(bytes) = <[u8; 8]>::try_from(bytes.as_slice())
+    {
+        let seconds = chrono::TimeDelta::try_seconds(i64::from_le_bytes(bytes))?;
+        let time = keepass_epoch().checked_add_signed(seconds)?;
+        return Some(time.format(TIMESTAMP_FORMAT).to_string());
+    }
+    chrono::DateTime::parse_from_rfc3339(text)
+        .ok()
+        .map(|time| time.naive_utc().format(TIMESTAMP_FORMAT).to_string())
+}
+
+fn encode_time(timestamp: &str) -> String {
+    let time = NaiveDateTime::parse_from_str(timestamp, TIMESTAMP_FORMAT)
+        .unwrap_or_else(|_| chrono::Utc::now().naive_utc());
+    let seconds = (time - keepass_epoch()).num_seconds();
+    BASE64.encode(seconds.to_le_bytes())
+}
+
+// ---------------------------------------------------------------------------
+// Writing
+// ---------------------------------------------------------------------------
+
+// Writes the vault as a KDBX 4 database (ChaCha20, Argon2d, gzip). Folders
+// become groups; trashed entries are left out.
+pub fn write_kdbx(database: &PasswordDatabase, path: &str, password: &str) -> Result<(), String> {
+    let bytes = encrypt_kdbx(database, password)?;
+    std::fs::write(path.trim(), bytes).map_err(|e| format!("Could not write database: {}", e))
+}
+
+fn random_bytes<const N: usize>() -> [u8; N] {
+    let mut bytes = [0u8; N];
+    rand::rng().fill_bytes(&mut bytes);
+    bytes
+}
+
+fn encrypt_kdbx(database: &PasswordDatabase, password: &str) -> Result<Vec<u8>, String> {
+    let master_seed = random_bytes::<32>();
+    let encryption_iv = random_bytes::<12>();
+    let argon2_salt = random_bytes::<32>();
+    let inner_stream_key = random_bytes::<64>();
+
+    let mut kdf_parameters = Vec::new();
+    kdf_parameters.extend_from_slice(&0x0100u16.to_le_bytes());
+    write_variant(&mut kdf_parameters, 0x42, "$UUID", &KDF_ARGON2D);
+    write_variant(&mut kdf_parameters, 0x42, "S", &argon2_salt);
+    write_variant(&mut kdf_parameters, 0x05, "M", &EXPORT_ARGON2_MEMORY.to_le_bytes());
+    write_variant(&mut kdf_parameters, 0x05, "I", &EXPORT_ARGON2_ITERATIONS.to_le_bytes());
+    write_variant(&mut kdf_parameters, 0x04, "P", &EXPORT_ARGON2_PARALLELISM.to_le_bytes());
+    write_variant(&mut kdf_parameters, 0x04, "V", &0x13u32.to_le_bytes());
+    kdf_parameters.push(0);
+
+    let mut header = Vec::new();
+    header.extend_from_slice(&SIGNATURE_1.to_le_bytes());
+    header.extend_from_slice(&SIGNATURE_2.to_le_bytes());
+    header.extend_from_slice(&FILE_VERSION.to_le_bytes());
+    write_field(&mut header, HEADER_CIPHER_ID, &CIPHER_CHACHA20);
+    write_field(&mut header, HEADER_COMPRESSION, &1u32.to_le_bytes());
+    write_field(&mut header, HEADER_MASTER_SEED, &master_seed);
+    write_field(&mut header, HEADER_ENCRYPTION_IV, &encryption_iv);
+    write_field(&mut header, HEADER_KDF_PARAMETERS, &kdf_parameters);
+    write_field(&mut header, HEADER_END, b"\r\n\r\n");
+
+    let kdf = VariantDictionary::parse(&kdf_parameters)?;
+    let keys = derive_keys(password, &master_seed, &kdf)?;
+
+    let mut out = header.clone();
+    out.extend_from_slice(&Sha256::digest(&header));
+    let mut mac = block_hmac(&keys.hmac_base, u64::MAX);
+    mac.update(&header);
+    out.extend_from_slice(&mac.finalize().into_bytes());
+
+    // Inner header + XML, gzipped, then encrypted
+    let mut payload = Vec::new();
+    write_field(&mut payload, INNER_STREAM_ID, &INNER_STREAM_CHACHA20.to_le_bytes());
+    write_field(&mut payload, INNER_STREAM_KEY, &inner_stream_key);
+    write_field(&mut payload, INNER_END, &[]);
+    let mut inner_stream = InnerStream::new(INNER_STREAM_CHACHA20, &inner_stream_key)?;
+    payload.extend_from_slice(database_to_xml(database, &mut inner_stream).as_bytes());
+
+    let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+    encoder.write_all(&payload).map_err(|e| format!("Could not compress database: {}", e))?;
+    let compressed = encoder.finish().map_err(|e| format!("Could not compress database: {}", e))?;
+    let encrypted = chacha20_apply(&keys.master_key, &encryption_iv, &compressed)?;
+
+    let mut blocks: Vec<&[u8]> = encrypted.chunks(BLOCK_SIZE).collect();
+    blocks.push(&[]);
+    for (block_index, data) in blocks.into_iter().enumerate() {
+        let block_index = block_index as u64;
+        let size = (data.len() as u32).to_le_bytes();
+        let mut mac = block_hmac(&keys.hmac_base, block_index);
+        mac.update(&block_index.to_le_bytes());
+        mac.update(&size);
+        mac.update(data);
+        out.extend_from_slice(&mac.finalize().into_bytes());
+        out.extend_from_slice(&size);
+        out.extend_from_slice(data);
+    }
+    Ok(out)
+}
+
+fn write_field(out: &mut Vec<u8>, id: u8, data: &[u8]) {
+    out.push(id);
+    out.extend_from_slice(&(data.len() as u32).to_le_bytes());
+    out.extend_from_slice(data);
+}
+
+fn write_variant(out: &mut Vec<u8>, kind: u8, key: &str, value: &[u8]) {
+    out.push(kind);
+    out.extend_from_slice(&(key.len() as u32).to_le_bytes());
+    out.extend_from_slice(key.as_bytes());
+    out.extend_from_slice(&(value.len() as u32).to_le_bytes());
+    out.extend_from_slice(value);
+}
+
+fn escape_xml(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&apos;"),
+            // Control characters other than tab/newline aren't allowed in XML 1.0
+            c if c.is_control() && !matches!(c, '\t' | '\n' | '\r') => {}
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+// Folder tree built from the entries' folder paths
+#[derive(Default)]
+struct GroupTree<'a> {
+    entries: Vec<&'a PasswordDetails>,
+    groups: BTreeMap<String, GroupTree<'a>>,
+}
+
+// Builds the XML sequentially so protected values are encrypted in document order
+struct XmlWriter<'a> {
+    out: String,
+    inner_stream: &'a mut InnerStream,
+}
+
+impl XmlWriter<'_> {
+    fn element(&mut self, name: &str, value: &str) {
+        self.out.push_str(&format!("<{}>{}</{}>", name, escape_xml(value), name));
+    }
+
+    fn string_field(&mut self, key: &str, value: &str, protected: bool) {
+        self.out.push_str("<String>");
+        self.element("Key", key);
+        if protected {
+            let mut bytes = value.as_bytes().to_vec();
+            self.inner_stream.apply(&mut bytes);
+            self.out.push_str(&format!("<Value Protected=\"True\">{}</Value>", BASE64.encode(bytes)));
+        } else {
+            self.element("Value", value);
+        }
+        self.out.push_str("</String>");
+    }
+
+    fn times(&mut self, created: &str, modified: &str) {
+        let created = encode_time(created);
+        let modified = encode_time(modified);
+        self.out.push_str("<Times>");
+        self.element("CreationTime", &created);
+        self.element("LastModificationTime", &modified);
+        self.element("LastAccessTime", &modified);
+        self.element("ExpiryTime", &created);
+        self.element("Expires", "False");
+        self.element("UsageCount", "0");
+        self.element("LocationChanged", &modified);
+        self.out.push_str("</Times>");
+    }
+
+    fn group(&mut self, name: &str, tree: &GroupTree) {
+        self.out.push_str("<Group>");
+        self.element("UUID", &BASE64.encode(random_bytes::<16>()));
+        self.element("Name", name);
+        self.element("IsExpanded", "True");
+        for entry in &tree.entries {
+            self.entry(entry);
+        }
+        for (child_name, child) in &tree.groups {
+            self.group(child_name, child);
+        }
+        self.out.push_str("</Group>");
+    }
+
+    fn entry(&mut self, entry: &PasswordDetails) {
+        let uuid = BASE64.encode(random_bytes::<16>());
+        let modified = if entry.updated_at.is_empty() { &entry.created_at } else { &entry.updated_at };
+
+        self.out.push_str("<Entry>");
+        self.element("UUID", &uuid);
+        self.times(&entry.created_at, modified);
+        self.entry_strings(entry, &entry.value);
+
+        // Our history is newest first with the time each password was replaced;
+        // KeePass wants snapshots oldest first with the time each was set
+        if !entry.history.is_empty() {
+            self.out.push_str("<History>");
+            for (i, old) in entry.history.iter().enumerate().rev() {
+                let set_at = entry.history.get(i + 1).map(|older| &older.changed_at).unwrap_or(&entry.created_at);
+                self.out.push_str("<Entry>");
+                self.element("UUID", &uuid);
+                self.times(&entry.created_at, set_at);
+                self.entry_strings(entry, &old.value);
+                self.out.push_str("</Entry>");
+            }
+            self.out.push_str("</History>");
+        }
+        self.out.push_str("</Entry>");
+    }
+
+    fn entry_strings(&mut self, entry: &PasswordDetails, password: &str) {
+        self.string_field("Title", &entry.name, false);
+        self.string_field("UserName", &entry.username, false);
+        self.string_field("Password", password, true);
+        self.string_field("URL", &entry.website, false);
+        self.string_field("Notes", &entry.notes, false);
+        for (name, value, protected) in kind_fields(&entry.kind) {
+            self.string_field(name, &value, protected);
+        }
+        if let Some(config) = &entry.otp {
+            self.string_field("otp", &otp::to_otpauth_uri(config), true);
+        }
+        if let Some(profile) = &entry.derivation {
+            let field = crate::derive::profile_field(profile);
+            self.string_field(&field.name, &field.value, field.protected);
+        }
+        if entry.rotation_days > 0 {
+            let field = st_json::rotation_field(entry.rotation_days);
+            self.string_field(&field.name, &field.value, field.protected);
+        }
+        for field in &entry.custom_fields {
+            self.string_field(&field.name, &field.value, field.protected);
+        }
+    }
+}
+
+// Custom fields that carry a Saltr entry type through KeePass
+fn kind_fields(kind: &EntryKind) -> Vec<(&'static str, String, bool)> {
+    match kind {
+        EntryKind::Login => Vec::new(),
+        EntryKind::SecureNote => vec![(TYPE_FIELD, "secure_note".to_string(), false)],
+        EntryKind::Card { cardholder, number, expiry, cvv } => vec![
+            (TYPE_FIELD, "card".to_string(), false),
+            ("Cardholder", cardholder.clone(), false),
+            ("Card Number", number.clone(), true),
+            ("Expiry", expiry.clone(), false),
+            ("CVV", cvv.clone(), true),
+        ],
+        EntryKind::Identity { full_name, address, phone, email } => vec![
+            (TYPE_FIELD, "identity".to_string(), false),
+            ("Full Name", full_name.clone(), false),
+            ("Address", address.clone(), false),
+            ("Phone", phone.clone(), false),
+            ("Email", email.clone(), false),
+        ],
+        EntryKind::ApiKey { expires_at } => vec![
+            (TYPE_FIELD, "api_key".to_string(), false),
+            ("Expires", expires_at.clone(), false),
+        ],
+        EntryKind::Wifi { ssid, security, hidden } => vec![
+            (TYPE_FIELD, "wifi".to_string(), false),
+            ("SSID", ssid.clone(), false),
+            ("Security", security.name().to_string(), false),
+            ("Hidden", hidden.to_string(), false),
+        ],
+    }
+}
+
+fn database_to_xml(database: &PasswordDatabase, inner_stream: &mut InnerStream) -> String {
+    let mut tree = GroupTree::default();
+    for entry in &database.passwords {
+        let mut node = &mut tree;
+        for part in entry.folder.split('/').map(str::trim).filter(|p| !p.is_empty()) {
+            node = node.groups.entry(part.to_string()).or_default();
+        }
+        node.entries.push(entry);
+    }
+
+    let mut writer = XmlWriter { out: String::new(), inner_stream };
+    writer.out.push_str("<?xml version=\"1.0\" encoding=\"utf-8\" standalone=\"yes\"?>\n<KeePassFile><Meta>");
+    writer.element("Generator", "Saltr");
+    writer.element("DatabaseName", "Saltr");
+    writer.out.push_str("<MemoryProtection>");
+    writer.element("ProtectTitle", "False");
+    writer.element("ProtectUserName", "False");
+    writer.element("ProtectPassword", "True");
+    writer.element("ProtectURL", "False");
+    writer.element("ProtectNotes", "False");
+    writer.out.push_str("</MemoryProtection>");
+    writer.element("RecycleBinEnabled", "True");
+    writer.out.push_str("</Meta><Root>");
+    writer.group("Saltr", &tree);
+    writer.out.push_str("<DeletedObjects/></Root></KeePassFile>");
+    writer.out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::st_json::create_password_details;
+
+    fn argon2_settings(memory: u64, iterations: u64) -> VariantDictionary {
+        let mut parameters = Vec::new();
+        parameters.extend_from_slice(&0x0100u16.to_le_bytes());
+        write_variant(&mut parameters, 0x42, "$UUID", &KDF_ARGON2D);
+        write_variant(&mut parameters, 0x42, "S", &[0u8; 32]);
+        write_variant(&mut parameters, 0x05, "M", &memory.to_le_bytes());
+        write_variant(&mut parameters, 0x05, "I", &iterations.to_le_bytes());
+        write_variant(&mut parameters, 0x04, "P", &1u32.to_le_bytes());
+        parameters.push(0);
+        VariantDictionary::parse(&parameters).unwrap()
+    }
+
+    #[test]
+    fn written_database_reads_back() {
+        let mut login = create_password_details("Mail", "hunter2-correct", "mail.example.com", "me@example.com", "work account");
+        login.folder = "Work/Email".to_string();
+        login.custom_fields.push(CustomField { name: "Recovery".to_string(), value: "1234 5678".to_string(), protected: true });
+        login.derivation = Some(crate::derive::DerivationProfile {
+            site: "mail.example.com".to_string(),
+            login: "me@example.com".to_string(),
+            counter: 3,
+            ..Default::default()
+        });
+        login.rotation_days = 90;
+        let mut card = create_password_details("Visa", "", "", "", "");
+        card.kind = EntryKind::Card {
+            cardholder: "Jo Doe".to_string(),
+            number: "4111 1111 1111 1111".to_string(),
+            expiry: "12/30".to_string(),
+            cvv: "123".to_string(),
+        };
+        let mut note = create_password_details("Door code", "", "", "", "4821#");
+        note.kind = EntryKind::SecureNote;
+        let database = PasswordDatabase { passwords: vec![login, card, note], trash: Vec::new(), history_lock: None };
+
+        let path = std::env::temp_dir().join(format!("saltr-kdbx-test-{}.kdbx", std::process::id()));
+        let path = path.to_string_lossy().to_string();
+        write_kdbx(&database, &path, "correct horse").unwrap();
+        let read_back = read_kdbx(&path, "correct horse");
+        let wrong_password = read_kdbx(&path, "wrong horse");
+        std::fs::remove_file(&path).unwrap();
+
+        let read_back = read_back.unwrap();
+        assert!(wrong_password.is_err());
+        assert_eq!(read_back.passwords.len(), database.passwords.len());
+        // Export regroups entries by folder, so match them by name
+        for original in &database.passwords {
+            let entry = read_back.passwords.iter().find(|p| p.name == original.name).unwrap();
+            assert_eq!(entry.value, original.value, "{}", original.name);
+            assert_eq!(entry.website, original.website, "{}", original.name);
+            assert_eq!(entry.username, original.username, "{}", original.name);
+            assert_eq!(entry.notes, original.notes, "{}", original.name);
+            assert_eq!(entry.folder, original.folder, "{}", original.name);
+            assert_eq!(entry.kind, original.kind, "{}", original.name);
+            assert_eq!(entry.custom_fields, original.custom_fields, "{}", original.name);
+            assert_eq!(entry.derivation, original.derivation, "{}", original.name);
+            assert_eq!(entry.rotation_days, original.rotation_days, "{}", original.name);
+        }
+    }
+
+    #[test]
+    fn oversized_key_derivation_settings_are_refused() {
+        let composite = [0u8; 32];
+        let too_much_memory = argon2_settings((MAX_ARGON2_MEMORY_KIB + 1) * 1024, 1);
+        assert!(transform_key(&composite, &too_much_memory).unwrap_err().contains("memory"));
+        let too_many_iterations = argon2_settings(1024 * 1024, MAX_ARGON2_ITERATIONS + 1);
+        assert!(transform_key(&composite, &too_many_iterations).unwrap_err().contains("iterations"));
+    }
+}
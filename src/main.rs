@@ -1,11 +1,31 @@
 // main.rs - Fixed to work with your existing code structure
 
+mod audit;
+mod backup;
+mod batch;
+mod bitwarden;
+mod crypto;
+mod csv_import;
+mod derive;
+mod dice;
+mod export;
 mod genr;
+mod hibp;
+mod history;
+mod history_key;
+mod kdbx;
+mod otp;
+mod otp_import;
+mod password_rules;
+mod presets;
+mod qr;
+mod settings;
 mod st_json;
 mod viewpasswords;
+mod wifi;
 
-use iced::widget::{button, row, column, container, text, svg, Space, text_input, scrollable};
-use iced::{Element, Fill, Size};
+use iced::widget::{button, row, column, container, text, svg, Space, text_input, scrollable, pick_list, text_editor, checkbox};
+use iced::{Element, Fill, Size, Subscription, Task};
 
 // Define the pages enum
 #[derive(Debug, Clone, Default)]
@@ -14,23 +34,263 @@ enum Pages {
     Current,
     AddDetails,
     ViewPasswords,
+    // Detail/edit page for the entry at this position in loaded_passwords
+    EntryDetails(usize),
+    ImportOtp,
+    ImportCsv,
+    KeePass,
+    Bitwarden,
+    Backup,
+    Export,
+    Batch,
+    // Passwords the generator made recently
+    GeneratorHistory,
+    Security,
+    Trash,
     Settings,
 }
 
+// Entry types offered on the AddDetails form
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+enum EntryType {
+    #[default]
+    Login,
+    SecureNote,
+    Card,
+    Identity,
+    ApiKey,
+    Wifi,
+}
+
+impl EntryType {
+    const ALL: [EntryType; 6] = [
+        EntryType::Login,
+        EntryType::SecureNote,
+        EntryType::Card,
+        EntryType::Identity,
+        EntryType::ApiKey,
+        EntryType::Wifi,
+    ];
+}
+
+impl std::fmt::Display for EntryType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            EntryType::Login => "Login",
+            EntryType::SecureNote => "Secure Note",
+            EntryType::Card => "Payment Card",
+            EntryType::Identity => "Identity",
+            EntryType::ApiKey => "API Key",
+            EntryType::Wifi => "Wi-Fi Network",
+        })
+    }
+}
+
+// How the generator page makes a password
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+enum GeneratorMode {
+    #[default]
+    Random,
+    // Worked out from a master password, site, login and counter (LessPass)
+    Derived,
+    // Follows a pattern like "[A-Z]{4}-\d{4}"
+    Pattern,
+    // Alternating consonants and vowels, easy to read aloud
+    Pronounceable,
+    // Random words from the word list
+    Passphrase,
+    // Words or characters from physical dice rolls or card draws
+    Dice,
+}
+
+impl GeneratorMode {
+    const ALL: [GeneratorMode; 6] = [
+        GeneratorMode::Random,
+        GeneratorMode::Derived,
+        GeneratorMode::Pattern,
+        GeneratorMode::Pronounceable,
+        GeneratorMode::Passphrase,
+        GeneratorMode::Dice,
+    ];
+}
+
+impl std::fmt::Display for GeneratorMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            GeneratorMode::Random => "Random",
+            GeneratorMode::Derived => "Derived (LessPass)",
+            GeneratorMode::Pattern => "Pattern",
+            GeneratorMode::Pronounceable => "Pronounceable",
+            GeneratorMode::Passphrase => "Passphrase",
+            GeneratorMode::Dice => "Dice or cards",
+        })
+    }
+}
+
 // Define the message enum to represent possible user actions
 #[derive(Debug, Clone)]
 enum Message {
     Copy,
     Reload,
     Save,
+    // Generator page modes
+    GeneratorModeSelected(GeneratorMode),
+    PresetSelected(String),
+    RandomLengthChanged(String),
+    DeriveSiteChanged(String),
+    DeriveLoginChanged(String),
+    DeriveMasterChanged(String),
+    DeriveCounterChanged(String),
+    DeriveLengthChanged(String),
+    DeriveClassToggled(derive::CharacterClass, bool),
+    PatternChanged(String),
+    PronounceableLengthChanged(String),
+    PronounceableDigitsChanged(String),
+    PronounceableSymbolsChanged(String),
+    PassphraseWordsChanged(String),
+    PassphraseSeparatorChanged(String),
+    DiceSourceSelected(dice::DiceSource),
+    DiceOutputSelected(dice::DiceOutput),
+    DiceLengthChanged(String),
+    DiceRollsChanged(String),
+    // Batch page
+    BatchKindSelected(batch::BatchKind),
+    BatchLengthChanged(String),
+    BatchCountChanged(String),
+    BatchNamesChanged(String),
+    BatchPathChanged(String),
+    GenerateBatch,
+    CopyBatchCsv,
+    SaveBatchCsv,
+    SaveBatchEntries,
+    // Generator history page
+    ToggleShowGeneratorHistory,
+    CopyFromGeneratorHistory(usize),
+    SaveFromGeneratorHistory(usize),
+    RemoveFromGeneratorHistory(usize),
+    ClearGeneratorHistory,
     NavigateTo(Pages),
     // Form input messages
     PasswordNameChanged(String),
     PasswordChanged(String),
+    GenerateForSite,
     WebsiteChanged(String),
     UsernameChanged(String),
+    GenerateUsername(genr::UsernameStyle),
+    GenerateEmailAlias(genr::AliasKind),
     NotesChanged(String),
+    EntryTypeSelected(EntryType),
+    SecureNoteEdited(text_editor::Action),
+    CardholderChanged(String),
+    CardNumberChanged(String),
+    CardExpiryChanged(String),
+    CardCvvChanged(String),
+    FullNameChanged(String),
+    AddressChanged(String),
+    PhoneChanged(String),
+    EmailChanged(String),
+    ApiKeyExpiryChanged(String),
+    WifiSsidChanged(String),
+    WifiSecuritySelected(wifi::WifiSecurity),
+    WifiHiddenToggled(bool),
+    GenerateWpaKey,
+    OtpInputChanged(String),
+    OtpKindSelected(otp::OtpKind),
+    OtpAlgorithmSelected(otp::OtpAlgorithm),
+    OtpDigitsSelected(u32),
+    OtpPeriodChanged(String),
     SavePasswordDetails,
+    // One-time codes on the View Passwords page
+    CopyOtp(usize),
+    NextHotpCode(usize),
+    Tick,
+    // Importing one-time code secrets
+    OtpImagePathChanged(String),
+    OtpImportTextChanged(String),
+    ScanOtpImage,
+    ParseOtpImportText,
+    ConfirmOtpImport,
+    // CSV import wizard
+    CsvPathChanged(String),
+    LoadCsv,
+    CsvSourceSelected(csv_import::CsvSource),
+    CsvMappingChanged(usize, csv_import::CsvField),
+    ConfirmCsvImport,
+    // KeePass import/export
+    KdbxPathChanged(String),
+    KdbxPasswordChanged(String),
+    KdbxConfirmPasswordChanged(String),
+    ImportKdbx,
+    ExportKdbx,
+    // Bitwarden import/export
+    BitwardenPathChanged(String),
+    BitwardenPasswordChanged(String),
+    BitwardenConfirmPasswordChanged(String),
+    BitwardenExportConfirmationChanged(String),
+    ImportBitwarden,
+    ExportBitwarden,
+    // Backup archives
+    BackupPathChanged(String),
+    BackupPasswordChanged(String),
+    BackupConfirmPasswordChanged(String),
+    CreateBackup,
+    OpenBackup,
+    RestoreBackup(backup::RestoreMode),
+    // Unencrypted export
+    ExportFormatSelected(export::ExportFormat),
+    ExportFieldToggled(export::ExportField, bool),
+    ExportPathChanged(String),
+    ExportConfirmationChanged(String),
+    ExportPlain,
+    // Entry detail page
+    OpenEntry(usize),
+    EditNameChanged(String),
+    EditValueChanged(String),
+    EditWebsiteChanged(String),
+    EditUsernameChanged(String),
+    EditNotesChanged(String),
+    EditFolderChanged(String),
+    EditRotationChanged(String),
+    // Derived entries: master password and counter to work the password out again
+    EditMasterChanged(String),
+    EditCounterChanged(String),
+    DeriveEntryPassword,
+    ToggleShowSecret,
+    CopyEntryValue,
+    CopyHistoryValue(usize),
+    // Password history: the history password, to unlock it or to set it the first time
+    HistoryPasswordChanged(String),
+    UnlockHistory,
+    // Wi-Fi entries: where to save the QR code
+    WifiQrPathChanged(String),
+    ExportWifiQr,
+    SaveEntryChanges,
+    // Trash
+    MoveToTrash,
+    RestoreFromTrash(usize),
+    PurgeFromTrash(usize),
+    EmptyTrash,
+    // Settings page
+    MaxHistoryChanged(String),
+    TrashRetentionChanged(String),
+    AuditMaxAgeChanged(String),
+    HibpPathChanged(String),
+    AliasEmailChanged(String),
+    CatchAllDomainChanged(String),
+    GeneratorHistorySizeChanged(String),
+    GeneratorHistoryMinutesChanged(String),
+    SaveSettings,
+    // Generator presets on the Settings page
+    PresetNameChanged(String),
+    SavePreset,
+    RemovePreset(usize),
+    PresetsPathChanged(String),
+    ExportPresets,
+    ImportPresets,
+    // Rotation reminders
+    ToggleDueOnly(bool),
+    ShowDueForRotation,
+    DismissRotationNotice,
     // New message for loading passwords
     LoadPasswordsFromFile,
 }
@@ -40,12 +300,144 @@ enum Message {
 struct PasswordGenerator {
     current_page: Pages,
     generated_password: String,
+    // Derived mode works the password out from these instead of picking it at
+    // random; empty counter and length inputs mean the profile's defaults
+    generator_mode: GeneratorMode,
+    // Random mode's length; empty means 16
+    random_length_input: String,
+    derive_profile: derive::DerivationProfile,
+    derive_master: String,
+    derive_counter_input: String,
+    derive_length_input: String,
+    // Pattern mode's pattern, e.g. "[A-Z]{4}-\d{4}"
+    pattern_input: String,
+    // Pronounceable mode; empty inputs use genr::PronounceableOptions::default()
+    pronounceable_length_input: String,
+    pronounceable_digits_input: String,
+    pronounceable_symbols_input: String,
+    // Passphrase mode; empty inputs use the defaults
+    passphrase_words_input: String,
+    passphrase_separator_input: String,
+    // Dice mode; the rolls or cards as typed, never saved
+    dice_source: dice::DiceSource,
+    dice_output: dice::DiceOutput,
+    dice_length_input: String,
+    dice_rolls_input: String,
+    // Batch page; the policy inputs above are shared with the generator page
+    batch_kind: batch::BatchKind,
+    batch_length_input: String,
+    batch_count_input: String,
+    batch_names_input: String,
+    batch_path_input: String,
+    batch_items: Vec<batch::BatchItem>,
+    // Recently generated passwords, sealed in memory; the values only show when asked
+    generator_history: history::GeneratorHistory,
+    show_generator_history: bool,
+    // Opens the old passwords in entry histories; set once the history password is entered
+    history_key: Option<history_key::HistoryKey>,
+    history_password_input: String,
+    // Profile of a derived password on its way to the add form; saved instead of the password
+    pending_derivation: Option<derive::DerivationProfile>,
     // Form fields for password details
     password_name: String,
     saved_password: String,
+    // Shown under the password on the add form when it is in the Pwned Passwords list
+    breach_warning: String,
+    // Known password rules for the add form's website, with the matching domain
+    site_rules: Option<(String, password_rules::PasswordRules)>,
+    // True while the add form's password is one the generator made, so it can
+    // be regenerated to fit the site's rules without losing anything typed
+    password_from_generator: bool,
     website: String,
     username: String,
     notes: String,
+    // Type-specific form fields
+    entry_type: EntryType,
+    secure_note: text_editor::Content,
+    cardholder: String,
+    card_number: String,
+    card_expiry: String,
+    card_cvv: String,
+    full_name: String,
+    address: String,
+    phone: String,
+    email: String,
+    api_key_expiry: String,
+    wifi_ssid: String,
+    wifi_security: wifi::WifiSecurity,
+    wifi_hidden: bool,
+    // One-time code settings; the options only apply to bare base32 secrets
+    otp_input: String,
+    otp_kind: otp::OtpKind,
+    otp_algorithm: otp::OtpAlgorithm,
+    otp_digits: Option<u32>,
+    otp_period: String,
+    // One-time code import page
+    otp_image_path: String,
+    otp_import_text: String,
+    otp_import_preview: Vec<otp::OtpConfig>,
+    // CSV import wizard: the loaded file, its column mapping and the resulting entries
+    csv_path: String,
+    csv_source: csv_import::CsvSource,
+    csv_file: Option<csv_import::CsvFile>,
+    csv_mapping: Vec<csv_import::CsvField>,
+    csv_preview: Vec<st_json::PasswordDetails>,
+    csv_skipped: Vec<String>,
+    // KeePass page
+    kdbx_path: String,
+    kdbx_password: String,
+    kdbx_confirm_password: String,
+    // Bitwarden page; the password is only used for password-protected exports
+    bitwarden_path: String,
+    bitwarden_password: String,
+    bitwarden_confirm_password: String,
+    // Typed export::CONFIRMATION_PHRASE; needed when the export has no password
+    bitwarden_export_confirmation: String,
+    // Backup page; an opened backup waits here until it is merged or restored
+    backup_path: String,
+    backup_password: String,
+    backup_confirm_password: String,
+    backup_preview: Option<backup::BackupContents>,
+    // Export page; the confirmation must match export::CONFIRMATION_PHRASE
+    export_format: export::ExportFormat,
+    export_fields: Vec<export::ExportField>,
+    export_path: String,
+    export_confirmation: String,
+    // Entry detail page fields, filled from the entry when it is opened
+    edit_name: String,
+    edit_value: String,
+    edit_website: String,
+    edit_username: String,
+    edit_notes: String,
+    edit_folder: String,
+    edit_rotation_days: String,
+    edit_master: String,
+    edit_counter: String,
+    wifi_qr_path: String,
+    show_secret: bool,
+    // Settings and the text of the settings form
+    settings: settings::Settings,
+    // The Pwned Passwords list from Settings, opened once when its path is set
+    pwned_passwords: Option<hibp::PwnedPasswords>,
+    max_history_input: String,
+    trash_retention_input: String,
+    audit_max_age_input: String,
+    hibp_path_input: String,
+    alias_email_input: String,
+    catch_all_domain_input: String,
+    generator_history_size_input: String,
+    generator_history_minutes_input: String,
+    // Settings page preset form and the file presets are exported to or imported from
+    preset_name_input: String,
+    presets_path_input: String,
+    // Findings shown on the Security page, for the entries in loaded_passwords
+    audit_report: audit::AuditReport,
+    // Items shown on the Trash page
+    trash: Vec<st_json::TrashedEntry>,
+    // View Passwords only lists entries due or overdue for rotation
+    due_only: bool,
+    // Shown on the generator page when passwords are overdue at startup
+    rotation_notice: String,
     // Add status message for user feedback
     status_message: String,
     // Add field to store loaded passwords - using the existing PasswordDetails from st_json
@@ -54,10 +446,80 @@ struct PasswordGenerator {
 
 // The main entry point of the application
 pub fn main() -> iced::Result {
+    // `pass_gen export ...` runs the plain export from the command line instead of opening the window
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.first().map(String::as_str) == Some("export") {
+        if let Err(e) = export::run_cli(&args[1..]) {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+    // `pass_gen batch ...` prints a batch of passwords as CSV
+    if args.first().map(String::as_str) == Some("batch") {
+        if let Err(e) = batch::run_cli(&args[1..]) {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
     iced::application("Saltr", update, view)
         .window_size(Size::new(700.0, 600.0))
         .resizable(false)
-        .run()
+        .subscription(subscription)
+        .run_with(init)
+}
+
+// Builds the initial state, loading saved settings
+fn init() -> (PasswordGenerator, Task<Message>) {
+    let mut password_generator = PasswordGenerator::default();
+    match settings::load_settings(settings::SETTINGS_FILE) {
+        Ok(settings) => password_generator.settings = settings,
+        Err(e) => println!("Error loading settings, using defaults: {}", e),
+    }
+    open_pwned_passwords(&mut password_generator);
+    match st_json::purge_expired_trash(password_generator.settings.trash_retention_days, "passwords.json") {
+        Ok(0) => {}
+        Ok(purged) => println!("Purged {} expired items from the trash", purged),
+        Err(e) => println!("Error purging trash: {}", e),
+    }
+    if let Ok(database) = st_json::load_password_database("passwords.json") {
+        let overdue = audit::overdue_names(&database.passwords);
+        if !overdue.is_empty() {
+            password_generator.rotation_notice = format!(
+                "{} password{} overdue for rotation: {}",
+                overdue.len(),
+                if overdue.len() == 1 { " is" } else { "s are" },
+                overdue.join(", ")
+            );
+            println!("{}", password_generator.rotation_notice);
+        }
+    }
+    (password_generator, Task::none())
+}
+
+// Reloads the Trash page list from disk
+fn load_trash(password_generator: &mut PasswordGenerator) {
+    match st_json::load_password_database("passwords.json") {
+        Ok(database) => password_generator.trash = database.trash,
+        Err(e) => {
+            password_generator.status_message = format!("Error loading trash: {}", e);
+            password_generator.trash.clear();
+        }
+    }
+}
+
+// Ticks once a second while one-time codes are on screen so the countdown stays live
+fn subscription(password_generator: &PasswordGenerator) -> Subscription<Message> {
+    let showing_codes = matches!(password_generator.current_page, Pages::ViewPasswords)
+        && password_generator.loaded_passwords.iter().any(|p| p.otp.is_some());
+
+    if showing_codes {
+        iced::time::every(std::time::Duration::from_secs(1)).map(|_| Message::Tick)
+    } else {
+        Subscription::none()
+    }
 }
 
 // The update function handles messages and updates the state accordingly
@@ -70,11 +532,81 @@ fn update(password_generator: &mut PasswordGenerator, message: Message) {
             println!("Copied to clipboard");
         }
         Message::Reload => {
-            genr::generate_password(&mut password_generator.generated_password, 16);
+            match password_generator.generator_mode {
+                GeneratorMode::Random => match random_length(password_generator) {
+                    Ok(length) => {
+                        genr::generate_password(&mut rand::rng(), &mut password_generator.generated_password, length);
+                        password_generator.status_message.clear();
+                    }
+                    Err(e) => {
+                        password_generator.generated_password.clear();
+                        password_generator.status_message = e;
+                    }
+                },
+                GeneratorMode::Derived => derive_generator_password(password_generator),
+                GeneratorMode::Pattern => match genr::parse_pattern(&password_generator.pattern_input) {
+                    Ok(pattern) => {
+                        password_generator.generated_password = genr::generate_from_pattern(&mut rand::rng(), &pattern);
+                        password_generator.status_message.clear();
+                    }
+                    Err(e) => {
+                        password_generator.generated_password.clear();
+                        password_generator.status_message = e;
+                    }
+                },
+                GeneratorMode::Passphrase => {
+                    match passphrase_options(password_generator).and_then(|(words, separator)| genr::generate_passphrase(&mut rand::rng(), words, &separator)) {
+                        Ok(password) => {
+                            password_generator.generated_password = password;
+                            password_generator.status_message.clear();
+                        }
+                        Err(e) => {
+                            password_generator.generated_password.clear();
+                            password_generator.status_message = e;
+                        }
+                    }
+                }
+                GeneratorMode::Dice => dice_generator_password(password_generator),
+                GeneratorMode::Pronounceable => {
+                    match pronounceable_options(password_generator).and_then(|options| genr::generate_pronounceable(&mut rand::rng(), &options)) {
+                        Ok(password) => {
+                            password_generator.generated_password = password;
+                            password_generator.status_message.clear();
+                        }
+                        Err(e) => {
+                            password_generator.generated_password.clear();
+                            password_generator.status_message = e;
+                        }
+                    }
+                }
+            }
+            // Derived passwords can always be worked out again, so only the others are kept
+            if password_generator.generator_mode != GeneratorMode::Derived {
+                let password = password_generator.generated_password.clone();
+                let source = password_generator.generator_mode.to_string();
+                remember_generated(password_generator, &password, "", &source);
+            }
             println!("Reload button has been clicked");
         }
         Message::Save => {
+            if password_generator.generated_password.is_empty() {
+                password_generator.status_message = "Generate a password first".to_string();
+                return;
+            }
             password_generator.saved_password = password_generator.generated_password.clone();
+            if password_generator.generator_mode == GeneratorMode::Derived {
+                // Only the profile gets saved, so the form starts from its site and login
+                let profile = password_generator.derive_profile.clone();
+                password_generator.website = profile.site.clone();
+                password_generator.username = profile.login.clone();
+                password_generator.site_rules = None;
+                password_generator.password_from_generator = false;
+                password_generator.pending_derivation = Some(profile);
+            } else {
+                password_generator.password_from_generator = true;
+                password_generator.pending_derivation = None;
+            }
+            check_breach(password_generator);
             password_generator.current_page = Pages::AddDetails;
             password_generator.status_message.clear();
             println!("Save button has been clicked");
@@ -93,6 +625,61 @@ fn update(password_generator: &mut PasswordGenerator, message: Message) {
                     }
                 }
             }
+            if matches!(page, Pages::Current | Pages::AddDetails | Pages::ImportOtp | Pages::ImportCsv | Pages::KeePass | Pages::Bitwarden | Pages::Backup | Pages::Export | Pages::Batch | Pages::GeneratorHistory | Pages::Security | Pages::Trash | Pages::Settings) {
+                password_generator.status_message.clear();
+            }
+            if matches!(page, Pages::GeneratorHistory) {
+                password_generator.generator_history.expire(
+                    chrono::Utc::now().naive_utc(),
+                    password_generator.settings.generator_history_minutes,
+                );
+                password_generator.show_generator_history = false;
+            }
+            if matches!(page, Pages::Trash) {
+                load_trash(password_generator);
+            }
+            if matches!(page, Pages::Security) {
+                match viewpasswords::load_passwords_from_json() {
+                    Ok(passwords) => password_generator.loaded_passwords = passwords,
+                    Err(e) => {
+                        password_generator.status_message = format!("Error loading passwords: {}", e);
+                        password_generator.loaded_passwords.clear();
+                    }
+                }
+                password_generator.audit_report = audit::audit(
+                    &password_generator.loaded_passwords,
+                    password_generator.settings.audit_max_age_months,
+                );
+                if let Some(pwned) = &password_generator.pwned_passwords {
+                    match pwned.breached_entries(&password_generator.loaded_passwords) {
+                        Ok(breached) => password_generator.audit_report.breached = breached,
+                        Err(e) => password_generator.status_message = e,
+                    }
+                } else if !password_generator.settings.hibp_path.is_empty() {
+                    password_generator.status_message =
+                        "Could not open the Pwned Passwords list; check its path in Settings".to_string();
+                }
+            }
+            if matches!(page, Pages::Bitwarden) {
+                password_generator.bitwarden_export_confirmation.clear();
+            }
+            if matches!(page, Pages::Export) {
+                // Always ask again, even if the page was used before
+                password_generator.export_confirmation.clear();
+                if password_generator.export_fields.is_empty() {
+                    password_generator.export_fields = export::ExportField::DEFAULT.to_vec();
+                }
+            }
+            if matches!(page, Pages::Settings) {
+                password_generator.max_history_input = password_generator.settings.max_password_history.to_string();
+                password_generator.trash_retention_input = password_generator.settings.trash_retention_days.to_string();
+                password_generator.audit_max_age_input = password_generator.settings.audit_max_age_months.to_string();
+                password_generator.hibp_path_input = password_generator.settings.hibp_path.clone();
+                password_generator.alias_email_input = password_generator.settings.alias_email.clone();
+                password_generator.catch_all_domain_input = password_generator.settings.catch_all_domain.clone();
+                password_generator.generator_history_size_input = password_generator.settings.generator_history_size.to_string();
+                password_generator.generator_history_minutes_input = password_generator.settings.generator_history_minutes.to_string();
+            }
             password_generator.current_page = page;
             println!("Navigated to: {:?}", password_generator.current_page);
         }
@@ -109,313 +696,4279 @@ fn update(password_generator: &mut PasswordGenerator, message: Message) {
             }
         }
         // Handle form input changes
+        Message::GeneratorModeSelected(mode) => {
+            password_generator.generator_mode = mode;
+            password_generator.generated_password.clear();
+            password_generator.status_message.clear();
+        }
+        // A derived password no longer matches once its inputs change
+        Message::DeriveSiteChanged(value) => {
+            password_generator.derive_profile.site = value;
+            password_generator.generated_password.clear();
+        }
+        Message::DeriveLoginChanged(value) => {
+            password_generator.derive_profile.login = value;
+            password_generator.generated_password.clear();
+        }
+        Message::DeriveMasterChanged(value) => {
+            password_generator.derive_master = value;
+            password_generator.generated_password.clear();
+        }
+        Message::DeriveCounterChanged(value) => {
+            password_generator.derive_counter_input = value;
+            password_generator.generated_password.clear();
+        }
+        Message::DeriveLengthChanged(value) => {
+            password_generator.derive_length_input = value;
+            password_generator.generated_password.clear();
+        }
+        Message::DeriveClassToggled(class, used) => {
+            password_generator.derive_profile.set_uses(class, used);
+            password_generator.generated_password.clear();
+        }
+        Message::PresetSelected(name) => {
+            let Some(preset) = password_generator.settings.presets.iter().find(|preset| preset.name == name).cloned() else {
+                return;
+            };
+            apply_preset(password_generator, &preset.policy);
+            update(password_generator, Message::Reload);
+        }
+        Message::RandomLengthChanged(value) => {
+            password_generator.random_length_input = value;
+        }
+        Message::PatternChanged(value) => {
+            password_generator.pattern_input = value;
+            password_generator.status_message.clear();
+        }
+        Message::PronounceableLengthChanged(value) => {
+            password_generator.pronounceable_length_input = value;
+        }
+        Message::PronounceableDigitsChanged(value) => {
+            password_generator.pronounceable_digits_input = value;
+        }
+        Message::PronounceableSymbolsChanged(value) => {
+            password_generator.pronounceable_symbols_input = value;
+        }
+        Message::PassphraseWordsChanged(value) => {
+            password_generator.passphrase_words_input = value;
+        }
+        Message::PassphraseSeparatorChanged(value) => {
+            password_generator.passphrase_separator_input = value;
+        }
+        // The password fills in as soon as there are enough rolls
+        Message::DiceSourceSelected(source) => {
+            password_generator.dice_source = source;
+            dice_generator_password(password_generator);
+        }
+        Message::DiceOutputSelected(output) => {
+            password_generator.dice_output = output;
+            dice_generator_password(password_generator);
+        }
+        Message::DiceLengthChanged(value) => {
+            password_generator.dice_length_input = value;
+            dice_generator_password(password_generator);
+        }
+        Message::DiceRollsChanged(value) => {
+            password_generator.dice_rolls_input = value;
+            dice_generator_password(password_generator);
+        }
+        Message::BatchKindSelected(kind) => {
+            password_generator.batch_kind = kind;
+            password_generator.batch_items.clear();
+        }
+        Message::BatchLengthChanged(value) => {
+            password_generator.batch_length_input = value;
+        }
+        Message::BatchCountChanged(value) => {
+            password_generator.batch_count_input = value;
+        }
+        Message::BatchNamesChanged(value) => {
+            password_generator.batch_names_input = value;
+        }
+        Message::BatchPathChanged(value) => {
+            password_generator.batch_path_input = value;
+        }
+        Message::GenerateBatch => {
+            let Ok(count) = password_generator.batch_count_input.trim().parse::<usize>() else {
+                password_generator.status_message = "How many must be a whole number".to_string();
+                return;
+            };
+            match batch_policy(password_generator)
+                .and_then(|policy| batch::generate_batch(&mut rand::rng(), &policy, count, &password_generator.batch_names_input))
+            {
+                Ok(items) => {
+                    password_generator.status_message = format!("{} passwords generated successfully", items.len());
+                    password_generator.batch_items = items;
+                }
+                Err(e) => {
+                    password_generator.batch_items.clear();
+                    password_generator.status_message = e;
+                }
+            }
+        }
+        Message::CopyBatchCsv => {
+            match batch::to_csv(&password_generator.batch_items) {
+                Ok(csv_text) => {
+                    let mut clipboard = arboard::Clipboard::new().expect("Failed to create clipboard");
+                    clipboard.set_text(csv_text)
+                        .expect("Failed to set clipboard text");
+                    password_generator.status_message = "CSV copied to clipboard successfully".to_string();
+                }
+                Err(e) => password_generator.status_message = e,
+            }
+        }
+        Message::SaveBatchCsv => {
+            let path = password_generator.batch_path_input.trim().to_string();
+            if path.is_empty() {
+                password_generator.status_message = "Enter a file to save the CSV to".to_string();
+                return;
+            }
+            let result = batch::to_csv(&password_generator.batch_items)
+                .and_then(|csv_text| std::fs::write(&path, csv_text).map_err(|e| format!("Could not write {}: {}", path, e)));
+            match result {
+                Ok(_) => {
+                    password_generator.status_message = format!(
+                        "{} passwords saved to {} successfully",
+                        password_generator.batch_items.len(),
+                        path
+                    );
+                }
+                Err(e) => password_generator.status_message = e,
+            }
+        }
+        Message::SaveBatchEntries => {
+            match batch::save_as_entries(&password_generator.batch_items, "passwords.json") {
+                Ok((added, duplicates)) => {
+                    password_generator.status_message = format!(
+                        "{} entries created successfully ({} already there)",
+                        added, duplicates
+                    );
+                }
+                Err(e) => password_generator.status_message = e,
+            }
+        }
+        Message::ToggleShowGeneratorHistory => {
+            password_generator.show_generator_history = !password_generator.show_generator_history;
+        }
+        Message::CopyFromGeneratorHistory(index) => {
+            match recall_generated(password_generator, index) {
+                Ok(password) => {
+                    let mut clipboard = arboard::Clipboard::new().expect("Failed to create clipboard");
+                    clipboard.set_text(password)
+                        .expect("Failed to set clipboard text");
+                    password_generator.status_message = "Password copied to clipboard successfully".to_string();
+                }
+                Err(e) => password_generator.status_message = e,
+            }
+        }
+        Message::SaveFromGeneratorHistory(index) => {
+            let password = match recall_generated(password_generator, index) {
+                Ok(password) => password,
+                Err(e) => {
+                    password_generator.status_message = e;
+                    return;
+                }
+            };
+            // Same as saving from the generator, with the website it was made for if known
+            let website = password_generator.generator_history.entries()[index].website.clone();
+            if !website.is_empty() {
+                password_generator.website = website;
+            }
+            password_generator.site_rules = password_rules::rules_for_website(&password_generator.website);
+            password_generator.saved_password = password;
+            password_generator.password_from_generator = true;
+            password_generator.pending_derivation = None;
+            check_breach(password_generator);
+            password_generator.current_page = Pages::AddDetails;
+            password_generator.status_message.clear();
+        }
+        Message::RemoveFromGeneratorHistory(index) => {
+            password_generator.generator_history.remove(index);
+        }
+        Message::ClearGeneratorHistory => {
+            password_generator.generator_history.clear();
+            password_generator.status_message = "Generator history cleared successfully".to_string();
+        }
         Message::PasswordNameChanged(value) => {
             password_generator.password_name = value;
         }
         Message::PasswordChanged(value) => {
             password_generator.saved_password = value;
+            password_generator.password_from_generator = false;
+            password_generator.pending_derivation = None;
+            check_breach(password_generator);
         }
         Message::WebsiteChanged(value) => {
             password_generator.website = value;
+            password_generator.site_rules = password_rules::rules_for_website(&password_generator.website);
+            // A generated password the site wouldn't accept is swapped for one that fits
+            if let Some((domain, rules)) = &password_generator.site_rules
+                && password_generator.password_from_generator
+                && !rules.problems(&password_generator.saved_password).is_empty()
+            {
+                match password_rules::generate(&mut rand::rng(), rules, 16) {
+                    Ok(password) => {
+                        password_generator.status_message = format!("Generated a new password that fits {}'s rules", domain);
+                        password_generator.saved_password = password;
+                        let (password, website) = (password_generator.saved_password.clone(), password_generator.website.clone());
+                        remember_generated(password_generator, &password, &website, "Site rules");
+                        check_breach(password_generator);
+                    }
+                    Err(e) => password_generator.status_message = format!("{}: {}", domain, e),
+                }
+            }
+        }
+        Message::GenerateForSite => {
+            let Some((domain, rules)) = &password_generator.site_rules else {
+                return;
+            };
+            let password = match password_rules::generate(&mut rand::rng(), rules, 16) {
+                Ok(password) => password,
+                Err(e) => {
+                    password_generator.status_message = format!("{}: {}", domain, e);
+                    return;
+                }
+            };
+            password_generator.saved_password = password;
+            password_generator.password_from_generator = true;
+            password_generator.pending_derivation = None;
+            let (password, website) = (password_generator.saved_password.clone(), password_generator.website.clone());
+            remember_generated(password_generator, &password, &website, "Site rules");
+            check_breach(password_generator);
         }
         Message::UsernameChanged(value) => {
             password_generator.username = value;
         }
+        Message::GenerateUsername(style) => {
+            password_generator.username = genr::generate_username(&mut rand::rng(), style);
+        }
+        Message::GenerateEmailAlias(kind) => {
+            match genr::generate_email_alias(
+                &mut rand::rng(),
+                kind,
+                &password_generator.settings.alias_email,
+                &password_generator.settings.catch_all_domain,
+            ) {
+                Ok(alias) => password_generator.username = alias,
+                Err(e) => password_generator.status_message = e,
+            }
+        }
         Message::NotesChanged(value) => {
             password_generator.notes = value;
         }
-        Message::SavePasswordDetails => {
-            if password_generator.password_name.trim().is_empty() || 
-               password_generator.saved_password.trim().is_empty() {
-                password_generator.status_message = "Please fill in all required fields".to_string();
+        Message::EntryTypeSelected(entry_type) => {
+            password_generator.entry_type = entry_type;
+            password_generator.status_message.clear();
+        }
+        Message::SecureNoteEdited(action) => {
+            password_generator.secure_note.perform(action);
+        }
+        Message::CardholderChanged(value) => {
+            password_generator.cardholder = value;
+        }
+        Message::CardNumberChanged(value) => {
+            password_generator.card_number = value;
+        }
+        Message::CardExpiryChanged(value) => {
+            password_generator.card_expiry = value;
+        }
+        Message::CardCvvChanged(value) => {
+            password_generator.card_cvv = value;
+        }
+        Message::FullNameChanged(value) => {
+            password_generator.full_name = value;
+        }
+        Message::AddressChanged(value) => {
+            password_generator.address = value;
+        }
+        Message::PhoneChanged(value) => {
+            password_generator.phone = value;
+        }
+        Message::EmailChanged(value) => {
+            password_generator.email = value;
+        }
+        Message::ApiKeyExpiryChanged(value) => {
+            password_generator.api_key_expiry = value;
+        }
+        Message::WifiSsidChanged(value) => {
+            password_generator.wifi_ssid = value;
+        }
+        Message::WifiSecuritySelected(security) => {
+            password_generator.wifi_security = security;
+        }
+        Message::WifiHiddenToggled(hidden) => {
+            password_generator.wifi_hidden = hidden;
+        }
+        Message::GenerateWpaKey => {
+            // The longest passphrase WPA allows, from letters and digits so it's easy to type on any device
+            password_generator.saved_password = genr::random_password(&mut rand::rng(), wifi::WPA_KEY_LENGTH);
+            password_generator.password_from_generator = true;
+            password_generator.pending_derivation = None;
+            let password = password_generator.saved_password.clone();
+            remember_generated(password_generator, &password, "", "WPA key");
+            check_breach(password_generator);
+        }
+        Message::OtpInputChanged(value) => {
+            password_generator.otp_input = value;
+        }
+        Message::OtpKindSelected(kind) => {
+            password_generator.otp_kind = kind;
+        }
+        Message::OtpAlgorithmSelected(algorithm) => {
+            password_generator.otp_algorithm = algorithm;
+        }
+        Message::OtpDigitsSelected(digits) => {
+            password_generator.otp_digits = Some(digits);
+        }
+        Message::OtpPeriodChanged(value) => {
+            password_generator.otp_period = value;
+        }
+        Message::CopyOtp(index) => {
+            let code = password_generator.loaded_passwords
+                .get(index)
+                .and_then(|p| p.otp.as_ref())
+                .map(|config| otp::current_code(config, otp::unix_now()));
+
+            match code {
+                Some(Ok((code, _))) => {
+                    let mut clipboard = arboard::Clipboard::new().expect("Failed to create clipboard");
+                    clipboard.set_text(&code)
+                        .expect("Failed to set clipboard text");
+                    password_generator.status_message = "Code copied to clipboard".to_string();
+                }
+                Some(Err(e)) => {
+                    password_generator.status_message = format!("Error generating code: {}", e);
+                }
+                None => {}
+            }
+        }
+        Message::NextHotpCode(index) => {
+            let Some(entry) = password_generator.loaded_passwords.get_mut(index) else {
                 return;
+            };
+            if let Some(config) = entry.otp.as_mut() {
+                config.counter += 1;
             }
 
-            println!("Saving password details:");
-            println!("Name: {}", password_generator.password_name);
-            println!("Password: {}", password_generator.saved_password);
-            println!("Website: {}", password_generator.website);
-            println!("Username: {}", password_generator.username);
-            println!("Notes: {}", password_generator.notes);
-            
-            let result = st_json::save_password_details_to_json(
-                &password_generator.password_name,
-                &password_generator.saved_password,
-                &password_generator.website,
-                &password_generator.username,
-                &password_generator.notes,
-                "passwords.json"
-            );
-
-            match result {
+            match st_json::replace_password_at(index, entry.clone(), "passwords.json") {
                 Ok(_) => {
-                    password_generator.status_message = "Password saved successfully!".to_string();
-                    
-                    // Clear form fields after successful save
-                    password_generator.password_name.clear();
-                    password_generator.saved_password.clear();
-                    password_generator.website.clear();
-                    password_generator.username.clear();
-                    password_generator.notes.clear();
+                    password_generator.status_message = "Counter advanced, new code loaded".to_string();
                 }
                 Err(e) => {
-                    password_generator.status_message = format!("Error saving password: {}", e);
+                    password_generator.status_message = format!("Error saving counter: {}", e);
                 }
             }
         }
-    }
-
-    // Still calling the test function for development
-    viewpasswords::rf_json();
-}
-
-// Current page view (unchanged from your original)
-fn view_current(password_generator: &PasswordGenerator) -> Element<Message> {
-    let reload_svg = svg::Handle::from_path("assets/reload.svg"); 
-    let copy_svg = svg::Handle::from_path("assets/copy.svg");
-    let save_svg = svg::Handle::from_path("assets/save.svg"); 
-
-    let header = container(
-        column![
-            text("A password generator:")
-                .size(20),
-        ]
-        .spacing(2)
-        .align_x(iced::Alignment::Center)
-    )
-    .width(Fill)
-    .padding(40);
-
-    let reload_btn = button(
-        container(
-            svg::Svg::new(reload_svg).width(20).height(20)
-        )
-        .center_x(Fill)
-        .center_y(Fill)
-    )
-    .on_press(Message::Reload)
-    .padding(10)
-    .width(40)
-    .height(40);
-
-    let copy_btn = button(
-        container(
-            svg::Svg::new(copy_svg).width(20).height(20)
-        )
-        .center_x(Fill)
-        .center_y(Fill)
-    )
-    .on_press(Message::Copy)
-    .padding(10)
-    .width(40)
-    .height(40);
-
-    let password_section = container(
-        column![
-            text(":)")
-                .size(14),
-            
-            row![
-                reload_btn,
-                Space::with_width(15),
-                container(
-                    text(if password_generator.generated_password.is_empty() { 
-                        "Click to generate password" 
-                    } else { 
-                        &password_generator.generated_password 
-                    })
-                    .size(18)
-                )
-                .padding(20)
-                .width(Fill),
-                Space::with_width(15),
-                copy_btn,
-            ]
-            .align_y(iced::Alignment::Center),
-            
-            button(
-                container(
-                    row![
-                        svg::Svg::new(save_svg).width(20).height(20),
-                        text("Save").size(16)
-                    ]
-                    .spacing(10)
-                )
+        Message::Tick => {
+            // Nothing to update, the view reads the clock when it redraws.
+            // Returning early also keeps the debug dump below from running every second.
+            return;
+        }
+        Message::OtpImagePathChanged(value) => {
+            password_generator.otp_image_path = value;
+        }
+        Message::OtpImportTextChanged(value) => {
+            password_generator.otp_import_text = value;
+        }
+        Message::ScanOtpImage => {
+            let result = otp_import::configs_from_image(&password_generator.otp_image_path);
+            show_otp_import_preview(password_generator, result);
+        }
+        Message::ParseOtpImportText => {
+            let result = otp_import::configs_from_text(&password_generator.otp_import_text);
+            show_otp_import_preview(password_generator, result);
+        }
+        Message::ConfirmOtpImport => {
+            match otp_import::merge_into_database(&password_generator.otp_import_preview, "passwords.json") {
+                Ok((created, updated)) => {
+                    password_generator.status_message = format!(
+                        "Imported successfully: {} new entries, {} updated", created, updated
+                    );
+                    password_generator.otp_import_preview.clear();
+                    password_generator.otp_import_text.clear();
+                }
+                Err(e) => {
+                    password_generator.status_message = format!("Error importing codes: {}", e);
+                }
+            }
+        }
+        Message::CsvPathChanged(value) => {
+            password_generator.csv_path = value;
+        }
+        Message::LoadCsv => {
+            match csv_import::read_csv(&password_generator.csv_path) {
+                Ok(file) => {
+                    password_generator.csv_source = csv_import::detect_source(&file.headers);
+                    password_generator.csv_mapping = csv_import::default_mapping(&file.headers);
+                    password_generator.status_message = format!(
+                        "Found {} rows, check the columns below", file.rows.len()
+                    );
+                    password_generator.csv_file = Some(file);
+                }
+                Err(e) => {
+                    password_generator.status_message = e;
+                    password_generator.csv_file = None;
+                    password_generator.csv_mapping.clear();
+                }
+            }
+            refresh_csv_preview(password_generator);
+        }
+        Message::CsvSourceSelected(source) => {
+            password_generator.csv_source = source;
+            refresh_csv_preview(password_generator);
+        }
+        Message::CsvMappingChanged(column, field) => {
+            if let Some(slot) = password_generator.csv_mapping.get_mut(column) {
+                *slot = field;
+            }
+            refresh_csv_preview(password_generator);
+        }
+        Message::ConfirmCsvImport => {
+            match st_json::import_entries(password_generator.csv_preview.clone(), "passwords.json") {
+                Ok((added, duplicates)) => {
+                    password_generator.status_message = format!(
+                        "Imported successfully: {} entries added, {} duplicates skipped", added, duplicates
+                    );
+                    password_generator.csv_file = None;
+                    password_generator.csv_mapping.clear();
+                    password_generator.csv_path.clear();
+                    refresh_csv_preview(password_generator);
+                }
+                Err(e) => {
+                    password_generator.status_message = format!("Error importing entries: {}", e);
+                }
+            }
+        }
+        Message::KdbxPathChanged(value) => {
+            password_generator.kdbx_path = value;
+        }
+        Message::KdbxPasswordChanged(value) => {
+            password_generator.kdbx_password = value;
+        }
+        Message::KdbxConfirmPasswordChanged(value) => {
+            password_generator.kdbx_confirm_password = value;
+        }
+        Message::ImportKdbx => {
+            let mut imported = match kdbx::read_kdbx(&password_generator.kdbx_path, &password_generator.kdbx_password) {
+                Ok(imported) => imported,
+                Err(e) => {
+                    password_generator.status_message = e;
+                    return;
+                }
+            };
+            if let Err(e) = history_key::seal_import(&mut imported, password_generator.history_key.as_ref()) {
+                password_generator.status_message = e;
+                return;
+            }
+            let trashed = imported.trash.len();
+            let result = st_json::import_entries(imported.passwords, "passwords.json")
+                .and_then(|counts| st_json::import_trash(imported.trash, "passwords.json").map(|_| counts));
+            match result {
+                Ok((added, duplicates)) => {
+                    password_generator.status_message = format!(
+                        "Imported successfully: {} entries added, {} duplicates skipped, {} moved to trash",
+                        added, duplicates, trashed
+                    );
+                    password_generator.kdbx_password.clear();
+                }
+                Err(e) => {
+                    password_generator.status_message = format!("Error importing entries: {}", e);
+                }
+            }
+        }
+        Message::ExportKdbx => {
+            if password_generator.kdbx_path.trim().is_empty() || password_generator.kdbx_password.is_empty() {
+                password_generator.status_message = "Please enter a file path and a password".to_string();
+                return;
+            }
+            if password_generator.kdbx_password != password_generator.kdbx_confirm_password {
+                password_generator.status_message = "The passwords do not match".to_string();
+                return;
+            }
+            let result = st_json::load_password_database("passwords.json")
+                .map_err(|e| format!("Error loading passwords: {}", e))
+                .and_then(|mut database| {
+                    history_key::open_for_export(&mut database, password_generator.history_key.as_ref())?;
+                    kdbx::write_kdbx(&database, &password_generator.kdbx_path, &password_generator.kdbx_password)
+                        .map(|_| database.passwords.len())
+                });
+            match result {
+                Ok(count) => {
+                    password_generator.status_message = format!("Exported {} entries successfully", count);
+                    password_generator.kdbx_password.clear();
+                    password_generator.kdbx_confirm_password.clear();
+                }
+                Err(e) => {
+                    password_generator.status_message = e;
+                }
+            }
+        }
+        Message::BitwardenPathChanged(value) => {
+            password_generator.bitwarden_path = value;
+        }
+        Message::BitwardenPasswordChanged(value) => {
+            password_generator.bitwarden_password = value;
+        }
+        Message::BitwardenConfirmPasswordChanged(value) => {
+            password_generator.bitwarden_confirm_password = value;
+        }
+        Message::BitwardenExportConfirmationChanged(value) => {
+            password_generator.bitwarden_export_confirmation = value;
+        }
+        Message::BackupPathChanged(value) => {
+            password_generator.backup_path = value;
+            password_generator.backup_preview = None;
+        }
+        Message::BackupPasswordChanged(value) => {
+            password_generator.backup_password = value;
+        }
+        Message::BackupConfirmPasswordChanged(value) => {
+            password_generator.backup_confirm_password = value;
+        }
+        Message::CreateBackup => {
+            if password_generator.backup_path.trim().is_empty() || password_generator.backup_password.is_empty() {
+                password_generator.status_message = "Please enter a file path and a password".to_string();
+                return;
+            }
+            if password_generator.backup_password != password_generator.backup_confirm_password {
+                password_generator.status_message = "The passwords do not match".to_string();
+                return;
+            }
+            match backup::create_backup(
+                &password_generator.backup_path,
+                &password_generator.backup_password,
+                "passwords.json",
+                settings::SETTINGS_FILE,
+            ) {
+                Ok(count) => {
+                    password_generator.status_message = format!("Backup of {} entries created successfully", count);
+                    password_generator.backup_password.clear();
+                    password_generator.backup_confirm_password.clear();
+                }
+                Err(e) => {
+                    password_generator.status_message = e;
+                }
+            }
+        }
+        Message::OpenBackup => {
+            match backup::read_backup(&password_generator.backup_path, &password_generator.backup_password) {
+                Ok(contents) => {
+                    password_generator.status_message.clear();
+                    password_generator.backup_password.clear();
+                    password_generator.backup_preview = Some(contents);
+                }
+                Err(e) => {
+                    password_generator.status_message = e;
+                    password_generator.backup_preview = None;
+                }
+            }
+        }
+        Message::RestoreBackup(mode) => {
+            let Some(contents) = password_generator.backup_preview.take() else {
+                return;
+            };
+            match backup::restore_backup(&contents, mode, "passwords.json", settings::SETTINGS_FILE) {
+                Ok((added, duplicates)) => {
+                    password_generator.status_message = match mode {
+                        backup::RestoreMode::Merge => format!(
+                            "Backup merged successfully: {} entries added, {} duplicates skipped",
+                            added, duplicates
+                        ),
+                        backup::RestoreMode::Replace => {
+                            // The backup's settings are now the saved ones, so use them right away
+                            password_generator.settings = contents.settings;
+                            open_pwned_passwords(password_generator);
+                            // The backup may have its own history password
+                            password_generator.history_key = None;
+                            format!(
+                                "Backup restored successfully: {} entries (previous vault kept as passwords.json.before-restore)",
+                                added
+                            )
+                        }
+                    };
+                }
+                Err(e) => {
+                    password_generator.status_message = format!("Error restoring backup: {}", e);
+                }
+            }
+        }
+        Message::ExportFormatSelected(format) => {
+            password_generator.export_format = format;
+        }
+        Message::ExportFieldToggled(field, selected) => {
+            password_generator.export_fields.retain(|f| *f != field);
+            if selected {
+                password_generator.export_fields.push(field);
+            }
+        }
+        Message::ExportPathChanged(value) => {
+            password_generator.export_path = value;
+        }
+        Message::ExportConfirmationChanged(value) => {
+            password_generator.export_confirmation = value;
+        }
+        Message::ExportPlain => {
+            if password_generator.export_path.trim().is_empty() {
+                password_generator.status_message = "Please enter a file path".to_string();
+                return;
+            }
+            if password_generator.export_confirmation.trim() != export::CONFIRMATION_PHRASE {
+                password_generator.status_message = export::CONFIRMATION_PROMPT.to_string();
+                return;
+            }
+            match export::export_to_file(
+                "passwords.json",
+                &password_generator.export_path,
+                &password_generator.export_fields,
+                password_generator.export_format,
+            ) {
+                Ok(count) => {
+                    password_generator.status_message = format!("Exported {} entries successfully", count);
+                }
+                Err(e) => {
+                    password_generator.status_message = e;
+                }
+            }
+            password_generator.export_confirmation.clear();
+        }
+        Message::ImportBitwarden => {
+            let mut imported = match bitwarden::read_export(&password_generator.bitwarden_path, &password_generator.bitwarden_password) {
+                Ok(imported) => imported,
+                Err(e) => {
+                    password_generator.status_message = e;
+                    return;
+                }
+            };
+            if let Err(e) = history_key::seal_import(&mut imported, password_generator.history_key.as_ref()) {
+                password_generator.status_message = e;
+                return;
+            }
+            let trashed = imported.trash.len();
+            let result = st_json::import_entries(imported.passwords, "passwords.json")
+                .and_then(|counts| st_json::import_trash(imported.trash, "passwords.json").map(|_| counts));
+            match result {
+                Ok((added, duplicates)) => {
+                    password_generator.status_message = format!(
+                        "Imported successfully: {} entries added, {} duplicates skipped, {} moved to trash",
+                        added, duplicates, trashed
+                    );
+                    password_generator.bitwarden_password.clear();
+                }
+                Err(e) => {
+                    password_generator.status_message = format!("Error importing entries: {}", e);
+                }
+            }
+        }
+        Message::ExportBitwarden => {
+            if password_generator.bitwarden_path.trim().is_empty() {
+                password_generator.status_message = "Please enter a file path".to_string();
+                return;
+            }
+            if password_generator.bitwarden_password != password_generator.bitwarden_confirm_password {
+                password_generator.status_message = "The passwords do not match".to_string();
+                return;
+            }
+            // Without a password the file is plain JSON, so it needs the same confirmation as the Export page
+            if password_generator.bitwarden_password.is_empty()
+                && password_generator.bitwarden_export_confirmation.trim() != export::CONFIRMATION_PHRASE
+            {
+                password_generator.status_message = format!("No password set: {}", export::CONFIRMATION_PROMPT);
+                return;
+            }
+            let result = st_json::load_password_database("passwords.json")
+                .map_err(|e| format!("Error loading passwords: {}", e))
+                .and_then(|mut database| {
+                    history_key::open_for_export(&mut database, password_generator.history_key.as_ref())?;
+                    bitwarden::write_export(&database, &password_generator.bitwarden_path, &password_generator.bitwarden_password)
+                        .map(|_| database.passwords.len())
+                });
+            match result {
+                Ok(count) => {
+                    password_generator.status_message = if password_generator.bitwarden_password.is_empty() {
+                        format!("Exported {} entries successfully (not encrypted - keep this file safe)", count)
+                    } else {
+                        format!("Exported {} entries successfully", count)
+                    };
+                    password_generator.bitwarden_password.clear();
+                    password_generator.bitwarden_confirm_password.clear();
+                    password_generator.bitwarden_export_confirmation.clear();
+                }
+                Err(e) => {
+                    password_generator.status_message = e;
+                }
+            }
+        }
+        Message::OpenEntry(index) => {
+            let Some(entry) = password_generator.loaded_passwords.get(index) else {
+                return;
+            };
+            password_generator.edit_name = entry.name.clone();
+            password_generator.edit_value = entry.value.clone();
+            password_generator.edit_website = entry.website.clone();
+            password_generator.edit_username = entry.username.clone();
+            password_generator.edit_notes = entry.notes.clone();
+            password_generator.edit_folder = entry.folder.clone();
+            password_generator.edit_rotation_days = if entry.rotation_days == 0 {
+                String::new()
+            } else {
+                entry.rotation_days.to_string()
+            };
+            password_generator.edit_master.clear();
+            password_generator.edit_counter = entry.derivation.as_ref().map(|profile| profile.counter.to_string()).unwrap_or_default();
+            password_generator.show_secret = false;
+            password_generator.status_message.clear();
+            password_generator.current_page = Pages::EntryDetails(index);
+        }
+        Message::EditNameChanged(value) => {
+            password_generator.edit_name = value;
+        }
+        Message::EditValueChanged(value) => {
+            password_generator.edit_value = value;
+        }
+        Message::EditWebsiteChanged(value) => {
+            password_generator.edit_website = value;
+        }
+        Message::EditUsernameChanged(value) => {
+            password_generator.edit_username = value;
+        }
+        Message::EditNotesChanged(value) => {
+            password_generator.edit_notes = value;
+        }
+        Message::EditFolderChanged(value) => {
+            password_generator.edit_folder = value;
+        }
+        Message::EditRotationChanged(value) => {
+            password_generator.edit_rotation_days = value;
+        }
+        Message::EditMasterChanged(value) => {
+            password_generator.edit_master = value;
+            password_generator.edit_value.clear();
+        }
+        Message::EditCounterChanged(value) => {
+            password_generator.edit_counter = value;
+            password_generator.edit_value.clear();
+        }
+        Message::DeriveEntryPassword => {
+            let Pages::EntryDetails(index) = password_generator.current_page else {
+                return;
+            };
+            let Some(mut profile) = password_generator.loaded_passwords.get(index).and_then(|entry| entry.derivation.clone()) else {
+                return;
+            };
+            let result = parse_counter(&password_generator.edit_counter)
+                .and_then(|counter| {
+                    profile.counter = counter;
+                    derive::derive_password(&password_generator.edit_master, &profile)
+                });
+            match result {
+                Ok(password) => {
+                    password_generator.edit_value = password;
+                    password_generator.status_message.clear();
+                }
+                Err(e) => password_generator.status_message = e,
+            }
+        }
+        Message::ToggleDueOnly(due_only) => {
+            password_generator.due_only = due_only;
+        }
+        Message::ShowDueForRotation => {
+            password_generator.due_only = true;
+            password_generator.rotation_notice.clear();
+            return update(password_generator, Message::NavigateTo(Pages::ViewPasswords));
+        }
+        Message::DismissRotationNotice => {
+            password_generator.rotation_notice.clear();
+        }
+        Message::ToggleShowSecret => {
+            password_generator.show_secret = !password_generator.show_secret;
+        }
+        Message::CopyEntryValue => {
+            if password_generator.edit_value.is_empty() {
+                password_generator.status_message = "Nothing to copy yet".to_string();
+                return;
+            }
+            let mut clipboard = arboard::Clipboard::new().expect("Failed to create clipboard");
+            clipboard.set_text(&password_generator.edit_value)
+                .expect("Failed to set clipboard text");
+            password_generator.status_message = "Password copied to clipboard".to_string();
+        }
+        Message::WifiQrPathChanged(value) => {
+            password_generator.wifi_qr_path = value;
+        }
+        Message::ExportWifiQr => {
+            let Pages::EntryDetails(index) = password_generator.current_page else {
+                return;
+            };
+            let Some(entry) = password_generator.loaded_passwords.get(index) else {
+                return;
+            };
+            let st_json::EntryKind::Wifi { ssid, security, hidden } = &entry.kind else {
+                return;
+            };
+            let path = password_generator.wifi_qr_path.trim().to_string();
+            if path.is_empty() {
+                password_generator.status_message = "Enter a .png or .svg file to save the QR code to".to_string();
+                return;
+            }
+            let payload = wifi::qr_payload(ssid, *security, &entry.value, *hidden);
+            match wifi::save_qr(&payload, &path) {
+                Ok(_) => password_generator.status_message = format!("QR code saved to {} successfully", path),
+                Err(e) => password_generator.status_message = e,
+            }
+        }
+        Message::CopyHistoryValue(history_index) => {
+            let Pages::EntryDetails(index) = password_generator.current_page else {
+                return;
+            };
+            let value = password_generator.loaded_passwords
+                .get(index)
+                .and_then(|entry| entry.history.get(history_index))
+                .map(|old| history_key::reveal(password_generator.history_key.as_ref(), old));
+
+            if let Some(value) = value {
+                let value = match value {
+                    Ok(value) => value,
+                    Err(e) => {
+                        password_generator.status_message = e;
+                        return;
+                    }
+                };
+                let mut clipboard = arboard::Clipboard::new().expect("Failed to create clipboard");
+                clipboard.set_text(&value)
+                    .expect("Failed to set clipboard text");
+                password_generator.status_message = "Previous password copied to clipboard".to_string();
+            }
+        }
+        Message::HistoryPasswordChanged(value) => {
+            password_generator.history_password_input = value;
+        }
+        Message::UnlockHistory => {
+            let mut database = match st_json::load_password_database("passwords.json") {
+                Ok(database) => database,
+                Err(e) => {
+                    password_generator.status_message = format!("Error loading passwords: {}", e);
+                    return;
+                }
+            };
+            let password = std::mem::take(&mut password_generator.history_password_input);
+            // The first password entered becomes the history password
+            let result = match &database.history_lock {
+                Some(lock) => history_key::unlock(lock, &password),
+                None => history_key::create_lock(&password).map(|(lock, key)| {
+                    database.history_lock = Some(lock);
+                    key
+                }),
+            };
+            let key = match result {
+                Ok(key) => key,
+                Err(e) => {
+                    password_generator.status_message = e;
+                    return;
+                }
+            };
+            // Old passwords saved before the history was encrypted get sealed now
+            let sealed = history_key::seal_database(&mut database, &key);
+            if let Err(e) = st_json::save_password_database(&database, "passwords.json") {
+                password_generator.status_message = format!("Error saving passwords: {}", e);
+                return;
+            }
+            password_generator.loaded_passwords = database.passwords;
+            password_generator.history_key = Some(key);
+            password_generator.status_message = if sealed > 0 {
+                format!("Password history unlocked successfully; {} old passwords encrypted", sealed)
+            } else {
+                "Password history unlocked successfully".to_string()
+            };
+        }
+        Message::SaveEntryChanges => {
+            let Pages::EntryDetails(index) = password_generator.current_page else {
+                return;
+            };
+            if password_generator.edit_name.trim().is_empty() {
+                password_generator.status_message = "Please fill in all required fields".to_string();
+                return;
+            }
+            let rotation_days = match password_generator.edit_rotation_days.trim() {
+                "" => 0,
+                days => match days.parse::<u32>() {
+                    Ok(days) => days,
+                    Err(_) => {
+                        password_generator.status_message = "Rotation interval must be a whole number of days".to_string();
+                        return;
+                    }
+                },
+            };
+            let Some(entry) = password_generator.loaded_passwords.get_mut(index) else {
+                return;
+            };
+            if let st_json::EntryKind::Wifi { ssid, security, .. } = &entry.kind
+                && let Err(e) = wifi::check_network(ssid, *security, &password_generator.edit_value)
+            {
+                password_generator.status_message = e;
+                return;
+            }
+            let counter = if entry.derivation.is_some() {
+                match parse_counter(&password_generator.edit_counter) {
+                    Ok(counter) => counter,
+                    Err(e) => {
+                        password_generator.status_message = e;
+                        return;
+                    }
+                }
+            } else {
+                0
+            };
+
+            let mut updated = entry.clone();
+            updated.name = password_generator.edit_name.clone();
+            updated.website = password_generator.edit_website.clone();
+            updated.username = password_generator.edit_username.clone();
+            updated.folder = password_generator.edit_folder.trim().trim_matches('/').to_string();
+            updated.rotation_days = rotation_days;
+            if updated.kind != st_json::EntryKind::SecureNote {
+                updated.notes = password_generator.edit_notes.clone();
+            }
+            let password_changed = if let Some(profile) = &mut updated.derivation {
+                // The derived password itself is never stored; a new counter is a new password
+                let changed = profile.counter != counter;
+                profile.counter = counter;
+                changed
+            } else {
+                match st_json::change_password(
+                    &mut updated,
+                    &password_generator.edit_value,
+                    password_generator.settings.max_password_history,
+                    password_generator.history_key.as_ref(),
+                ) {
+                    Ok(changed) => changed,
+                    Err(e) => {
+                        password_generator.status_message = e;
+                        return;
+                    }
+                }
+            };
+            // updated_at is when the password was last changed, which rotation reminders count from
+            if password_changed {
+                updated.updated_at = st_json::timestamp_now();
+            }
+
+            match st_json::replace_password_at(index, updated.clone(), "passwords.json") {
+                Ok(_) => {
+                    *entry = updated;
+                    password_generator.status_message = "Changes saved successfully!".to_string();
+                }
+                Err(e) => {
+                    password_generator.status_message = format!("Error saving changes: {}", e);
+                }
+            }
+        }
+        Message::MoveToTrash => {
+            let Pages::EntryDetails(index) = password_generator.current_page else {
+                return;
+            };
+            match st_json::move_to_trash(index, "passwords.json") {
+                Ok(_) => {
+                    let name = password_generator.loaded_passwords.remove(index).name;
+                    password_generator.status_message = format!("'{}' moved to trash successfully", name);
+                    password_generator.current_page = Pages::ViewPasswords;
+                }
+                Err(e) => {
+                    password_generator.status_message = format!("Error deleting entry: {}", e);
+                }
+            }
+        }
+        Message::RestoreFromTrash(index) => {
+            match st_json::restore_from_trash(index, "passwords.json") {
+                Ok(_) => {
+                    load_trash(password_generator);
+                    password_generator.status_message = "Entry restored successfully".to_string();
+                }
+                Err(e) => {
+                    password_generator.status_message = format!("Error restoring entry: {}", e);
+                }
+            }
+        }
+        Message::PurgeFromTrash(index) => {
+            match st_json::purge_from_trash(index, "passwords.json") {
+                Ok(_) => {
+                    load_trash(password_generator);
+                    password_generator.status_message = "Entry permanently deleted".to_string();
+                }
+                Err(e) => {
+                    password_generator.status_message = format!("Error deleting entry: {}", e);
+                }
+            }
+        }
+        Message::EmptyTrash => {
+            match st_json::empty_trash("passwords.json") {
+                Ok(_) => {
+                    load_trash(password_generator);
+                    password_generator.status_message = "Trash emptied".to_string();
+                }
+                Err(e) => {
+                    password_generator.status_message = format!("Error emptying trash: {}", e);
+                }
+            }
+        }
+        Message::TrashRetentionChanged(value) => {
+            password_generator.trash_retention_input = value;
+        }
+        Message::AuditMaxAgeChanged(value) => {
+            password_generator.audit_max_age_input = value;
+        }
+        Message::HibpPathChanged(value) => {
+            password_generator.hibp_path_input = value;
+        }
+        Message::AliasEmailChanged(value) => {
+            password_generator.alias_email_input = value;
+        }
+        Message::CatchAllDomainChanged(value) => {
+            password_generator.catch_all_domain_input = value;
+        }
+        Message::PresetNameChanged(value) => {
+            password_generator.preset_name_input = value;
+        }
+        Message::SavePreset => {
+            let name = password_generator.preset_name_input.trim().to_string();
+            if name.is_empty() {
+                password_generator.status_message = "Enter a name for the preset".to_string();
+                return;
+            }
+            let policy = match current_preset_policy(password_generator) {
+                Ok(policy) => policy,
+                Err(e) => {
+                    password_generator.status_message = e;
+                    return;
+                }
+            };
+            let (added, _) = presets::merge_presets(
+                &mut password_generator.settings.presets,
+                vec![presets::GeneratorPreset { name: name.clone(), policy }],
+            );
+            match settings::save_settings(&password_generator.settings, settings::SETTINGS_FILE) {
+                Ok(_) => {
+                    password_generator.preset_name_input.clear();
+                    password_generator.status_message = if added == 1 {
+                        format!("Preset '{}' saved successfully", name)
+                    } else {
+                        format!("Preset '{}' updated successfully", name)
+                    };
+                }
+                Err(e) => password_generator.status_message = format!("Error saving settings: {}", e),
+            }
+        }
+        Message::RemovePreset(index) => {
+            if index >= password_generator.settings.presets.len() {
+                return;
+            }
+            let removed = password_generator.settings.presets.remove(index);
+            match settings::save_settings(&password_generator.settings, settings::SETTINGS_FILE) {
+                Ok(_) => password_generator.status_message = format!("Preset '{}' removed successfully", removed.name),
+                Err(e) => password_generator.status_message = format!("Error saving settings: {}", e),
+            }
+        }
+        Message::PresetsPathChanged(value) => {
+            password_generator.presets_path_input = value;
+        }
+        Message::ExportPresets => {
+            let path = password_generator.presets_path_input.trim().to_string();
+            if path.is_empty() {
+                password_generator.status_message = "Enter a file to export the presets to".to_string();
+                return;
+            }
+            match presets::export_presets(&password_generator.settings.presets, &path) {
+                Ok(_) => {
+                    password_generator.status_message = format!(
+                        "{} presets exported to {} successfully",
+                        password_generator.settings.presets.len(),
+                        path
+                    );
+                }
+                Err(e) => password_generator.status_message = e,
+            }
+        }
+        Message::ImportPresets => {
+            let path = password_generator.presets_path_input.trim().to_string();
+            if path.is_empty() {
+                password_generator.status_message = "Enter a presets file to import".to_string();
+                return;
+            }
+            let imported = match presets::import_presets(&path) {
+                Ok(imported) => imported,
+                Err(e) => {
+                    password_generator.status_message = e;
+                    return;
+                }
+            };
+            let (added, replaced) = presets::merge_presets(&mut password_generator.settings.presets, imported);
+            match settings::save_settings(&password_generator.settings, settings::SETTINGS_FILE) {
+                Ok(_) => {
+                    password_generator.status_message = format!(
+                        "{} presets imported successfully ({} replaced)",
+                        added + replaced,
+                        replaced
+                    );
+                }
+                Err(e) => password_generator.status_message = format!("Error saving settings: {}", e),
+            }
+        }
+        Message::GeneratorHistorySizeChanged(value) => {
+            password_generator.generator_history_size_input = value;
+        }
+        Message::GeneratorHistoryMinutesChanged(value) => {
+            password_generator.generator_history_minutes_input = value;
+        }
+        Message::MaxHistoryChanged(value) => {
+            password_generator.max_history_input = value;
+        }
+        Message::SaveSettings => {
+            let Ok(max_history) = password_generator.max_history_input.trim().parse::<usize>() else {
+                password_generator.status_message = "History length must be a whole number".to_string();
+                return;
+            };
+            let Ok(retention_days) = password_generator.trash_retention_input.trim().parse::<u32>() else {
+                password_generator.status_message = "Trash retention must be a whole number of days".to_string();
+                return;
+            };
+            let Ok(max_age_months) = password_generator.audit_max_age_input.trim().parse::<u32>() else {
+                password_generator.status_message = "Password age limit must be a whole number of months".to_string();
+                return;
+            };
+            let Ok(generator_history_size) = password_generator.generator_history_size_input.trim().parse::<usize>() else {
+                password_generator.status_message = "Generator history size must be a whole number".to_string();
+                return;
+            };
+            let Ok(generator_history_minutes) = password_generator.generator_history_minutes_input.trim().parse::<u32>() else {
+                password_generator.status_message = "Generator history time must be a whole number of minutes".to_string();
+                return;
+            };
+            password_generator.settings.max_password_history = max_history;
+            password_generator.settings.trash_retention_days = retention_days;
+            password_generator.settings.audit_max_age_months = max_age_months;
+            password_generator.settings.generator_history_size = generator_history_size;
+            password_generator.settings.generator_history_minutes = generator_history_minutes;
+            password_generator.generator_history.truncate(generator_history_size);
+            let hibp_path = password_generator.hibp_path_input.trim().to_string();
+            let pwned_passwords = if hibp_path.is_empty() {
+                None
+            } else {
+                match hibp::PwnedPasswords::open(&hibp_path) {
+                    Ok(pwned) => Some(pwned),
+                    Err(e) => {
+                        password_generator.status_message = e;
+                        return;
+                    }
+                }
+            };
+            password_generator.settings.hibp_path = hibp_path;
+            password_generator.pwned_passwords = pwned_passwords;
+            let alias_email = password_generator.alias_email_input.trim().to_string();
+            if !alias_email.is_empty() && let Err(e) = genr::check_alias_email(&alias_email) {
+                password_generator.status_message = e;
+                return;
+            }
+            let catch_all_domain = password_generator.catch_all_domain_input.trim().trim_start_matches('@').to_lowercase();
+            if !catch_all_domain.is_empty() && let Err(e) = genr::check_catch_all_domain(&catch_all_domain) {
+                password_generator.status_message = e;
+                return;
+            }
+            password_generator.settings.alias_email = alias_email;
+            password_generator.settings.catch_all_domain = catch_all_domain;
+
+            match settings::save_settings(&password_generator.settings, settings::SETTINGS_FILE) {
+                Ok(_) => {
+                    password_generator.status_message = "Settings saved successfully!".to_string();
+                }
+                Err(e) => {
+                    password_generator.status_message = format!("Error saving settings: {}", e);
+                }
+            }
+        }
+        Message::SavePasswordDetails => {
+            let (kind, value, notes) = match build_entry_kind(password_generator) {
+                Ok(parts) => parts,
+                Err(message) => {
+                    password_generator.status_message = message;
+                    return;
+                }
+            };
+            let otp_config = match build_otp_config(password_generator) {
+                Ok(config) => config,
+                Err(message) => {
+                    password_generator.status_message = message;
+                    return;
+                }
+            };
+
+            println!("Saving {} details:", kind.label());
+            println!("Name: {}", password_generator.password_name);
+            println!("Website: {}", password_generator.website);
+            println!("Username: {}", password_generator.username);
+            
+            let mut entry = st_json::create_password_details(
+                &password_generator.password_name,
+                &value,
+                &password_generator.website,
+                &password_generator.username,
+                &notes,
+            );
+            entry.kind = kind;
+            entry.otp = otp_config;
+            // Derived passwords are worked out again when needed, so only the profile is kept
+            if password_generator.entry_type == EntryType::Login
+                && let Some(profile) = &password_generator.pending_derivation
+            {
+                entry.value.clear();
+                entry.derivation = Some(profile.clone());
+            }
+
+            let result = st_json::save_password_details_to_json(entry, "passwords.json");
+
+            match result {
+                Ok(_) => {
+                    password_generator.status_message = "Password saved successfully!".to_string();
+                    
+                    // Clear form fields after successful save
+                    clear_entry_form(password_generator);
+                }
+                Err(e) => {
+                    password_generator.status_message = format!("Error saving password: {}", e);
+                }
+            }
+        }
+    }
+
+    // Still calling the test function for development
+    viewpasswords::rf_json();
+}
+
+// Validates the AddDetails form for the selected entry type.
+// Returns the entry kind together with the `value` and `notes` to store.
+fn build_entry_kind(password_generator: &PasswordGenerator) -> Result<(st_json::EntryKind, String, String), String> {
+    let required = "Please fill in all required fields".to_string();
+    if password_generator.password_name.trim().is_empty() {
+        return Err(required);
+    }
+
+    let notes = password_generator.notes.clone();
+    match password_generator.entry_type {
+        EntryType::Login => {
+            if password_generator.saved_password.trim().is_empty() {
+                return Err(required);
+            }
+            Ok((st_json::EntryKind::Login, password_generator.saved_password.clone(), notes))
+        }
+        EntryType::SecureNote => {
+            let text = password_generator.secure_note.text();
+            if text.trim().is_empty() {
+                return Err(required);
+            }
+            Ok((st_json::EntryKind::SecureNote, String::new(), text.trim_end().to_string()))
+        }
+        EntryType::Card => {
+            if !st_json::luhn_valid(&password_generator.card_number) {
+                return Err("Card number is not valid".to_string());
+            }
+            if !st_json::card_expiry_valid(&password_generator.card_expiry) {
+                return Err("Expiry must be in MM/YY format".to_string());
+            }
+            let cvv = password_generator.card_cvv.trim();
+            if !(3..=4).contains(&cvv.len()) || !cvv.chars().all(|c| c.is_ascii_digit()) {
+                return Err("CVV must be 3 or 4 digits".to_string());
+            }
+            let kind = st_json::EntryKind::Card {
+                cardholder: password_generator.cardholder.trim().to_string(),
+                number: password_generator.card_number.trim().to_string(),
+                expiry: password_generator.card_expiry.trim().to_string(),
+                cvv: cvv.to_string(),
+            };
+            Ok((kind, String::new(), notes))
+        }
+        EntryType::Identity => {
+            if password_generator.full_name.trim().is_empty() {
+                return Err(required);
+            }
+            let kind = st_json::EntryKind::Identity {
+                full_name: password_generator.full_name.trim().to_string(),
+                address: password_generator.address.trim().to_string(),
+                phone: password_generator.phone.trim().to_string(),
+                email: password_generator.email.trim().to_string(),
+            };
+            Ok((kind, String::new(), notes))
+        }
+        EntryType::ApiKey => {
+            if password_generator.saved_password.trim().is_empty() {
+                return Err(required);
+            }
+            let expires_at = password_generator.api_key_expiry.trim();
+            if !expires_at.is_empty() && !st_json::date_valid(expires_at) {
+                return Err("Expiry date must be in YYYY-MM-DD format".to_string());
+            }
+            let kind = st_json::EntryKind::ApiKey {
+                expires_at: expires_at.to_string(),
+            };
+            Ok((kind, password_generator.saved_password.clone(), notes))
+        }
+        EntryType::Wifi => {
+            let ssid = password_generator.wifi_ssid.trim();
+            // The passphrase field is hidden for open networks, so ignore anything left in it
+            let passphrase = if password_generator.wifi_security == wifi::WifiSecurity::Open {
+                String::new()
+            } else {
+                password_generator.saved_password.clone()
+            };
+            wifi::check_network(ssid, password_generator.wifi_security, &passphrase)?;
+            let kind = st_json::EntryKind::Wifi {
+                ssid: ssid.to_string(),
+                security: password_generator.wifi_security,
+                hidden: password_generator.wifi_hidden,
+            };
+            Ok((kind, passphrase, notes))
+        }
+    }
+}
+
+// Parses the optional one-time code field of a login.
+// otpauth:// URIs carry their own settings; bare secrets use the pickers on the form.
+fn build_otp_config(password_generator: &PasswordGenerator) -> Result<Option<otp::OtpConfig>, String> {
+    let input = password_generator.otp_input.trim();
+    if input.is_empty() || password_generator.entry_type != EntryType::Login {
+        return Ok(None);
+    }
+
+    let mut config = otp::parse_otp_input(input)?;
+    if !is_otpauth_uri(input) {
+        config.kind = password_generator.otp_kind;
+        config.algorithm = password_generator.otp_algorithm;
+        config.digits = password_generator.otp_digits.unwrap_or(6);
+        if !password_generator.otp_period.trim().is_empty() {
+            config.period = password_generator.otp_period.trim().parse()
+                .map_err(|_| "OTP period must be a number of seconds".to_string())?;
+        }
+        otp::validate(&config)?;
+    }
+    if config.account.is_empty() {
+        config.account = password_generator.username.trim().to_string();
+    }
+    Ok(Some(config))
+}
+
+fn show_otp_import_preview(password_generator: &mut PasswordGenerator, result: Result<Vec<otp::OtpConfig>, String>) {
+    match result {
+        Ok(configs) if configs.is_empty() => {
+            password_generator.status_message = "No one-time code secrets found".to_string();
+            password_generator.otp_import_preview.clear();
+        }
+        Ok(configs) => {
+            password_generator.status_message = format!("Found {} accounts, review them below", configs.len());
+            password_generator.otp_import_preview = configs;
+        }
+        Err(e) => {
+            password_generator.status_message = e;
+            password_generator.otp_import_preview.clear();
+        }
+    }
+}
+
+// Rebuilds the entries the CSV wizard would import with the current mapping
+fn refresh_csv_preview(password_generator: &mut PasswordGenerator) {
+    match &password_generator.csv_file {
+        Some(file) => {
+            let (entries, skipped) = csv_import::build_entries(
+                file,
+                &password_generator.csv_mapping,
+                password_generator.csv_source,
+            );
+            password_generator.csv_preview = entries;
+            password_generator.csv_skipped = skipped;
+        }
+        None => {
+            password_generator.csv_preview.clear();
+            password_generator.csv_skipped.clear();
+        }
+    }
+}
+
+// The website's password rules under the add form's password, with anything
+// the current password breaks and a button to generate one that fits
+fn site_rules_hint(password_generator: &PasswordGenerator) -> Element<'_, Message> {
+    let Some((domain, rules)) = &password_generator.site_rules else {
+        return Space::with_height(0).into();
+    };
+    let mut hint = column![
+        Space::with_height(5),
+        text(format!("{}: {}", domain, rules.describe()))
+            .size(12)
+            .color(iced::Color::from_rgb(0.5, 0.5, 0.5)),
+    ]
+    .width(300)
+    .spacing(2);
+    if !password_generator.saved_password.is_empty() {
+        for problem in rules.problems(&password_generator.saved_password) {
+            hint = hint.push(text(problem).size(12).color(iced::Color::from_rgb(0.8, 0.0, 0.0)));
+        }
+    }
+    hint.push(
+        button(text("Generate for this site").size(12))
+            .on_press(Message::GenerateForSite)
+            .padding([4, 10])
+    )
+    .into()
+}
+
+// Buttons under the add form's username that fill it with a new random
+// username, or an email alias when Settings has an email or catch-all domain
+fn username_generators(password_generator: &PasswordGenerator) -> Element<'_, Message> {
+    let mut buttons = row![].spacing(5);
+    for style in [genr::UsernameStyle::Words, genr::UsernameStyle::Handle] {
+        buttons = buttons.push(
+            button(text(style.to_string()).size(12))
+                .on_press(Message::GenerateUsername(style))
+                .padding([4, 8])
+        );
+    }
+    let settings = &password_generator.settings;
+    for (kind, configured) in [
+        (genr::AliasKind::PlusAddress, !settings.alias_email.is_empty()),
+        (genr::AliasKind::CatchAll, !settings.catch_all_domain.is_empty()),
+    ] {
+        if configured {
+            buttons = buttons.push(
+                button(text(kind.to_string()).size(12))
+                    .on_press(Message::GenerateEmailAlias(kind))
+                    .padding([4, 8])
+            );
+        }
+    }
+    column![Space::with_height(5), buttons.wrap()].width(300).into()
+}
+
+// Says what gets saved when the add form's password was derived
+fn derivation_note(password_generator: &PasswordGenerator) -> Element<'_, Message> {
+    let Some(profile) = &password_generator.pending_derivation else {
+        return Space::with_height(0).into();
+    };
+    column![
+        Space::with_height(5),
+        text(format!("Derived from {}; only these settings are saved, not the password", profile.describe()))
+            .size(12)
+            .color(iced::Color::from_rgb(0.5, 0.5, 0.5)),
+    ]
+    .width(300)
+    .into()
+}
+
+// Red warning under the add form's password, or nothing
+fn breach_warning(password_generator: &PasswordGenerator) -> Element<'_, Message> {
+    if password_generator.breach_warning.is_empty() {
+        return Space::with_height(0).into();
+    }
+    column![
+        Space::with_height(5),
+        text(&password_generator.breach_warning)
+            .size(12)
+            .color(iced::Color::from_rgb(0.8, 0.0, 0.0)),
+    ]
+    .into()
+}
+
+// Keeps a generated password in the generator history, dropping ones that are too old
+fn remember_generated(password_generator: &mut PasswordGenerator, password: &str, website: &str, source: &str) {
+    let history = &mut password_generator.generator_history;
+    history.expire(chrono::Utc::now().naive_utc(), password_generator.settings.generator_history_minutes);
+    history.record(password, website, source, password_generator.settings.generator_history_size);
+}
+
+// Reads a password back from the generator history, unless it has expired since the page was opened
+fn recall_generated(password_generator: &mut PasswordGenerator, index: usize) -> Result<String, String> {
+    password_generator.generator_history.expire(
+        chrono::Utc::now().naive_utc(),
+        password_generator.settings.generator_history_minutes,
+    );
+    password_generator.generator_history.reveal(index)
+}
+
+// Opens the Pwned Passwords list set in Settings, so checks don't open it again
+// on every keystroke
+fn open_pwned_passwords(password_generator: &mut PasswordGenerator) {
+    password_generator.pwned_passwords = None;
+    if password_generator.settings.hibp_path.is_empty() {
+        return;
+    }
+    match hibp::PwnedPasswords::open(&password_generator.settings.hibp_path) {
+        Ok(pwned) => password_generator.pwned_passwords = Some(pwned),
+        Err(e) => println!("Error opening the Pwned Passwords list: {}", e),
+    }
+}
+
+// Looks the add form's password up in the Pwned Passwords list, if one is set up
+fn check_breach(password_generator: &mut PasswordGenerator) {
+    password_generator.breach_warning.clear();
+    let Some(pwned) = &password_generator.pwned_passwords else {
+        return;
+    };
+    if password_generator.saved_password.is_empty() {
+        return;
+    }
+    match pwned.breach_count(&password_generator.saved_password) {
+        Ok(0) => {}
+        Ok(count) => {
+            password_generator.breach_warning = format!("This password has appeared in {} data breaches. Choose another one.", count);
+        }
+        Err(e) => println!("Error checking password against breaches: {}", e),
+    }
+}
+
+fn is_otpauth_uri(input: &str) -> bool {
+    input.trim().to_ascii_lowercase().starts_with("otpauth://")
+}
+
+// "" counts as the first password for a site
+fn parse_counter(input: &str) -> Result<u32, String> {
+    match input.trim() {
+        "" => Ok(1),
+        counter => counter.parse::<u32>().map_err(|_| "Counter must be a whole number".to_string()),
+    }
+}
+
+const DEFAULT_PASSPHRASE_WORDS: usize = 5;
+const DEFAULT_PASSPHRASE_SEPARATOR: &str = "-";
+
+// Reads the passphrase inputs: (words, separator), with defaults for empty ones
+fn passphrase_options(password_generator: &PasswordGenerator) -> Result<(usize, String), String> {
+    let words = match password_generator.passphrase_words_input.trim() {
+        "" => DEFAULT_PASSPHRASE_WORDS,
+        words => words.parse::<usize>().map_err(|_| "Words must be a whole number".to_string())?,
+    };
+    if !(genr::MIN_PASSPHRASE_WORDS..=genr::MAX_PASSPHRASE_WORDS).contains(&words) {
+        return Err(format!(
+            "Passphrases need between {} and {} words",
+            genr::MIN_PASSPHRASE_WORDS, genr::MAX_PASSPHRASE_WORDS
+        ));
+    }
+    let separator = if password_generator.passphrase_separator_input.is_empty() {
+        DEFAULT_PASSPHRASE_SEPARATOR.to_string()
+    } else {
+        password_generator.passphrase_separator_input.clone()
+    };
+    Ok((words, separator))
+}
+
+fn random_length(password_generator: &PasswordGenerator) -> Result<usize, String> {
+    let length = match password_generator.random_length_input.trim() {
+        "" => 16,
+        length => length.parse::<usize>().map_err(|_| "Length must be a whole number".to_string())?,
+    };
+    if !(1..=presets::MAX_RANDOM_LENGTH).contains(&length) {
+        return Err(format!("Length must be between 1 and {}", presets::MAX_RANDOM_LENGTH));
+    }
+    Ok(length)
+}
+
+// The generator page's current settings, to save as a preset
+fn current_preset_policy(password_generator: &PasswordGenerator) -> Result<presets::PresetPolicy, String> {
+    match password_generator.generator_mode {
+        GeneratorMode::Random => Ok(presets::PresetPolicy::Random { length: random_length(password_generator)? }),
+        GeneratorMode::Pattern => {
+            genr::parse_pattern(&password_generator.pattern_input)?;
+            Ok(presets::PresetPolicy::Pattern { pattern: password_generator.pattern_input.clone() })
+        }
+        GeneratorMode::Pronounceable => {
+            let options = pronounceable_options(password_generator)?;
+            Ok(presets::PresetPolicy::Pronounceable {
+                length: options.length,
+                digits: options.digits,
+                symbols: options.symbols,
+            })
+        }
+        GeneratorMode::Passphrase => {
+            let (words, separator) = passphrase_options(password_generator)?;
+            Ok(presets::PresetPolicy::Passphrase { words, separator })
+        }
+        GeneratorMode::Derived | GeneratorMode::Dice => {
+            Err(format!("{} passwords can't be saved as a preset", password_generator.generator_mode))
+        }
+    }
+}
+
+// Sets the generator page up the way a preset says
+fn apply_preset(password_generator: &mut PasswordGenerator, policy: &presets::PresetPolicy) {
+    match policy {
+        presets::PresetPolicy::Random { length } => {
+            password_generator.generator_mode = GeneratorMode::Random;
+            password_generator.random_length_input = length.to_string();
+        }
+        presets::PresetPolicy::Pattern { pattern } => {
+            password_generator.generator_mode = GeneratorMode::Pattern;
+            password_generator.pattern_input = pattern.clone();
+        }
+        presets::PresetPolicy::Pronounceable { length, digits, symbols } => {
+            password_generator.generator_mode = GeneratorMode::Pronounceable;
+            password_generator.pronounceable_length_input = length.to_string();
+            password_generator.pronounceable_digits_input = digits.to_string();
+            password_generator.pronounceable_symbols_input = symbols.to_string();
+        }
+        presets::PresetPolicy::Passphrase { words, separator } => {
+            password_generator.generator_mode = GeneratorMode::Passphrase;
+            password_generator.passphrase_words_input = words.to_string();
+            password_generator.passphrase_separator_input = separator.clone();
+        }
+    }
+}
+
+// Dice mode's progress with the rolls entered so far
+fn dice_progress(password_generator: &PasswordGenerator) -> Result<dice::DiceProgress, String> {
+    let length = match password_generator.dice_length_input.trim() {
+        "" => password_generator.dice_output.default_length(),
+        length => length.parse::<usize>().map_err(|_| "Length must be a whole number".to_string())?,
+    };
+    dice::from_rolls(
+        password_generator.dice_source,
+        password_generator.dice_output,
+        length,
+        &password_generator.dice_rolls_input,
+    )
+}
+
+// Shows the dice password once the rolls cover all of it; the fields show how far along it is
+fn dice_generator_password(password_generator: &mut PasswordGenerator) {
+    match dice_progress(password_generator) {
+        Ok(progress) if progress.is_complete() => password_generator.generated_password = progress.password,
+        _ => password_generator.generated_password.clear(),
+    }
+}
+
+// The Batch page's policy, from the same inputs the generator page uses
+fn batch_policy(password_generator: &PasswordGenerator) -> Result<batch::BatchPolicy, String> {
+    match password_generator.batch_kind {
+        batch::BatchKind::Random => {
+            let length = match password_generator.batch_length_input.trim() {
+                "" => 16,
+                length => length.parse::<usize>().map_err(|_| "Length must be a whole number".to_string())?,
+            };
+            Ok(batch::BatchPolicy::Random { length })
+        }
+        batch::BatchKind::Pattern => genr::parse_pattern(&password_generator.pattern_input).map(batch::BatchPolicy::Pattern),
+        batch::BatchKind::Pronounceable => pronounceable_options(password_generator).map(batch::BatchPolicy::Pronounceable),
+        batch::BatchKind::Passphrase => passphrase_options(password_generator)
+            .map(|(words, separator)| batch::BatchPolicy::Passphrase { words, separator }),
+    }
+}
+
+// Reads the pronounceable mode's inputs; empty ones keep their defaults
+fn pronounceable_options(password_generator: &PasswordGenerator) -> Result<genr::PronounceableOptions, String> {
+    let defaults = genr::PronounceableOptions::default();
+    let number = |input: &str, default: usize, name: &str| match input.trim() {
+        "" => Ok(default),
+        value => value.parse::<usize>().map_err(|_| format!("{} must be a whole number", name)),
+    };
+    let options = genr::PronounceableOptions {
+        length: number(&password_generator.pronounceable_length_input, defaults.length, "Length")?,
+        digits: number(&password_generator.pronounceable_digits_input, defaults.digits, "Digits")?,
+        symbols: number(&password_generator.pronounceable_symbols_input, defaults.symbols, "Symbols")?,
+    };
+    options.check()?;
+    Ok(options)
+}
+
+// Works out the generator page's derived password from its fields
+fn derive_generator_password(password_generator: &mut PasswordGenerator) {
+    let mut profile = password_generator.derive_profile.clone();
+    let length = match password_generator.derive_length_input.trim() {
+        "" => Ok(derive::DerivationProfile::default().length),
+        length => length.parse::<usize>().map_err(|_| "Length must be a whole number".to_string()),
+    };
+    let result = parse_counter(&password_generator.derive_counter_input)
+        .and_then(|counter| {
+            profile.counter = counter;
+            profile.length = length?;
+            derive::derive_password(&password_generator.derive_master, &profile)
+        });
+    match result {
+        Ok(password) => {
+            password_generator.generated_password = password;
+            password_generator.derive_profile = profile;
+            password_generator.status_message.clear();
+        }
+        Err(e) => {
+            password_generator.generated_password.clear();
+            password_generator.status_message = e;
+        }
+    }
+}
+
+fn clear_entry_form(password_generator: &mut PasswordGenerator) {
+    password_generator.password_name.clear();
+    password_generator.saved_password.clear();
+    password_generator.breach_warning.clear();
+    password_generator.site_rules = None;
+    password_generator.password_from_generator = false;
+    password_generator.pending_derivation = None;
+    password_generator.website.clear();
+    password_generator.username.clear();
+    password_generator.notes.clear();
+    password_generator.secure_note = text_editor::Content::new();
+    password_generator.cardholder.clear();
+    password_generator.card_number.clear();
+    password_generator.card_expiry.clear();
+    password_generator.card_cvv.clear();
+    password_generator.full_name.clear();
+    password_generator.address.clear();
+    password_generator.phone.clear();
+    password_generator.email.clear();
+    password_generator.api_key_expiry.clear();
+    password_generator.wifi_ssid.clear();
+    password_generator.wifi_security = wifi::WifiSecurity::default();
+    password_generator.wifi_hidden = false;
+    password_generator.otp_input.clear();
+    password_generator.otp_period.clear();
+}
+
+// Current page view (unchanged from your original)
+fn view_current(password_generator: &PasswordGenerator) -> Element<'_, Message> {
+    let reload_svg = svg::Handle::from_path("assets/reload.svg"); 
+    let copy_svg = svg::Handle::from_path("assets/copy.svg");
+    let save_svg = svg::Handle::from_path("assets/save.svg"); 
+
+    let header = container(
+        column![
+            text("A password generator:")
+                .size(20),
+        ]
+        .spacing(2)
+        .align_x(iced::Alignment::Center)
+    )
+    .width(Fill)
+    .padding(40);
+
+    let reload_btn = button(
+        container(
+            svg::Svg::new(reload_svg).width(20).height(20)
+        )
+        .center_x(Fill)
+        .center_y(Fill)
+    )
+    .on_press(Message::Reload)
+    .padding(10)
+    .width(40)
+    .height(40);
+
+    let copy_btn = button(
+        container(
+            svg::Svg::new(copy_svg).width(20).height(20)
+        )
+        .center_x(Fill)
+        .center_y(Fill)
+    )
+    .on_press(Message::Copy)
+    .padding(10)
+    .width(40)
+    .height(40);
+
+    let password_section = container(
+        column![
+            text(":)")
+                .size(14),
+            
+            row![
+                reload_btn,
+                Space::with_width(15),
+                container(
+                    text(if password_generator.generated_password.is_empty() { 
+                        match password_generator.generator_mode {
+                            GeneratorMode::Random => "Click to generate password",
+                            GeneratorMode::Derived => "Click to derive password",
+                            GeneratorMode::Pattern => "Click to generate from the pattern",
+                            GeneratorMode::Pronounceable => "Click to generate a pronounceable password",
+                            GeneratorMode::Passphrase => "Click to generate a passphrase",
+                            GeneratorMode::Dice => "Enter your rolls below",
+                        }
+                    } else { 
+                        &password_generator.generated_password 
+                    })
+                    .size(18)
+                )
+                .padding(20)
+                .width(Fill),
+                Space::with_width(15),
+                copy_btn,
+            ]
+            .align_y(iced::Alignment::Center),
+            
+            button(
+                container(
+                    row![
+                        svg::Svg::new(save_svg).width(20).height(20),
+                        text("Save").size(16)
+                    ]
+                    .spacing(10)
+                )
+            )
+            .on_press(Message::Save)
+            .padding([15, 25])
+            .width(150),
+        ]
+        .spacing(20)
+        .align_x(iced::Alignment::Center)
+    )
+    .padding(25)
+    .width(Fill);
+
+    // The preset matching the current settings shows as selected
+    let preset_names: Vec<String> = password_generator.settings.presets.iter().map(|preset| preset.name.clone()).collect();
+    let current_policy = current_preset_policy(password_generator).ok();
+    let selected_preset = password_generator
+        .settings
+        .presets
+        .iter()
+        .find(|preset| current_policy.as_ref() == Some(&preset.policy))
+        .map(|preset| preset.name.clone());
+    let mode_picker = row![
+        text("Mode").size(14),
+        pick_list(&GeneratorMode::ALL[..], Some(password_generator.generator_mode), Message::GeneratorModeSelected)
+            .padding(8)
+            .width(200),
+        text("Preset").size(14),
+        pick_list(preset_names, selected_preset, Message::PresetSelected)
+            .placeholder("None")
+            .padding(8)
+            .width(150),
+    ]
+    .spacing(10)
+    .align_y(iced::Alignment::Center);
+
+    // Derived mode: the inputs the password is worked out from
+    let derive_fields: Element<Message> = if password_generator.generator_mode == GeneratorMode::Derived {
+        let profile = &password_generator.derive_profile;
+        let classes = row(derive::CharacterClass::ALL.into_iter().map(|class| {
+            checkbox(class.to_string(), profile.uses(class))
+                .on_toggle(move |used| Message::DeriveClassToggled(class, used))
+                .into()
+        }))
+        .spacing(15);
+        column![
+            form_field("Site", "e.g., example.org", &profile.site, Message::DeriveSiteChanged),
+            form_field("Login", "e.g., contact@example.org", &profile.login, Message::DeriveLoginChanged),
+            secure_form_field("Master Password", "Never saved", &password_generator.derive_master, Message::DeriveMasterChanged),
+            row![
+                text("Counter").size(14),
+                text_input("1", &password_generator.derive_counter_input)
+                    .on_input(Message::DeriveCounterChanged)
+                    .padding(8)
+                    .width(60),
+                text("Length").size(14),
+                text_input("16", &password_generator.derive_length_input)
+                    .on_input(Message::DeriveLengthChanged)
+                    .padding(8)
+                    .width(60),
+            ]
+            .spacing(10)
+            .align_y(iced::Alignment::Center),
+            classes,
+            text("The same inputs always give the same password; only the site, login, counter and length are saved")
+                .size(12)
+                .color(iced::Color::from_rgb(0.5, 0.5, 0.5)),
+        ]
+        .spacing(10)
+        .width(300)
+        .into()
+    } else {
+        Space::with_height(0).into()
+    };
+
+    let mode_fields: Element<Message> = match password_generator.generator_mode {
+        GeneratorMode::Pattern => pattern_fields(password_generator),
+        GeneratorMode::Pronounceable => pronounceable_fields(password_generator),
+        GeneratorMode::Passphrase => passphrase_fields(password_generator),
+        GeneratorMode::Dice => dice_fields(password_generator),
+        GeneratorMode::Random => random_fields(password_generator),
+        GeneratorMode::Derived => Space::with_height(0).into(),
+    };
+
+    let status: Element<Message> = if password_generator.status_message.is_empty() {
+        Space::with_height(0).into()
+    } else {
+        text(&password_generator.status_message)
+            .size(14)
+            .color(iced::Color::from_rgb(0.8, 0.0, 0.0))
+            .into()
+    };
+
+    let navigation = row![
+        button("View Passwords").on_press(Message::NavigateTo(Pages::ViewPasswords)),
+        Space::with_width(10),
+        button("Batch").on_press(Message::NavigateTo(Pages::Batch)),
+        Space::with_width(10),
+        button("History").on_press(Message::NavigateTo(Pages::GeneratorHistory)),
+        Space::with_width(10),
+        button("Security").on_press(Message::NavigateTo(Pages::Security)),
+        Space::with_width(10),
+        button("Settings").on_press(Message::NavigateTo(Pages::Settings)),
+    ]
+    .spacing(10);
+
+    let rotation_notice: Element<Message> = if password_generator.rotation_notice.is_empty() {
+        Space::with_height(0).into()
+    } else {
+        column![
+            text(&password_generator.rotation_notice)
+                .size(14)
+                .color(iced::Color::from_rgb(0.8, 0.0, 0.0)),
+            Space::with_height(5),
+            row![
+                button(text("Show").size(12))
+                    .on_press(Message::ShowDueForRotation)
+                    .padding([4, 10]),
+                button(text("Dismiss").size(12))
+                    .on_press(Message::DismissRotationNotice)
+                    .style(button::secondary)
+                    .padding([4, 10]),
+            ]
+            .spacing(10),
+        ]
+        .align_x(iced::Alignment::Center)
+        .into()
+    };
+
+    let main_content = column![
+        rotation_notice,
+        header,
+        mode_picker,
+        Space::with_height(15),
+        derive_fields,
+        mode_fields,
+        status,
+        password_section,
+        Space::with_height(20),
+        navigation,
+        Space::with_height(40),
+    ]
+    .spacing(0)
+    .width(Fill)
+    .align_x(iced::Alignment::Center);
+
+    container(scrollable(main_content))
+        .padding(20)
+        .width(Fill)
+        .height(Fill)
+        .into()
+}
+
+// "52.3 bits of entropy (Fair)" under a generator's options
+fn entropy_feedback<'a>(bits: f64) -> iced::widget::Text<'a> {
+    text(format!("{:.1} bits of entropy ({})", bits, audit::strength_for_bits(bits)))
+        .size(12)
+        .color(iced::Color::from_rgb(0.5, 0.5, 0.5))
+}
+
+// A small labelled number box; the placeholder shows the default
+fn number_input<'a>(label: &'a str, default: usize, value: &'a str, on_input: fn(String) -> Message) -> Element<'a, Message> {
+    row![
+        text(label).size(14),
+        text_input(&default.to_string(), value)
+            .on_input(on_input)
+            .padding(8)
+            .width(50),
+    ]
+    .spacing(5)
+    .align_y(iced::Alignment::Center)
+    .into()
+}
+
+// Pattern generator: the pattern, with its entropy or what's wrong with it
+fn pattern_fields(password_generator: &PasswordGenerator) -> Element<'_, Message> {
+    let feedback = if password_generator.pattern_input.is_empty() {
+        text("[A-Z] sets, \\d digit, u/l/d/s upper/lower/digit/symbol, {4} or {2,5} repeats")
+            .size(12)
+            .color(iced::Color::from_rgb(0.5, 0.5, 0.5))
+    } else {
+        match genr::parse_pattern(&password_generator.pattern_input) {
+            Ok(pattern) => entropy_feedback(pattern.entropy_bits()),
+            Err(e) => text(e).size(12).color(iced::Color::from_rgb(0.8, 0.0, 0.0)),
+        }
+    };
+    column![
+        form_field("Pattern", "e.g., [A-Z]{4}-\\d{4}-[a-z]{4} or uuullldd", &password_generator.pattern_input, Message::PatternChanged),
+        feedback,
+    ]
+    .spacing(5)
+    .width(300)
+    .into()
+}
+
+// Pronounceable generator: length and how many digits and symbols to mix in
+fn pronounceable_fields(password_generator: &PasswordGenerator) -> Element<'_, Message> {
+    let defaults = genr::PronounceableOptions::default();
+    let feedback = match pronounceable_options(password_generator) {
+        Ok(options) => entropy_feedback(options.entropy_bits()),
+        Err(e) => text(e).size(12).color(iced::Color::from_rgb(0.8, 0.0, 0.0)),
+    };
+    column![
+        row![
+            number_input("Length", defaults.length, &password_generator.pronounceable_length_input, Message::PronounceableLengthChanged),
+            number_input("Digits", defaults.digits, &password_generator.pronounceable_digits_input, Message::PronounceableDigitsChanged),
+            number_input("Symbols", defaults.symbols, &password_generator.pronounceable_symbols_input, Message::PronounceableSymbolsChanged),
+        ]
+        .spacing(15),
+        feedback,
+    ]
+    .spacing(5)
+    .align_x(iced::Alignment::Center)
+    .into()
+}
+
+// Passphrase generator: how many words and what goes between them
+fn random_fields(password_generator: &PasswordGenerator) -> Element<'_, Message> {
+    let feedback = match random_length(password_generator) {
+        // Letters and digits: 62 characters
+        Ok(length) => entropy_feedback(length as f64 * 62f64.log2()),
+        Err(e) => text(e).size(12).color(iced::Color::from_rgb(0.8, 0.0, 0.0)),
+    };
+    column![
+        number_input("Length", 16, &password_generator.random_length_input, Message::RandomLengthChanged),
+        feedback,
+    ]
+    .spacing(5)
+    .align_x(iced::Alignment::Center)
+    .into()
+}
+
+fn dice_fields(password_generator: &PasswordGenerator) -> Element<'_, Message> {
+    let muted = iced::Color::from_rgb(0.5, 0.5, 0.5);
+    let unit = match password_generator.dice_output {
+        dice::DiceOutput::Words => "words",
+        dice::DiceOutput::Characters => "characters",
+    };
+    let (rolls, placeholder, note) = match password_generator.dice_source {
+        dice::DiceSource::Dice => ("rolls", "e.g., 3 5 1 6 2 4", "Roll real dice and type what they show; rolls are never saved"),
+        dice::DiceSource::Cards => ("cards", "e.g., AS 10H QD 7C", "Shuffle the card back into the deck before every draw; cards are never saved"),
+    };
+    let feedback: Element<Message> = match dice_progress(password_generator) {
+        Ok(progress) => {
+            let collected = text(format!(
+                "{:.1} bits collected from {} {} - {} of {} {}",
+                progress.collected_bits, progress.rolls - progress.unused_rolls, rolls, progress.produced, progress.wanted, unit
+            ))
+            .size(12)
+            .color(muted);
+            let next = if !progress.is_complete() {
+                text(format!("Enter at least {} more {}", progress.rolls_needed, rolls)).size(12).color(muted)
+            } else if progress.unused_rolls > 0 {
+                text(format!("Done; the last {} {} weren't needed", progress.unused_rolls, rolls)).size(12).color(muted)
+            } else {
+                entropy_feedback(progress.password_bits)
+            };
+            column![collected, next].spacing(3).align_x(iced::Alignment::Center).into()
+        }
+        Err(e) => text(e).size(12).color(iced::Color::from_rgb(0.8, 0.0, 0.0)).into(),
+    };
+    column![
+        row![
+            pick_list(&dice::DiceSource::ALL[..], Some(password_generator.dice_source), Message::DiceSourceSelected)
+                .padding(8),
+            pick_list(&dice::DiceOutput::ALL[..], Some(password_generator.dice_output), Message::DiceOutputSelected)
+                .padding(8),
+            number_input("Length", password_generator.dice_output.default_length(), &password_generator.dice_length_input, Message::DiceLengthChanged),
+        ]
+        .spacing(10)
+        .align_y(iced::Alignment::Center),
+        text_input(placeholder, &password_generator.dice_rolls_input)
+            .on_input(Message::DiceRollsChanged)
+            .padding(8)
+            .width(400),
+        feedback,
+        text(note)
+            .size(12)
+            .color(muted),
+    ]
+    .spacing(5)
+    .align_x(iced::Alignment::Center)
+    .into()
+}
+
+fn passphrase_fields(password_generator: &PasswordGenerator) -> Element<'_, Message> {
+    let feedback = match passphrase_options(password_generator) {
+        Ok((words, _)) => entropy_feedback(genr::passphrase_entropy(words)),
+        Err(e) => text(e).size(12).color(iced::Color::from_rgb(0.8, 0.0, 0.0)),
+    };
+    column![
+        row![
+            number_input("Words", DEFAULT_PASSPHRASE_WORDS, &password_generator.passphrase_words_input, Message::PassphraseWordsChanged),
+            text("Separator").size(14),
+            text_input(DEFAULT_PASSPHRASE_SEPARATOR, &password_generator.passphrase_separator_input)
+                .on_input(Message::PassphraseSeparatorChanged)
+                .padding(8)
+                .width(50),
+        ]
+        .spacing(10)
+        .align_y(iced::Alignment::Center),
+        feedback,
+    ]
+    .spacing(5)
+    .align_x(iced::Alignment::Center)
+    .into()
+}
+
+// Add Details page view (unchanged from your original)
+fn view_add_details(password_generator: &PasswordGenerator) -> Element<'_, Message> {
+    let mut content_items = vec![
+        text("Save Entry Details")
+            .size(24)
+            .into(),
+        Space::with_height(30).into(),
+    ];
+
+    if !password_generator.status_message.is_empty() {
+        let status_color = if password_generator.status_message.contains("successfully") {
+            iced::Color::from_rgb(0.0, 0.6, 0.0)
+        } else {
+            iced::Color::from_rgb(0.8, 0.0, 0.0)
+        };
+        
+        content_items.push(
+            text(&password_generator.status_message)
+                .size(14)
+                .color(status_color)
+                .into()
+        );
+        content_items.push(Space::with_height(15).into());
+    }
+
+    content_items.extend(vec![
+        form_field("Entry Name *", "e.g., Gmail Account", &password_generator.password_name, Message::PasswordNameChanged),
+        Space::with_height(15).into(),
+        column![
+            text("Entry Type")
+                .size(14),
+            pick_list(&EntryType::ALL[..], Some(password_generator.entry_type), Message::EntryTypeSelected)
+                .padding(10)
+                .width(300),
+        ]
+        .spacing(5)
+        .into(),
+        Space::with_height(15).into(),
+    ]);
+
+    // Fields specific to the selected entry type
+    match password_generator.entry_type {
+        EntryType::Login => {
+            content_items.extend(vec![
+                secure_form_field("Password *", "Your password", &password_generator.saved_password, Message::PasswordChanged),
+                breach_warning(password_generator),
+                derivation_note(password_generator),
+                site_rules_hint(password_generator),
+                Space::with_height(15).into(),
+                form_field("Website/App", "e.g., gmail.com", &password_generator.website, Message::WebsiteChanged),
+                Space::with_height(15).into(),
+                form_field("Username/Email", "e.g., john@example.com", &password_generator.username, Message::UsernameChanged),
+                username_generators(password_generator),
+                Space::with_height(15).into(),
+                secure_form_field("One-Time Code Secret", "otpauth:// URI or base32 secret (optional)", &password_generator.otp_input, Message::OtpInputChanged),
+                Space::with_height(15).into(),
+            ]);
+
+            // A bare secret doesn't say how codes are generated, so let the user choose
+            if !password_generator.otp_input.trim().is_empty() && !is_otpauth_uri(&password_generator.otp_input) {
+                content_items.extend(vec![
+                    row![
+                        pick_list(&otp::OtpKind::ALL[..], Some(password_generator.otp_kind), Message::OtpKindSelected)
+                            .width(80),
+                        pick_list(&otp::OtpAlgorithm::ALL[..], Some(password_generator.otp_algorithm), Message::OtpAlgorithmSelected)
+                            .width(100),
+                        pick_list(&[6u32, 7, 8][..], password_generator.otp_digits, Message::OtpDigitsSelected)
+                            .placeholder("6")
+                            .width(55),
+                        text_input("30", &password_generator.otp_period)
+                            .on_input(Message::OtpPeriodChanged)
+                            .width(50),
+                        text("s").size(14),
+                    ]
+                    .spacing(5)
+                    .width(300)
+                    .align_y(iced::Alignment::Center)
+                    .into(),
+                    Space::with_height(15).into(),
+                ]);
+            }
+        }
+        EntryType::SecureNote => {
+            content_items.extend(vec![
+                column![
+                    text("Note *")
+                        .size(14),
+                    text_editor(&password_generator.secure_note)
+                        .placeholder("Write your note here")
+                        .on_action(Message::SecureNoteEdited)
+                        .padding(10)
+                        .height(150),
+                ]
+                .spacing(5)
+                .width(300)
+                .into(),
+                Space::with_height(15).into(),
+            ]);
+        }
+        EntryType::Card => {
+            content_items.extend(vec![
+                form_field("Cardholder Name", "e.g., John Doe", &password_generator.cardholder, Message::CardholderChanged),
+                Space::with_height(15).into(),
+                secure_form_field("Card Number *", "1234 5678 9012 3456", &password_generator.card_number, Message::CardNumberChanged),
+                Space::with_height(15).into(),
+                form_field("Expiry *", "MM/YY", &password_generator.card_expiry, Message::CardExpiryChanged),
+                Space::with_height(15).into(),
+                secure_form_field("CVV *", "123", &password_generator.card_cvv, Message::CardCvvChanged),
+                Space::with_height(15).into(),
+            ]);
+        }
+        EntryType::Identity => {
+            content_items.extend(vec![
+                form_field("Full Name *", "e.g., John Doe", &password_generator.full_name, Message::FullNameChanged),
+                Space::with_height(15).into(),
+                form_field("Address", "Street, City, Postcode, Country", &password_generator.address, Message::AddressChanged),
+                Space::with_height(15).into(),
+                form_field("Phone", "e.g., +1 555 0100", &password_generator.phone, Message::PhoneChanged),
+                Space::with_height(15).into(),
+                form_field("Email", "e.g., john@example.com", &password_generator.email, Message::EmailChanged),
+                Space::with_height(15).into(),
+            ]);
+        }
+        EntryType::ApiKey => {
+            content_items.extend(vec![
+                secure_form_field("Key/Token *", "Your API key", &password_generator.saved_password, Message::PasswordChanged),
+                breach_warning(password_generator),
+                Space::with_height(15).into(),
+                form_field("Service", "e.g., api.github.com", &password_generator.website, Message::WebsiteChanged),
+                Space::with_height(15).into(),
+                form_field("Expires On", "YYYY-MM-DD (optional)", &password_generator.api_key_expiry, Message::ApiKeyExpiryChanged),
+                Space::with_height(15).into(),
+            ]);
+        }
+        EntryType::Wifi => {
+            content_items.extend(vec![
+                form_field("Network Name (SSID) *", "e.g., HomeNetwork", &password_generator.wifi_ssid, Message::WifiSsidChanged),
+                Space::with_height(15).into(),
+                column![
+                    text("Security")
+                        .size(14),
+                    pick_list(&wifi::WifiSecurity::ALL[..], Some(password_generator.wifi_security), Message::WifiSecuritySelected)
+                        .padding(10)
+                        .width(300),
+                ]
+                .spacing(5)
+                .into(),
+                Space::with_height(15).into(),
+            ]);
+            if password_generator.wifi_security != wifi::WifiSecurity::Open {
+                content_items.extend(vec![
+                    secure_form_field("Passphrase *", "8 to 63 characters", &password_generator.saved_password, Message::PasswordChanged),
+                    breach_warning(password_generator),
+                    Space::with_height(5).into(),
+                    button(text("Generate WPA Key").size(12))
+                        .on_press(Message::GenerateWpaKey)
+                        .padding([5, 10])
+                        .into(),
+                    Space::with_height(15).into(),
+                ]);
+            }
+            content_items.extend(vec![
+                checkbox("Hidden network", password_generator.wifi_hidden)
+                    .on_toggle(Message::WifiHiddenToggled)
+                    .into(),
+                Space::with_height(15).into(),
+            ]);
+        }
+    }
+
+    if password_generator.entry_type != EntryType::SecureNote {
+        content_items.extend(vec![
+            form_field("Notes", "Additional notes (optional)", &password_generator.notes, Message::NotesChanged),
+            Space::with_height(15).into(),
+        ]);
+    }
+
+    content_items.extend(vec![
+        Space::with_height(15).into(),
+        
+        row![
+            button("Cancel")
+                .on_press(Message::NavigateTo(Pages::Current))
+                .padding([10, 20]),
+            Space::with_width(15),
+            button("Save Entry")
+                .on_press(Message::SavePasswordDetails)
+                .padding([10, 20]),
+        ]
+        .spacing(10)
+        .into(),
+        
+        Space::with_height(20).into(),
+        text("* Required fields")
+            .size(12)
+            .into(),
+    ]);
+
+    let content = column(content_items)
+        .spacing(0)
+        .width(Fill)
+        .align_x(iced::Alignment::Center);
+
+    container(scrollable(content))
+        .padding(40)
+        .width(Fill)
+        .height(Fill)
+        .into()
+}
+
+// The history password field, shown while the password history is locked
+fn history_unlock(password_generator: &PasswordGenerator) -> Element<'_, Message> {
+    column![
+        row![
+            text_input("History password", &password_generator.history_password_input)
+                .on_input(Message::HistoryPasswordChanged)
+                .on_submit(Message::UnlockHistory)
+                .secure(true)
+                .padding(10),
+            button("Unlock")
+                .on_press(Message::UnlockHistory)
+                .padding([10, 20]),
+        ]
+        .spacing(10),
+        text("Old passwords are encrypted with this password. The first one you enter becomes it.")
+            .size(12)
+            .color(iced::Color::from_rgb(0.5, 0.5, 0.5)),
+    ]
+    .spacing(5)
+    .width(400)
+    .into()
+}
+
+// A labelled text input as used on the AddDetails form
+fn form_field<'a>(
+    label: &'a str,
+    placeholder: &'a str,
+    value: &'a str,
+    on_input: fn(String) -> Message,
+) -> Element<'a, Message> {
+    column![
+        text(label)
+            .size(14),
+        text_input(placeholder, value)
+            .on_input(on_input)
+            .padding(10)
+            .width(300),
+    ]
+    .spacing(5)
+    .into()
+}
+
+// Same as form_field but hides what is typed
+fn secure_form_field<'a>(
+    label: &'a str,
+    placeholder: &'a str,
+    value: &'a str,
+    on_input: fn(String) -> Message,
+) -> Element<'a, Message> {
+    column![
+        text(label)
+            .size(14),
+        text_input(placeholder, value)
+            .on_input(on_input)
+            .padding(10)
+            .width(300)
+            .secure(true),
+    ]
+    .spacing(5)
+    .into()
+}
+
+// Enhanced View Passwords page that displays the loaded passwords
+fn view_passwords(password_generator: &PasswordGenerator) -> Element<'_, Message> {
+    let mut content_items = vec![
+        text("Saved Passwords")
+            .size(24)
+            .into(),
+        Space::with_height(20).into(),
+    ];
+
+    // Show status message if any
+    if !password_generator.status_message.is_empty() {
+        let status_color = if password_generator.status_message.contains("loaded") || 
+                             password_generator.status_message.contains("Refreshed") ||
+                             password_generator.status_message.contains("copied") {
+            iced::Color::from_rgb(0.0, 0.6, 0.0)
+        } else {
+            iced::Color::from_rgb(0.8, 0.0, 0.0)
+        };
+        
+        content_items.push(
+            text(&password_generator.status_message)
+                .size(14)
+                .color(status_color)
+                .into()
+        );
+        content_items.push(Space::with_height(15).into());
+    }
+
+    // Display passwords if any are loaded
+    if password_generator.loaded_passwords.is_empty() {
+        content_items.extend(vec![
+            text("No passwords found")
+                .size(16)
+                .into(),
+            Space::with_height(10).into(),
+            text("Save some passwords first!")
+                .size(14)
+                .into(),
+        ]);
+    } else {
+        content_items.push(
+            checkbox("Due for rotation", password_generator.due_only)
+                .on_toggle(Message::ToggleDueOnly)
+                .size(16)
+                .into()
+        );
+        content_items.push(Space::with_height(10).into());
+
+        // Create a scrollable list of passwords
+        let now = chrono::Utc::now().naive_utc();
+        let password_list: Vec<Element<Message>> = password_generator.loaded_passwords
+            .iter()
+            .enumerate()
+            .filter(|(_, password)| !password_generator.due_only || audit::rotation_status(password, now).needs_attention())
+            .map(|(index, password)| entry_card(index, password))
+            .collect();
+
+        if password_list.is_empty() {
+            content_items.push(text("No entries are due for rotation").size(14).into());
+        }
+        content_items.push(
+            scrollable(
+                column(password_list)
+                    .spacing(10)
+            ).height(300)
+             .width(Fill)
+             .into()
+        );
+    }
+
+    let reload_svg = svg::Handle::from_path("assets/reload.svg"); 
+    let back_svg = svg::Handle::from_path("assets/back.svg");
+
+    // Navigation buttons
+    content_items.extend(vec![
+        Space::with_height(40).into(),
+        row![
+            button(
+                container(
+                    svg::Svg::new(back_svg).width(20).height(20)
+                )
             )
-            .on_press(Message::Save)
-            .padding([15, 25])
-            .width(150),
+                .on_press(Message::NavigateTo(Pages::Current))
+                .padding([10, 20]),
+            Space::with_width(15),
+            button(
+                container(
+                    svg::Svg::new(reload_svg).width(20).height(20)
+                )
+            )
+                .on_press(Message::LoadPasswordsFromFile)
+                .padding([10, 20]),
+            Space::with_width(15),
+            button("Add Entry")
+                .on_press(Message::NavigateTo(Pages::AddDetails))
+                .padding([10, 20]),
+            Space::with_width(15),
+            button("Backup")
+                .on_press(Message::NavigateTo(Pages::Backup))
+                .padding([10, 20]),
+            button("Export")
+                .on_press(Message::NavigateTo(Pages::Export))
+                .padding([10, 20]),
+        ]
+        .spacing(10)
+        .into(),
+        Space::with_height(10).into(),
+        row![
+            button("Import Codes")
+                .on_press(Message::NavigateTo(Pages::ImportOtp))
+                .padding([10, 20]),
+            button("Import CSV")
+                .on_press(Message::NavigateTo(Pages::ImportCsv))
+                .padding([10, 20]),
+            button("KeePass")
+                .on_press(Message::NavigateTo(Pages::KeePass))
+                .padding([10, 20]),
+            button("Bitwarden")
+                .on_press(Message::NavigateTo(Pages::Bitwarden))
+                .padding([10, 20]),
+            button("Trash")
+                .on_press(Message::NavigateTo(Pages::Trash))
+                .padding([10, 20]),
+        ]
+        .spacing(10)
+        .into(),
+        
+    ]);
+
+    let content = column(content_items)
+        .spacing(0)
+        .align_x(iced::Alignment::Center);
+
+    container(content)
+        .padding(40)
+        .width(Fill)
+        .height(Fill)
+        .into()
+}
+
+// Red for overdue, orange for due soon
+fn rotation_color(status: audit::RotationStatus) -> iced::Color {
+    match status {
+        audit::RotationStatus::Overdue(_) => iced::Color::from_rgb(0.8, 0.0, 0.0),
+        _ => iced::Color::from_rgb(0.9, 0.5, 0.0),
+    }
+}
+
+// A single entry in the View Passwords list, rendered according to its type
+fn entry_card(index: usize, password: &st_json::PasswordDetails) -> Element<'_, Message> {
+    let muted = iced::Color::from_rgb(0.6, 0.6, 0.6);
+    let rotation = audit::rotation_status(password, chrono::Utc::now().naive_utc());
+    let mut lines: Vec<Element<Message>> = vec![
+        // Entry name (title) and type
+        row![
+            text(&password.name)
+                .size(18)
+                .color(iced::Color::from_rgb(0.2, 0.6, 1.0)),
+            Space::with_width(10),
+            text(password.kind.label())
+                .size(12)
+                .color(muted),
+            Space::with_width(10),
+            text(rotation.to_string())
+                .size(12)
+                .color(rotation_color(rotation)),
+            Space::with_width(Fill),
+            button(text("Details").size(12))
+                .on_press(Message::OpenEntry(index))
+                .padding([2, 8]),
+        ]
+        .align_y(iced::Alignment::Center)
+        .into(),
+        Space::with_height(5).into(),
+    ];
+
+    // Only show fields that have a value
+    let mut detail = |label: &str, value: &str| {
+        if !value.is_empty() {
+            lines.push(text(format!("{}: {}", label, value)).size(14).into());
+        }
+    };
+
+    match &password.kind {
+        st_json::EntryKind::Login => {
+            detail("Website", &password.website);
+            detail("Username", &password.username);
+            detail("Notes", &password.notes);
+            // Password (hidden for security); derived ones aren't stored at all
+            let shown = match &password.derivation {
+                Some(profile) => format!("derived, #{}", profile.counter),
+                None => "*".repeat(password.value.len()),
+            };
+            lines.push(
+                text(format!("Password: {}", shown))
+                    .size(14)
+                    .color(muted)
+                    .into(),
+            );
+        }
+        st_json::EntryKind::SecureNote => {
+            lines.push(text(&password.notes).size(14).into());
+        }
+        st_json::EntryKind::Card { cardholder, number, expiry, .. } => {
+            detail("Cardholder", cardholder);
+            let digits: String = number.chars().filter(|c| c.is_ascii_digit()).collect();
+            let last_four = &digits[digits.len().saturating_sub(4)..];
+            detail("Card", &format!("**** **** **** {}", last_four));
+            detail("Expires", expiry);
+            detail("Notes", &password.notes);
+        }
+        st_json::EntryKind::Identity { full_name, address, phone, email } => {
+            detail("Name", full_name);
+            detail("Address", address);
+            detail("Phone", phone);
+            detail("Email", email);
+            detail("Notes", &password.notes);
+        }
+        st_json::EntryKind::ApiKey { expires_at } => {
+            detail("Service", &password.website);
+            detail("Notes", &password.notes);
+            lines.push(
+                text(format!("Key: {}", "*".repeat(password.value.len().min(24))))
+                    .size(14)
+                    .color(muted)
+                    .into(),
+            );
+            if !expires_at.is_empty() {
+                let expired = chrono::NaiveDate::parse_from_str(expires_at, "%Y-%m-%d")
+                    .map(|date| date < chrono::Utc::now().date_naive())
+                    .unwrap_or(false);
+                let color = if expired {
+                    iced::Color::from_rgb(0.8, 0.0, 0.0)
+                } else {
+                    muted
+                };
+                lines.push(
+                    text(format!("Expires: {}{}", expires_at, if expired { " (expired)" } else { "" }))
+                        .size(14)
+                        .color(color)
+                        .into(),
+                );
+            }
+        }
+        st_json::EntryKind::Wifi { ssid, security, hidden } => {
+            detail("Network", ssid);
+            detail("Security", &security.to_string());
+            if *hidden {
+                detail("Hidden", "yes");
+            }
+            detail("Notes", &password.notes);
+            if !password.value.is_empty() {
+                lines.push(
+                    text(format!("Passphrase: {}", "*".repeat(password.value.len().min(24))))
+                        .size(14)
+                        .color(muted)
+                        .into(),
+                );
+            }
+        }
+    }
+
+    // Live one-time code
+    if let Some(config) = &password.otp {
+        match otp::current_code(config, otp::unix_now()) {
+            Ok((code, seconds_left)) => {
+                let (first, second) = code.split_at(code.len() / 2);
+                let mut code_row = row![
+                    text(format!("Code: {} {}", first, second))
+                        .size(16)
+                        .color(iced::Color::from_rgb(0.2, 0.6, 1.0)),
+                ]
+                .spacing(10)
+                .align_y(iced::Alignment::Center);
+
+                code_row = match seconds_left {
+                    Some(seconds) => code_row.push(text(format!("{}s", seconds)).size(12).color(muted)),
+                    None => code_row.push(
+                        button(text("Next").size(12))
+                            .on_press(Message::NextHotpCode(index))
+                            .padding([2, 8]),
+                    ),
+                };
+                code_row = code_row.push(
+                    button(text("Copy").size(12))
+                        .on_press(Message::CopyOtp(index))
+                        .padding([2, 8]),
+                );
+                lines.push(code_row.into());
+            }
+            Err(e) => {
+                lines.push(
+                    text(format!("Code: {}", e))
+                        .size(14)
+                        .color(iced::Color::from_rgb(0.8, 0.0, 0.0))
+                        .into(),
+                );
+            }
+        }
+    }
+
+    // Created date
+    lines.push(
+        text(format!("Created: {}", password.created_at))
+            .size(12)
+            .color(iced::Color::from_rgb(0.5, 0.5, 0.5))
+            .into(),
+    );
+
+    container(column(lines).spacing(3))
+        .padding(15)
+        .width(Fill)
+        .into()
+}
+
+// Import one-time code secrets from a QR code image or pasted otpauth URIs
+fn view_import_otp(password_generator: &PasswordGenerator) -> Element<'_, Message> {
+    let mut content_items: Vec<Element<Message>> = vec![
+        text("Import One-Time Codes")
+            .size(24)
+            .into(),
+        Space::with_height(10).into(),
+        text("QR codes and exports are decoded on this computer, nothing is uploaded")
+            .size(12)
+            .color(iced::Color::from_rgb(0.5, 0.5, 0.5))
+            .into(),
+        Space::with_height(20).into(),
+    ];
+
+    if !password_generator.status_message.is_empty() {
+        let status_color = if password_generator.status_message.contains("successfully") ||
+                             password_generator.status_message.contains("Found") {
+            iced::Color::from_rgb(0.0, 0.6, 0.0)
+        } else {
+            iced::Color::from_rgb(0.8, 0.0, 0.0)
+        };
+
+        content_items.push(
+            text(&password_generator.status_message)
+                .size(14)
+                .color(status_color)
+                .into()
+        );
+        content_items.push(Space::with_height(15).into());
+    }
+
+    content_items.extend(vec![
+        column![
+            text("QR Code Image (PNG/JPG)")
+                .size(14),
+            row![
+                text_input("/path/to/qr-code.png", &password_generator.otp_image_path)
+                    .on_input(Message::OtpImagePathChanged)
+                    .on_submit(Message::ScanOtpImage)
+                    .padding(10),
+                button("Scan")
+                    .on_press(Message::ScanOtpImage)
+                    .padding([10, 20]),
+            ]
+            .spacing(10),
+        ]
+        .spacing(5)
+        .width(450)
+        .into(),
+        Space::with_height(15).into(),
+        column![
+            text("Or paste otpauth:// / otpauth-migration:// URIs")
+                .size(14),
+            row![
+                text_input("otpauth-migration://offline?data=...", &password_generator.otp_import_text)
+                    .on_input(Message::OtpImportTextChanged)
+                    .on_submit(Message::ParseOtpImportText)
+                    .padding(10)
+                    .secure(true),
+                button("Read")
+                    .on_press(Message::ParseOtpImportText)
+                    .padding([10, 20]),
+            ]
+            .spacing(10),
+        ]
+        .spacing(5)
+        .width(450)
+        .into(),
+        Space::with_height(20).into(),
+    ]);
+
+    if !password_generator.otp_import_preview.is_empty() {
+        let preview: Vec<Element<Message>> = password_generator.otp_import_preview
+            .iter()
+            .map(|config| text(otp_import::describe(config)).size(14).into())
+            .collect();
+
+        content_items.push(
+            scrollable(column(preview).spacing(5))
+                .height(150)
+                .width(450)
+                .into()
+        );
+        content_items.push(Space::with_height(15).into());
+        content_items.push(
+            button(text(format!("Import {} Accounts", password_generator.otp_import_preview.len())))
+                .on_press(Message::ConfirmOtpImport)
+                .padding([10, 20])
+                .into()
+        );
+        content_items.push(Space::with_height(15).into());
+    }
+
+    content_items.push(
+        button("Back to Passwords")
+            .on_press(Message::NavigateTo(Pages::ViewPasswords))
+            .padding([10, 20])
+            .into()
+    );
+
+    let content = column(content_items)
+        .spacing(0)
+        .align_x(iced::Alignment::Center);
+
+    container(content)
+        .padding(40)
+        .width(Fill)
+        .height(Fill)
+        .into()
+}
+
+// CSV import wizard: pick a file, check the column mapping, preview, import
+fn view_import_csv(password_generator: &PasswordGenerator) -> Element<'_, Message> {
+    let muted = iced::Color::from_rgb(0.5, 0.5, 0.5);
+    let mut content_items: Vec<Element<Message>> = vec![
+        text("Import from CSV")
+            .size(24)
+            .into(),
+        Space::with_height(10).into(),
+        text("Exports from Chrome, Firefox, Bitwarden, LastPass or any CSV with a header row")
+            .size(12)
+            .color(muted)
+            .into(),
+        Space::with_height(20).into(),
+    ];
+
+    if !password_generator.status_message.is_empty() {
+        let status_color = if password_generator.status_message.contains("successfully") ||
+                             password_generator.status_message.contains("Found") {
+            iced::Color::from_rgb(0.0, 0.6, 0.0)
+        } else {
+            iced::Color::from_rgb(0.8, 0.0, 0.0)
+        };
+
+        content_items.push(
+            text(&password_generator.status_message)
+                .size(14)
+                .color(status_color)
+                .into()
+        );
+        content_items.push(Space::with_height(15).into());
+    }
+
+    // Step 1: the file
+    content_items.push(
+        column![
+            text("CSV File")
+                .size(14),
+            row![
+                text_input("/path/to/export.csv", &password_generator.csv_path)
+                    .on_input(Message::CsvPathChanged)
+                    .on_submit(Message::LoadCsv)
+                    .padding(10),
+                button("Load")
+                    .on_press(Message::LoadCsv)
+                    .padding([10, 20]),
+            ]
+            .spacing(10),
+        ]
+        .spacing(5)
+        .width(450)
+        .into()
+    );
+    content_items.push(Space::with_height(15).into());
+
+    if let Some(file) = &password_generator.csv_file {
+        // Step 2: where each column goes
+        content_items.push(
+            row![
+                text("Exported from").size(14),
+                pick_list(
+                    &csv_import::CsvSource::ALL[..],
+                    Some(password_generator.csv_source),
+                    Message::CsvSourceSelected,
+                ),
+            ]
+            .spacing(10)
+            .align_y(iced::Alignment::Center)
+            .into()
+        );
+        content_items.push(Space::with_height(10).into());
+
+        let mut mapping_list = column![].spacing(5);
+        for (column_index, header) in file.headers.iter().enumerate() {
+            let sample = file.rows
+                .first()
+                .and_then(|row| row.get(column_index))
+                .map(|cell| cell.as_str())
+                .unwrap_or("");
+            let field = password_generator.csv_mapping.get(column_index).copied().unwrap_or_default();
+            // Don't show sample passwords on screen
+            let sample = if field == csv_import::CsvField::Password || field == csv_import::CsvField::Totp {
+                "*".repeat(sample.chars().count().min(12))
+            } else {
+                sample.chars().take(30).collect()
+            };
+
+            mapping_list = mapping_list.push(
+                row![
+                    column![
+                        text(header).size(14),
+                        text(sample).size(11).color(muted),
+                    ]
+                    .width(200),
+                    pick_list(
+                        &csv_import::CsvField::ALL[..],
+                        Some(field),
+                        move |field| Message::CsvMappingChanged(column_index, field),
+                    ),
+                ]
+                .spacing(10)
+                .align_y(iced::Alignment::Center)
+            );
+        }
+        content_items.push(
+            scrollable(mapping_list)
+                .height(150)
+                .width(450)
+                .into()
+        );
+        content_items.push(Space::with_height(15).into());
+
+        // Step 3: what will be created
+        let mut preview_list = column![].spacing(3);
+        for entry in password_generator.csv_preview.iter().take(50) {
+            let detail = if entry.username.is_empty() {
+                entry.website.clone()
+            } else {
+                format!("{} - {}", entry.username, entry.website)
+            };
+            preview_list = preview_list.push(
+                row![
+                    text(&entry.name).size(14),
+                    text(detail).size(12).color(muted),
+                ]
+                .spacing(10)
+            );
+        }
+        if password_generator.csv_preview.len() > 50 {
+            preview_list = preview_list.push(
+                text(format!("...and {} more", password_generator.csv_preview.len() - 50))
+                    .size(12)
+                    .color(muted)
+            );
+        }
+        for reason in &password_generator.csv_skipped {
+            preview_list = preview_list.push(
+                text(format!("Skipped - {}", reason))
+                    .size(12)
+                    .color(iced::Color::from_rgb(0.8, 0.4, 0.0))
+            );
+        }
+        content_items.push(
+            scrollable(preview_list)
+                .height(120)
+                .width(450)
+                .into()
+        );
+        content_items.push(Space::with_height(15).into());
+
+        let mut import_button = button(text(format!("Import {} Entries", password_generator.csv_preview.len())))
+            .padding([10, 20]);
+        if !password_generator.csv_preview.is_empty() {
+            import_button = import_button.on_press(Message::ConfirmCsvImport);
+        }
+        content_items.push(import_button.into());
+        content_items.push(Space::with_height(15).into());
+    }
+
+    content_items.push(
+        button("Back to Passwords")
+            .on_press(Message::NavigateTo(Pages::ViewPasswords))
+            .padding([10, 20])
+            .into()
+    );
+
+    let content = column(content_items)
+        .spacing(0)
+        .align_x(iced::Alignment::Center);
+
+    container(scrollable(content))
+        .padding(40)
+        .width(Fill)
+        .height(Fill)
+        .into()
+}
+
+// KeePass page: import a KDBX 4 database or export the vault as one
+fn view_keepass(password_generator: &PasswordGenerator) -> Element<'_, Message> {
+    let mut content_items: Vec<Element<Message>> = vec![
+        text("KeePass Import / Export")
+            .size(24)
+            .into(),
+        Space::with_height(10).into(),
+        text("Reads and writes KDBX 4 databases (KeePassXC, KeePass 2.x). Key files are not supported.")
+            .size(12)
+            .color(iced::Color::from_rgb(0.5, 0.5, 0.5))
+            .into(),
+        Space::with_height(20).into(),
+    ];
+
+    if !password_generator.status_message.is_empty() {
+        let status_color = if password_generator.status_message.contains("successfully") {
+            iced::Color::from_rgb(0.0, 0.6, 0.0)
+        } else {
+            iced::Color::from_rgb(0.8, 0.0, 0.0)
+        };
+
+        content_items.push(
+            text(&password_generator.status_message)
+                .size(14)
+                .color(status_color)
+                .into()
+        );
+        content_items.push(Space::with_height(15).into());
+    }
+
+    content_items.extend(vec![
+        form_field("Database File (.kdbx)", "/path/to/database.kdbx", &password_generator.kdbx_path, Message::KdbxPathChanged),
+        Space::with_height(15).into(),
+        secure_form_field("Database Password", "Master password", &password_generator.kdbx_password, Message::KdbxPasswordChanged),
+        Space::with_height(15).into(),
+        secure_form_field("Confirm Password (export only)", "Repeat the password", &password_generator.kdbx_confirm_password, Message::KdbxConfirmPasswordChanged),
+        Space::with_height(30).into(),
+        row![
+            button("Import")
+                .on_press(Message::ImportKdbx)
+                .padding([10, 20]),
+            Space::with_width(15),
+            button("Export")
+                .on_press(Message::ExportKdbx)
+                .padding([10, 20]),
+        ]
+        .spacing(10)
+        .into(),
+        Space::with_height(15).into(),
+        button("Back to Passwords")
+            .on_press(Message::NavigateTo(Pages::ViewPasswords))
+            .padding([10, 20])
+            .into(),
+    ]);
+
+    let content = column(content_items)
+        .spacing(0)
+        .align_x(iced::Alignment::Center);
+
+    container(content)
+        .padding(40)
+        .width(Fill)
+        .height(Fill)
+        .into()
+}
+
+// Backup page: create a password-protected backup, or open one and restore it
+fn view_backup(password_generator: &PasswordGenerator) -> Element<'_, Message> {
+    let mut content_items: Vec<Element<Message>> = vec![
+        text("Backup & Restore")
+            .size(24)
+            .into(),
+        Space::with_height(10).into(),
+        text("A backup holds every entry, the trash and your settings, encrypted with its own password.")
+            .size(12)
+            .color(iced::Color::from_rgb(0.5, 0.5, 0.5))
+            .into(),
+        Space::with_height(20).into(),
+    ];
+
+    if !password_generator.status_message.is_empty() {
+        let status_color = if password_generator.status_message.contains("successfully") {
+            iced::Color::from_rgb(0.0, 0.6, 0.0)
+        } else {
+            iced::Color::from_rgb(0.8, 0.0, 0.0)
+        };
+
+        content_items.push(
+            text(&password_generator.status_message)
+                .size(14)
+                .color(status_color)
+                .into()
+        );
+        content_items.push(Space::with_height(15).into());
+    }
+
+    content_items.extend(vec![
+        form_field("Backup File (.saltrbak)", "/path/to/vault.saltrbak", &password_generator.backup_path, Message::BackupPathChanged),
+        Space::with_height(15).into(),
+        secure_form_field("Backup Password", "Password for this backup", &password_generator.backup_password, Message::BackupPasswordChanged),
+        Space::with_height(15).into(),
+        secure_form_field("Confirm Password (new backups only)", "Repeat the password", &password_generator.backup_confirm_password, Message::BackupConfirmPasswordChanged),
+        Space::with_height(30).into(),
+        row![
+            button("Create Backup")
+                .on_press(Message::CreateBackup)
+                .padding([10, 20]),
+            Space::with_width(15),
+            button("Open Backup")
+                .on_press(Message::OpenBackup)
+                .padding([10, 20]),
+        ]
+        .spacing(10)
+        .into(),
+    ]);
+
+    // Once a backup is opened, show what is in it and how to restore it
+    if let Some(contents) = &password_generator.backup_preview {
+        content_items.extend(vec![
+            Space::with_height(25).into(),
+            text(format!("Backup from {}", contents.created_at))
+                .size(16)
+                .into(),
+            Space::with_height(5).into(),
+            text(format!(
+                "{} entries, {} in the trash",
+                contents.vault.passwords.len(),
+                contents.vault.trash.len()
+            ))
+                .size(14)
+                .into(),
+            Space::with_height(5).into(),
+            text("Merge adds entries you don't already have. Replace swaps your vault and settings for the backup's.")
+                .size(12)
+                .color(iced::Color::from_rgb(0.5, 0.5, 0.5))
+                .into(),
+            Space::with_height(15).into(),
+            row![
+                button("Merge Into Vault")
+                    .on_press(Message::RestoreBackup(backup::RestoreMode::Merge))
+                    .padding([10, 20]),
+                Space::with_width(15),
+                button("Replace Vault")
+                    .on_press(Message::RestoreBackup(backup::RestoreMode::Replace))
+                    .style(button::danger)
+                    .padding([10, 20]),
+            ]
+            .spacing(10)
+            .into(),
+        ]);
+    }
+
+    content_items.extend(vec![
+        Space::with_height(25).into(),
+        button("Back to Passwords")
+            .on_press(Message::NavigateTo(Pages::ViewPasswords))
+            .padding([10, 20])
+            .into(),
+    ]);
+
+    let content = column(content_items)
+        .spacing(0)
+        .width(Fill)
+        .align_x(iced::Alignment::Center);
+
+    container(scrollable(content))
+        .padding(40)
+        .width(Fill)
+        .height(Fill)
+        .into()
+}
+
+// Export page: unencrypted CSV or JSON, for moving to another password manager
+fn view_export(password_generator: &PasswordGenerator) -> Element<'_, Message> {
+    let mut content_items: Vec<Element<Message>> = vec![
+        text("Unencrypted Export")
+            .size(24)
+            .into(),
+        Space::with_height(10).into(),
+        text(export::WARNING)
+            .size(14)
+            .color(iced::Color::from_rgb(0.8, 0.0, 0.0))
+            .into(),
+        Space::with_height(20).into(),
+    ];
+
+    if !password_generator.status_message.is_empty() {
+        let status_color = if password_generator.status_message.contains("successfully") {
+            iced::Color::from_rgb(0.0, 0.6, 0.0)
+        } else {
+            iced::Color::from_rgb(0.8, 0.0, 0.0)
+        };
+
+        content_items.push(
+            text(&password_generator.status_message)
+                .size(14)
+                .color(status_color)
+                .into()
+        );
+        content_items.push(Space::with_height(15).into());
+    }
+
+    content_items.push(
+        row![
+            text("Format").size(14),
+            Space::with_width(10),
+            pick_list(&export::ExportFormat::ALL[..], Some(password_generator.export_format), Message::ExportFormatSelected),
+        ]
+        .align_y(iced::Alignment::Center)
+        .into()
+    );
+    content_items.push(Space::with_height(15).into());
+    content_items.push(text("Fields to include").size(14).into());
+    content_items.push(Space::with_height(5).into());
+
+    // Two columns of checkboxes
+    for pair in export::ExportField::ALL.chunks(2) {
+        let mut field_row = row![].spacing(20);
+        for field in pair {
+            let field = *field;
+            field_row = field_row.push(
+                container(
+                    checkbox(field.to_string(), password_generator.export_fields.contains(&field))
+                        .on_toggle(move |selected| Message::ExportFieldToggled(field, selected))
+                )
+                .width(250)
+            );
+        }
+        content_items.push(field_row.into());
+        content_items.push(Space::with_height(5).into());
+    }
+
+    content_items.extend(vec![
+        Space::with_height(15).into(),
+        form_field("Export File", "/path/to/export.csv", &password_generator.export_path, Message::ExportPathChanged),
+        Space::with_height(15).into(),
+        form_field(
+            "Confirmation",
+            export::CONFIRMATION_PROMPT,
+            &password_generator.export_confirmation,
+            Message::ExportConfirmationChanged,
+        ),
+        Space::with_height(30).into(),
+        row![
+            button("Back to Passwords")
+                .on_press(Message::NavigateTo(Pages::ViewPasswords))
+                .padding([10, 20]),
+            Space::with_width(15),
+            button("Export")
+                .on_press(Message::ExportPlain)
+                .style(button::danger)
+                .padding([10, 20]),
+        ]
+        .spacing(10)
+        .into(),
+    ]);
+
+    let content = column(content_items)
+        .spacing(0)
+        .width(Fill)
+        .align_x(iced::Alignment::Center);
+
+    container(scrollable(content))
+        .padding(40)
+        .width(Fill)
+        .height(Fill)
+        .into()
+}
+
+// Batch page: many passwords under one policy, out to CSV or straight into the vault
+fn view_batch(password_generator: &PasswordGenerator) -> Element<'_, Message> {
+    let muted = iced::Color::from_rgb(0.5, 0.5, 0.5);
+    let mut content_items: Vec<Element<Message>> = vec![
+        text("Batch Generate")
+            .size(24)
+            .into(),
+        Space::with_height(20).into(),
+    ];
+
+    if !password_generator.status_message.is_empty() {
+        let status_color = if password_generator.status_message.contains("successfully") {
+            iced::Color::from_rgb(0.0, 0.6, 0.0)
+        } else {
+            iced::Color::from_rgb(0.8, 0.0, 0.0)
+        };
+
+        content_items.push(
+            text(&password_generator.status_message)
+                .size(14)
+                .color(status_color)
+                .into()
+        );
+        content_items.push(Space::with_height(15).into());
+    }
+
+    content_items.push(
+        row![
+            text("Kind").size(14),
+            Space::with_width(10),
+            pick_list(&batch::BatchKind::ALL[..], Some(password_generator.batch_kind), Message::BatchKindSelected),
+        ]
+        .align_y(iced::Alignment::Center)
+        .into()
+    );
+    content_items.push(Space::with_height(15).into());
+    content_items.push(match password_generator.batch_kind {
+        batch::BatchKind::Random => number_input("Length", 16, &password_generator.batch_length_input, Message::BatchLengthChanged),
+        batch::BatchKind::Pattern => pattern_fields(password_generator),
+        batch::BatchKind::Pronounceable => pronounceable_fields(password_generator),
+        batch::BatchKind::Passphrase => passphrase_fields(password_generator),
+    });
+    content_items.extend(vec![
+        Space::with_height(15).into(),
+        form_field("How many *", "e.g., 25", &password_generator.batch_count_input, Message::BatchCountChanged),
+        Space::with_height(15).into(),
+        form_field("Entry names", batch::DEFAULT_TEMPLATE, &password_generator.batch_names_input, Message::BatchNamesChanged),
+        Space::with_height(5).into(),
+        text("{n} becomes 1, 2, 3, ...")
+            .size(12)
+            .color(muted)
+            .into(),
+        Space::with_height(15).into(),
+        button("Generate")
+            .on_press(Message::GenerateBatch)
+            .padding([10, 20])
+            .into(),
+        Space::with_height(20).into(),
+    ]);
+
+    if !password_generator.batch_items.is_empty() {
+        // Just the start of the batch; the rest is in the CSV
+        const PREVIEW_ROWS: usize = 10;
+        for item in password_generator.batch_items.iter().take(PREVIEW_ROWS) {
+            content_items.push(
+                row![
+                    text(&item.name).size(14).width(150),
+                    text(&item.password).size(14),
+                ]
+                .spacing(10)
+                .width(450)
+                .into()
+            );
+        }
+        if password_generator.batch_items.len() > PREVIEW_ROWS {
+            content_items.push(
+                text(format!("...and {} more", password_generator.batch_items.len() - PREVIEW_ROWS))
+                    .size(12)
+                    .color(muted)
+                    .into()
+            );
+        }
+        content_items.extend(vec![
+            Space::with_height(20).into(),
+            form_field("CSV file", "e.g., batch.csv", &password_generator.batch_path_input, Message::BatchPathChanged),
+            Space::with_height(5).into(),
+            text("The CSV is not encrypted; delete it once the accounts are set up")
+                .size(12)
+                .color(muted)
+                .into(),
+            Space::with_height(15).into(),
+            row![
+                button("Copy CSV")
+                    .on_press(Message::CopyBatchCsv)
+                    .padding([10, 20]),
+                button("Save CSV")
+                    .on_press(Message::SaveBatchCsv)
+                    .padding([10, 20]),
+                button("Create Entries")
+                    .on_press(Message::SaveBatchEntries)
+                    .padding([10, 20]),
+            ]
+            .spacing(10)
+            .into(),
+            Space::with_height(20).into(),
+        ]);
+    }
+
+    content_items.push(
+        button("Back to Generator")
+            .on_press(Message::NavigateTo(Pages::Current))
+            .padding([10, 20])
+            .into()
+    );
+
+    let content = column(content_items)
+        .spacing(0)
+        .width(Fill)
+        .align_x(iced::Alignment::Center);
+
+    container(scrollable(content))
+        .padding(40)
+        .width(Fill)
+        .height(Fill)
+        .into()
+}
+
+// Bitwarden page: import a JSON export or export the vault in Bitwarden's format
+fn view_bitwarden(password_generator: &PasswordGenerator) -> Element<'_, Message> {
+    let mut content_items: Vec<Element<Message>> = vec![
+        text("Bitwarden Import / Export")
+            .size(24)
+            .into(),
+        Space::with_height(10).into(),
+        text("JSON exports, plain or password protected. Leave the password empty for a plain export.")
+            .size(12)
+            .color(iced::Color::from_rgb(0.5, 0.5, 0.5))
+            .into(),
+        Space::with_height(20).into(),
+    ];
+
+    if !password_generator.status_message.is_empty() {
+        let status_color = if password_generator.status_message.contains("successfully") {
+            iced::Color::from_rgb(0.0, 0.6, 0.0)
+        } else {
+            iced::Color::from_rgb(0.8, 0.0, 0.0)
+        };
+
+        content_items.push(
+            text(&password_generator.status_message)
+                .size(14)
+                .color(status_color)
+                .into()
+        );
+        content_items.push(Space::with_height(15).into());
+    }
+
+    content_items.extend(vec![
+        form_field("Export File (.json)", "/path/to/bitwarden_export.json", &password_generator.bitwarden_path, Message::BitwardenPathChanged),
+        Space::with_height(15).into(),
+        secure_form_field("File Password", "Only for password-protected exports", &password_generator.bitwarden_password, Message::BitwardenPasswordChanged),
+        Space::with_height(15).into(),
+        secure_form_field("Confirm Password (export only)", "Repeat the password", &password_generator.bitwarden_confirm_password, Message::BitwardenConfirmPasswordChanged),
+    ]);
+
+    // A plain export is as exposed as the unencrypted exports, so it gets the same warning
+    if password_generator.bitwarden_password.is_empty() {
+        content_items.extend(vec![
+            Space::with_height(15).into(),
+            text(export::WARNING)
+                .size(12)
+                .color(iced::Color::from_rgb(0.8, 0.0, 0.0))
+                .width(300)
+                .into(),
+            Space::with_height(10).into(),
+            form_field(
+                "Confirmation (plain export only)",
+                export::CONFIRMATION_PROMPT,
+                &password_generator.bitwarden_export_confirmation,
+                Message::BitwardenExportConfirmationChanged,
+            ),
+        ]);
+    }
+
+    content_items.extend(vec![
+        Space::with_height(30).into(),
+        row![
+            button("Import")
+                .on_press(Message::ImportBitwarden)
+                .padding([10, 20]),
+            Space::with_width(15),
+            button("Export")
+                .on_press(Message::ExportBitwarden)
+                .padding([10, 20]),
         ]
-        .spacing(20)
-        .align_x(iced::Alignment::Center)
-    )
-    .padding(25)
-    .width(Fill);
+        .spacing(10)
+        .into(),
+        Space::with_height(15).into(),
+        button("Back to Passwords")
+            .on_press(Message::NavigateTo(Pages::ViewPasswords))
+            .padding([10, 20])
+            .into(),
+    ]);
+
+    let content = column(content_items)
+        .spacing(0)
+        .align_x(iced::Alignment::Center);
+
+    container(scrollable(content))
+        .padding(40)
+        .width(Fill)
+        .height(Fill)
+        .into()
+}
+
+// Detail page for one entry: edit the common fields and browse previous passwords
+// A Wi-Fi entry's network details and QR code. The code contains the passphrase,
+// so it only shows while the passphrase is shown.
+fn wifi_qr_section<'a>(
+    password_generator: &'a PasswordGenerator,
+    ssid: &'a str,
+    security: wifi::WifiSecurity,
+    passphrase: &str,
+    hidden: bool,
+) -> Element<'a, Message> {
+    let muted = iced::Color::from_rgb(0.5, 0.5, 0.5);
+    let mut details = format!("{} - {}", ssid, security);
+    if hidden {
+        details.push_str(" - hidden");
+    }
+    let code: Element<Message> = if !password_generator.show_secret && security != wifi::WifiSecurity::Open {
+        text("Show the passphrase to see the QR code")
+            .size(12)
+            .color(muted)
+            .into()
+    } else {
+        match wifi::qr_svg(&wifi::qr_payload(ssid, security, passphrase, hidden)) {
+            Ok(svg_markup) => svg::Svg::new(svg::Handle::from_memory(svg_markup.into_bytes()))
+                .width(200)
+                .height(200)
+                .into(),
+            Err(e) => text(e).size(12).color(iced::Color::from_rgb(0.8, 0.0, 0.0)).into(),
+        }
+    };
+    column![
+        text("Wi-Fi QR Code")
+            .size(14),
+        text(details)
+            .size(12)
+            .color(muted),
+        code,
+        row![
+            text_input("wifi.png or wifi.svg", &password_generator.wifi_qr_path)
+                .on_input(Message::WifiQrPathChanged)
+                .padding(10),
+            button(text("Export").size(12))
+                .on_press(Message::ExportWifiQr)
+                .padding([10, 10]),
+        ]
+        .spacing(5)
+        .align_y(iced::Alignment::Center),
+        text("Scanning the code joins the network; print it for guests")
+            .size(12)
+            .color(muted),
+    ]
+    .spacing(5)
+    .width(300)
+    .align_x(iced::Alignment::Center)
+    .into()
+}
+
+fn view_entry_details(password_generator: &PasswordGenerator, index: usize) -> Element<'_, Message> {
+    let Some(entry) = password_generator.loaded_passwords.get(index) else {
+        return container(
+            column![
+                text("Entry not found").size(16),
+                button("Back to Passwords")
+                    .on_press(Message::NavigateTo(Pages::ViewPasswords))
+                    .padding([10, 20]),
+            ]
+            .spacing(20)
+            .align_x(iced::Alignment::Center)
+        )
+        .padding(40)
+        .width(Fill)
+        .into();
+    };
+    let muted = iced::Color::from_rgb(0.5, 0.5, 0.5);
+
+    let mut content_items: Vec<Element<Message>> = vec![
+        text(&entry.name)
+            .size(24)
+            .into(),
+        text(format!("{} - created {}", entry.kind.label(), entry.created_at))
+            .size(12)
+            .color(muted)
+            .into(),
+        Space::with_height(20).into(),
+    ];
+
+    if !password_generator.status_message.is_empty() {
+        let status_color = if password_generator.status_message.contains("successfully") ||
+                             password_generator.status_message.contains("copied") {
+            iced::Color::from_rgb(0.0, 0.6, 0.0)
+        } else {
+            iced::Color::from_rgb(0.8, 0.0, 0.0)
+        };
+
+        content_items.push(
+            text(&password_generator.status_message)
+                .size(14)
+                .color(status_color)
+                .into()
+        );
+        content_items.push(Space::with_height(15).into());
+    }
+
+    content_items.push(form_field("Entry Name *", "e.g., Gmail Account", &password_generator.edit_name, Message::EditNameChanged));
+    content_items.push(Space::with_height(15).into());
+
+    // Logins, API keys and Wi-Fi networks keep their secret in `value`
+    let has_value = match &entry.kind {
+        st_json::EntryKind::Login | st_json::EntryKind::ApiKey { .. } => true,
+        st_json::EntryKind::Wifi { security, .. } => *security != wifi::WifiSecurity::Open,
+        _ => false,
+    };
+    if has_value {
+        let label = match entry.kind {
+            st_json::EntryKind::Login => "Password",
+            st_json::EntryKind::Wifi { .. } => "Passphrase",
+            _ => "Key/Token",
+        };
+        // Derived passwords aren't stored, so they're worked out again from the master password
+        let mut value_input = text_input(
+            if entry.derivation.is_some() { "Derive to see the password" } else { "Your password" },
+            &password_generator.edit_value,
+        )
+        .padding(10)
+        .secure(!password_generator.show_secret);
+        if let Some(profile) = &entry.derivation {
+            content_items.push(
+                column![
+                    text("Derived Password")
+                        .size(14),
+                    text(profile.describe())
+                        .size(12)
+                        .color(muted),
+                    row![
+                        text_input("Master password", &password_generator.edit_master)
+                            .on_input(Message::EditMasterChanged)
+                            .on_submit(Message::DeriveEntryPassword)
+                            .padding(10)
+                            .secure(true),
+                        button(text("Derive").size(12))
+                            .on_press(Message::DeriveEntryPassword)
+                            .padding([10, 10]),
+                    ]
+                    .spacing(5)
+                    .align_y(iced::Alignment::Center),
+                ]
+                .spacing(5)
+                .width(300)
+                .into()
+            );
+            content_items.push(Space::with_height(10).into());
+        } else {
+            value_input = value_input.on_input(Message::EditValueChanged);
+        }
+        content_items.push(
+            column![
+                text(label)
+                    .size(14),
+                row![
+                    value_input,
+                    button(text(if password_generator.show_secret { "Hide" } else { "Show" }).size(12))
+                        .on_press(Message::ToggleShowSecret)
+                        .padding([10, 10]),
+                    button(text("Copy").size(12))
+                        .on_press(Message::CopyEntryValue)
+                        .padding([10, 10]),
+                ]
+                .spacing(5)
+                .align_y(iced::Alignment::Center),
+            ]
+            .spacing(5)
+            .width(300)
+            .into()
+        );
+        content_items.push(Space::with_height(15).into());
+        if entry.derivation.is_some() {
+            content_items.push(form_field("Counter", "1", &password_generator.edit_counter, Message::EditCounterChanged));
+            content_items.push(Space::with_height(15).into());
+        }
+        if !matches!(entry.kind, st_json::EntryKind::Wifi { .. }) {
+            content_items.push(form_field("Website/App", "e.g., gmail.com", &password_generator.edit_website, Message::EditWebsiteChanged));
+            content_items.push(Space::with_height(15).into());
+        }
+    }
+    if let st_json::EntryKind::Wifi { ssid, security, hidden } = &entry.kind {
+        content_items.push(wifi_qr_section(password_generator, ssid, *security, &entry.value, *hidden));
+        content_items.push(Space::with_height(15).into());
+    }
+    if entry.kind == st_json::EntryKind::Login {
+        content_items.push(form_field("Username/Email", "e.g., john@example.com", &password_generator.edit_username, Message::EditUsernameChanged));
+        content_items.push(Space::with_height(15).into());
+    }
+
+    if entry.kind == st_json::EntryKind::SecureNote {
+        content_items.push(
+            container(text(&entry.notes).size(14))
+                .padding(10)
+                .width(300)
+                .into()
+        );
+    } else {
+        content_items.push(form_field("Notes", "Additional notes (optional)", &password_generator.edit_notes, Message::EditNotesChanged));
+    }
+    content_items.push(Space::with_height(15).into());
+    content_items.push(form_field("Folder", "e.g., Work/Servers", &password_generator.edit_folder, Message::EditFolderChanged));
+    content_items.push(Space::with_height(15).into());
+    content_items.push(form_field("Rotate every (days, optional)", "e.g., 90", &password_generator.edit_rotation_days, Message::EditRotationChanged));
+    let rotation = audit::rotation_status(entry, chrono::Utc::now().naive_utc());
+    if rotation.needs_attention() {
+        content_items.push(Space::with_height(5).into());
+        content_items.push(text(rotation.to_string()).size(12).color(rotation_color(rotation)).into());
+    }
+    content_items.push(Space::with_height(20).into());
+
+    // Extra fields, e.g. from KeePass; protected ones are masked like the password
+    if !entry.custom_fields.is_empty() {
+        content_items.push(text("Custom Fields").size(16).into());
+        content_items.push(Space::with_height(5).into());
+        for field in &entry.custom_fields {
+            let value = if field.protected && !password_generator.show_secret {
+                "*".repeat(field.value.chars().count().min(24))
+            } else {
+                field.value.clone()
+            };
+            content_items.push(
+                row![
+                    text(&field.name)
+                        .size(14)
+                        .color(muted)
+                        .width(120),
+                    text(value)
+                        .size(14),
+                ]
+                .spacing(10)
+                .width(400)
+                .into()
+            );
+        }
+        content_items.push(Space::with_height(20).into());
+    }
+
+    // Previous passwords, newest first. Saving a changed password needs the
+    // history unlocked too, so the old one can be sealed.
+    let changing_password = entry.derivation.is_none()
+        && !entry.value.is_empty()
+        && password_generator.edit_value != entry.value
+        && password_generator.settings.max_password_history > 0;
+    if !entry.history.is_empty() || changing_password {
+        content_items.push(text("Password History").size(16).into());
+        content_items.push(Space::with_height(5).into());
+        if password_generator.history_key.is_none() {
+            content_items.push(history_unlock(password_generator));
+            content_items.push(Space::with_height(10).into());
+        }
+        for (history_index, old) in entry.history.iter().enumerate() {
+            let shown = match history_key::reveal(password_generator.history_key.as_ref(), old) {
+                Ok(value) if password_generator.show_secret => value,
+                Ok(value) => "*".repeat(value.len().min(24)),
+                Err(_) => "(locked)".to_string(),
+            };
+            content_items.push(
+                row![
+                    text(shown)
+                    .size(14),
+                    Space::with_width(Fill),
+                    text(&old.changed_at)
+                        .size(12)
+                        .color(muted),
+                    button(text("Copy").size(12))
+                        .on_press(Message::CopyHistoryValue(history_index))
+                        .padding([2, 8]),
+                ]
+                .spacing(10)
+                .width(400)
+                .align_y(iced::Alignment::Center)
+                .into()
+            );
+        }
+        content_items.push(Space::with_height(20).into());
+    }
+
+    content_items.push(
+        row![
+            button("Back")
+                .on_press(Message::NavigateTo(Pages::ViewPasswords))
+                .padding([10, 20]),
+            Space::with_width(15),
+            button("Save Changes")
+                .on_press(Message::SaveEntryChanges)
+                .padding([10, 20]),
+            Space::with_width(15),
+            button("Move to Trash")
+                .on_press(Message::MoveToTrash)
+                .padding([10, 20])
+                .style(button::danger),
+        ]
+        .spacing(10)
+        .into()
+    );
+
+    let content = column(content_items)
+        .spacing(0)
+        .width(Fill)
+        .align_x(iced::Alignment::Center);
+
+    container(scrollable(content))
+        .padding(40)
+        .width(Fill)
+        .height(Fill)
+        .into()
+}
+
+// Trash page: restore or permanently delete removed entries
+fn view_generator_history(password_generator: &PasswordGenerator) -> Element<'_, Message> {
+    let muted = iced::Color::from_rgb(0.5, 0.5, 0.5);
+    let mut content_items: Vec<Element<Message>> = vec![
+        text("Generator History")
+            .size(24)
+            .into(),
+        Space::with_height(20).into(),
+    ];
+
+    if !password_generator.status_message.is_empty() {
+        let status_color = if password_generator.status_message.contains("successfully") {
+            iced::Color::from_rgb(0.0, 0.6, 0.0)
+        } else {
+            iced::Color::from_rgb(0.8, 0.0, 0.0)
+        };
+
+        content_items.push(
+            text(&password_generator.status_message)
+                .size(14)
+                .color(status_color)
+                .into()
+        );
+        content_items.push(Space::with_height(15).into());
+    }
+
+    let entries = password_generator.generator_history.entries();
+    if entries.is_empty() {
+        let empty_text = if password_generator.settings.generator_history_size == 0 {
+            "The generator history is turned off in Settings"
+        } else {
+            "No passwords generated yet"
+        };
+        content_items.push(
+            text(empty_text)
+                .size(16)
+                .color(muted)
+                .into()
+        );
+    } else {
+        let kept_for = match password_generator.settings.generator_history_minutes {
+            0 => "Passwords are kept until Saltr closes".to_string(),
+            minutes => format!("Passwords are forgotten {} minutes after being generated", minutes),
+        };
+        content_items.push(text(kept_for).size(12).color(muted).into());
+        content_items.push(Space::with_height(10).into());
+        content_items.push(
+            checkbox("Show passwords", password_generator.show_generator_history)
+                .on_toggle(|_| Message::ToggleShowGeneratorHistory)
+                .into()
+        );
+        content_items.push(Space::with_height(10).into());
+
+        let mut history_list = column![].spacing(10);
+        for (index, entry) in entries.iter().enumerate() {
+            let shown = if password_generator.show_generator_history {
+                password_generator.generator_history.reveal(index).unwrap_or_else(|e| e)
+            } else {
+                "••••••••••••".to_string()
+            };
+            let mut details = format!("{} - {} UTC", entry.source, entry.created_at.format("%H:%M:%S"));
+            if !entry.website.is_empty() {
+                details = format!("{} - {}", entry.website, details);
+            }
+            history_list = history_list.push(
+                container(
+                    row![
+                        column![
+                            text(shown)
+                                .size(16),
+                            text(details)
+                                .size(12)
+                                .color(muted),
+                        ]
+                        .spacing(3),
+                        Space::with_width(Fill),
+                        button(text("Copy").size(12))
+                            .on_press(Message::CopyFromGeneratorHistory(index))
+                            .padding([5, 10]),
+                        button(text("Save as Entry").size(12))
+                            .on_press(Message::SaveFromGeneratorHistory(index))
+                            .padding([5, 10]),
+                        button(text("Remove").size(12))
+                            .on_press(Message::RemoveFromGeneratorHistory(index))
+                            .padding([5, 10])
+                            .style(button::danger),
+                    ]
+                    .spacing(10)
+                    .align_y(iced::Alignment::Center)
+                )
+                .padding(10)
+                .width(Fill)
+            );
+        }
+        content_items.push(
+            scrollable(history_list)
+                .height(300)
+                .width(Fill)
+                .into()
+        );
+    }
+
+    let mut nav = row![
+        button("Back to Generator")
+            .on_press(Message::NavigateTo(Pages::Current))
+            .padding([10, 20]),
+    ]
+    .spacing(15);
+    if !entries.is_empty() {
+        nav = nav.push(
+            button("Clear History")
+                .on_press(Message::ClearGeneratorHistory)
+                .padding([10, 20])
+                .style(button::danger)
+        );
+    }
+    content_items.push(Space::with_height(30).into());
+    content_items.push(nav.into());
+
+    let content = column(content_items)
+        .spacing(0)
+        .width(Fill)
+        .align_x(iced::Alignment::Center);
+
+    container(content)
+        .padding(40)
+        .width(Fill)
+        .height(Fill)
+        .into()
+}
+
+fn view_trash(password_generator: &PasswordGenerator) -> Element<'_, Message> {
+    let muted = iced::Color::from_rgb(0.5, 0.5, 0.5);
+    let mut content_items: Vec<Element<Message>> = vec![
+        text("Trash")
+            .size(24)
+            .into(),
+        Space::with_height(20).into(),
+    ];
+
+    if !password_generator.status_message.is_empty() {
+        let status_color = if password_generator.status_message.starts_with("Error") {
+            iced::Color::from_rgb(0.8, 0.0, 0.0)
+        } else {
+            iced::Color::from_rgb(0.0, 0.6, 0.0)
+        };
+
+        content_items.push(
+            text(&password_generator.status_message)
+                .size(14)
+                .color(status_color)
+                .into()
+        );
+        content_items.push(Space::with_height(15).into());
+    }
+
+    if password_generator.trash.is_empty() {
+        content_items.push(
+            text("The trash is empty")
+                .size(16)
+                .color(muted)
+                .into()
+        );
+    } else {
+        let retention = match password_generator.settings.trash_retention_days {
+            0 => "Items stay here until you delete them".to_string(),
+            days => format!("Items are permanently deleted {} days after being trashed", days),
+        };
+        content_items.push(text(retention).size(12).color(muted).into());
+        content_items.push(Space::with_height(10).into());
+
+        let mut trash_list = column![].spacing(10);
+        for (index, trashed) in password_generator.trash.iter().enumerate() {
+            trash_list = trash_list.push(
+                container(
+                    row![
+                        column![
+                            text(&trashed.entry.name)
+                                .size(16),
+                            text(format!("{} - deleted {}", trashed.entry.kind.label(), trashed.deleted_at))
+                                .size(12)
+                                .color(muted),
+                        ]
+                        .spacing(3),
+                        Space::with_width(Fill),
+                        button(text("Restore").size(12))
+                            .on_press(Message::RestoreFromTrash(index))
+                            .padding([5, 10]),
+                        button(text("Delete Forever").size(12))
+                            .on_press(Message::PurgeFromTrash(index))
+                            .padding([5, 10])
+                            .style(button::danger),
+                    ]
+                    .spacing(10)
+                    .align_y(iced::Alignment::Center)
+                )
+                .padding(10)
+                .width(Fill)
+            );
+        }
+        content_items.push(
+            scrollable(trash_list)
+                .height(300)
+                .width(Fill)
+                .into()
+        );
+    }
 
-    let navigation = row![
-        button("View Passwords").on_press(Message::NavigateTo(Pages::ViewPasswords)),
-        Space::with_width(10),
-        button("Settings").on_press(Message::NavigateTo(Pages::Settings)),
+    let mut nav = row![
+        button("Back to Passwords")
+            .on_press(Message::NavigateTo(Pages::ViewPasswords))
+            .padding([10, 20]),
     ]
-    .spacing(10);
+    .spacing(15);
+    if !password_generator.trash.is_empty() {
+        nav = nav.push(
+            button("Empty Trash")
+                .on_press(Message::EmptyTrash)
+                .padding([10, 20])
+                .style(button::danger)
+        );
+    }
+    content_items.push(Space::with_height(30).into());
+    content_items.push(nav.into());
 
-    let main_content = column![
-        header,
-        Space::with_height(20),
-        password_section,
-        Space::with_height(20),
-        navigation,
-        Space::with_height(40),
-    ]
-    .spacing(0)
-    .align_x(iced::Alignment::Center);
+    let content = column(content_items)
+        .spacing(0)
+        .width(Fill)
+        .align_x(iced::Alignment::Center);
 
-    container(main_content)
-        .padding(20)
+    container(content)
+        .padding(40)
         .width(Fill)
         .height(Fill)
         .into()
 }
 
-// Add Details page view (unchanged from your original)
-fn view_add_details(password_generator: &PasswordGenerator) -> Element<Message> {
-    let mut content_items = vec![
-        text("Save Password Details")
+// A clickable entry name that opens the entry so the finding can be fixed
+fn entry_link(password_generator: &PasswordGenerator, index: usize) -> Element<'_, Message> {
+    let name = password_generator.loaded_passwords.get(index).map(|entry| entry.name.as_str()).unwrap_or("?");
+    button(text(name).size(14))
+        .on_press(Message::OpenEntry(index))
+        .style(button::secondary)
+        .padding([4, 10])
+        .into()
+}
+
+// Heading for one group of findings on the Security page
+fn audit_section(title: &str, count: usize, explanation: &str) -> Element<'static, Message> {
+    column![
+        Space::with_height(20),
+        text(format!("{} ({})", title, count)).size(18),
+        text(explanation.to_string())
+            .size(12)
+            .color(iced::Color::from_rgb(0.5, 0.5, 0.5)),
+        Space::with_height(8),
+    ]
+    .into()
+}
+
+// Security page: reused, weak and old passwords, and incomplete logins
+fn view_security(password_generator: &PasswordGenerator) -> Element<'_, Message> {
+    let report = &password_generator.audit_report;
+    let mut content_items: Vec<Element<Message>> = vec![
+        text("Security Audit")
             .size(24)
             .into(),
-        Space::with_height(30).into(),
+        Space::with_height(10).into(),
+        text(format!("Checked {} entries. Click an entry to fix it.", password_generator.loaded_passwords.len()))
+            .size(12)
+            .color(iced::Color::from_rgb(0.5, 0.5, 0.5))
+            .into(),
     ];
 
     if !password_generator.status_message.is_empty() {
-        let status_color = if password_generator.status_message.contains("successfully") {
-            iced::Color::from_rgb(0.0, 0.6, 0.0)
-        } else {
-            iced::Color::from_rgb(0.8, 0.0, 0.0)
-        };
-        
+        content_items.push(Space::with_height(15).into());
         content_items.push(
             text(&password_generator.status_message)
                 .size(14)
-                .color(status_color)
+                .color(iced::Color::from_rgb(0.8, 0.0, 0.0))
                 .into()
         );
-        content_items.push(Space::with_height(15).into());
+    }
+
+    if report.is_clean() {
+        content_items.push(Space::with_height(30).into());
+        content_items.push(
+            text("No problems found")
+                .size(16)
+                .color(iced::Color::from_rgb(0.0, 0.6, 0.0))
+                .into()
+        );
+    }
+
+    if !report.breached.is_empty() {
+        content_items.push(audit_section("Breached passwords", report.breached.len(), "Found in the Pwned Passwords list. Change these first."));
+        for (index, count) in &report.breached {
+            content_items.push(
+                row![
+                    entry_link(password_generator, *index),
+                    text(format!("seen {} times", count)).size(12).color(iced::Color::from_rgb(0.8, 0.0, 0.0)),
+                ]
+                .spacing(10)
+                .align_y(iced::Alignment::Center)
+                .into()
+            );
+            content_items.push(Space::with_height(4).into());
+        }
+    }
+
+    if !report.reused.is_empty() {
+        content_items.push(audit_section("Reused passwords", report.reused.len(), "These entries share exactly the same password."));
+        for group in &report.reused {
+            let links = group.iter().map(|&index| entry_link(password_generator, index));
+            content_items.push(row(links).spacing(8).wrap().into());
+            content_items.push(Space::with_height(8).into());
+        }
+    }
+
+    if !report.similar.is_empty() {
+        content_items.push(audit_section("Similar passwords", report.similar.len(), "Families of passwords that are small variations of each other, like Summer2023! and Summer2024!."));
+        for group in &report.similar {
+            let links = group.iter().map(|&index| entry_link(password_generator, index));
+            content_items.push(row(links).spacing(8).wrap().into());
+            content_items.push(Space::with_height(8).into());
+        }
+    }
+
+    if !report.weak.is_empty() {
+        content_items.push(audit_section("Weak passwords", report.weak.len(), "Short, simple or built on a common word."));
+        for (index, strength) in &report.weak {
+            content_items.push(
+                row![
+                    entry_link(password_generator, *index),
+                    text(strength.to_string()).size(12).color(iced::Color::from_rgb(0.8, 0.0, 0.0)),
+                ]
+                .spacing(10)
+                .align_y(iced::Alignment::Center)
+                .into()
+            );
+            content_items.push(Space::with_height(4).into());
+        }
+    }
+
+    if !report.old.is_empty() {
+        let explanation = format!("Not changed in over {} months.", password_generator.settings.audit_max_age_months);
+        content_items.push(audit_section("Old passwords", report.old.len(), &explanation));
+        for (index, months) in &report.old {
+            content_items.push(
+                row![
+                    entry_link(password_generator, *index),
+                    text(format!("{} months old", months)).size(12),
+                ]
+                .spacing(10)
+                .align_y(iced::Alignment::Center)
+                .into()
+            );
+            content_items.push(Space::with_height(4).into());
+        }
+    }
+
+    if !report.overdue.is_empty() {
+        content_items.push(audit_section("Overdue for rotation", report.overdue.len(), "Past the rotation interval set on the entry."));
+        for (index, days) in &report.overdue {
+            content_items.push(
+                row![
+                    entry_link(password_generator, *index),
+                    text(audit::RotationStatus::Overdue(*days).to_string()).size(12).color(iced::Color::from_rgb(0.8, 0.0, 0.0)),
+                ]
+                .spacing(10)
+                .align_y(iced::Alignment::Center)
+                .into()
+            );
+            content_items.push(Space::with_height(4).into());
+        }
+    }
+
+    if !report.missing_username.is_empty() {
+        content_items.push(audit_section("Missing username", report.missing_username.len(), "Logins without a username."));
+        let links = report.missing_username.iter().map(|&index| entry_link(password_generator, index));
+        content_items.push(row(links).spacing(8).wrap().into());
+    }
+
+    if !report.missing_website.is_empty() {
+        content_items.push(audit_section("Missing website", report.missing_website.len(), "Logins without a website."));
+        let links = report.missing_website.iter().map(|&index| entry_link(password_generator, index));
+        content_items.push(row(links).spacing(8).wrap().into());
     }
 
     content_items.extend(vec![
-        column![
-            text("Password Name *")
-                .size(14),
-            text_input("e.g., Gmail Account", &password_generator.password_name)
-                .on_input(Message::PasswordNameChanged)
-                .padding(10)
-                .width(300),
-        ]
-        .spacing(5)
-        .into(),
-        
-        Space::with_height(15).into(),
-        
-        column![
-            text("Password *")
-                .size(14),
-            text_input("Your password", &password_generator.saved_password)
-                .on_input(Message::PasswordChanged)
-                .padding(10)
-                .width(300)
-                .secure(true),
-        ]
-        .spacing(5)
-        .into(),
-        
-        Space::with_height(15).into(),
-        
-        column![
-            text("Website/App")
-                .size(14),
-            text_input("e.g., gmail.com", &password_generator.website)
-                .on_input(Message::WebsiteChanged)
-                .padding(10)
-                .width(300),
-        ]
-        .spacing(5)
-        .into(),
-        
-        Space::with_height(15).into(),
-        
-        column![
-            text("Username/Email")
-                .size(14),
-            text_input("e.g., john@example.com", &password_generator.username)
-                .on_input(Message::UsernameChanged)
-                .padding(10)
-                .width(300),
-        ]
-        .spacing(5)
-        .into(),
-        
-        Space::with_height(15).into(),
-        
-        column![
-            text("Notes")
-                .size(14),
-            text_input("Additional notes (optional)", &password_generator.notes)
-                .on_input(Message::NotesChanged)
-                .padding(10)
-                .width(300),
-        ]
-        .spacing(5)
-        .into(),
-        
         Space::with_height(30).into(),
-        
         row![
-            button("Cancel")
+            button("Back to Generator")
                 .on_press(Message::NavigateTo(Pages::Current))
                 .padding([10, 20]),
             Space::with_width(15),
-            button("Save Password")
-                .on_press(Message::SavePasswordDetails)
+            button("Scan Again")
+                .on_press(Message::NavigateTo(Pages::Security))
                 .padding([10, 20]),
         ]
         .spacing(10)
         .into(),
-        
-        Space::with_height(20).into(),
-        text("* Required fields")
-            .size(12)
-            .into(),
     ]);
 
     let content = column(content_items)
         .spacing(0)
-        .align_x(iced::Alignment::Center);
+        .width(Fill)
+        .padding([0, 20]);
 
-    container(content)
+    container(scrollable(content))
         .padding(40)
         .width(Fill)
         .height(Fill)
         .into()
 }
 
-// Enhanced View Passwords page that displays the loaded passwords
-fn view_passwords(password_generator: &PasswordGenerator) -> Element<Message> {
-    let mut content_items = vec![
-        text("Saved Passwords")
+// Settings page
+// The presets list, the form to add one from the generator's current settings, and export/import
+fn preset_settings(password_generator: &PasswordGenerator) -> Element<'_, Message> {
+    let muted = iced::Color::from_rgb(0.5, 0.5, 0.5);
+    let mut preset_list = column![].spacing(10);
+    for (index, preset) in password_generator.settings.presets.iter().enumerate() {
+        preset_list = preset_list.push(
+            row![
+                column![
+                    text(&preset.name).size(16),
+                    text(preset.policy.describe()).size(12).color(muted),
+                ]
+                .spacing(3),
+                Space::with_width(Fill),
+                button(text("Remove").size(12))
+                    .on_press(Message::RemovePreset(index))
+                    .padding([5, 10])
+                    .style(button::danger),
+            ]
+            .align_y(iced::Alignment::Center)
+        );
+    }
+    let current = match current_preset_policy(password_generator) {
+        Ok(policy) => format!("Saves the generator's current settings: {}", policy.describe()),
+        Err(e) => e,
+    };
+    column![
+        text("Generator Presets").size(18),
+        preset_list,
+        row![
+            text_input("Preset name", &password_generator.preset_name_input)
+                .on_input(Message::PresetNameChanged)
+                .padding(10),
+            button("Save as Preset")
+                .on_press(Message::SavePreset)
+                .padding([10, 20]),
+        ]
+        .spacing(10),
+        text(current).size(12).color(muted),
+        row![
+            text_input("presets.json", &password_generator.presets_path_input)
+                .on_input(Message::PresetsPathChanged)
+                .padding(10),
+            button("Export")
+                .on_press(Message::ExportPresets)
+                .padding([10, 20]),
+            button("Import")
+                .on_press(Message::ImportPresets)
+                .padding([10, 20]),
+        ]
+        .spacing(10),
+        text("Share presets with a team by exporting them to a file and importing it on their machines")
+            .size(12)
+            .color(muted),
+    ]
+    .spacing(10)
+    .width(Fill)
+    .into()
+}
+
+fn view_settings(password_generator: &PasswordGenerator) -> Element<'_, Message> {
+    let mut content_items: Vec<Element<Message>> = vec![
+        text("Settings")
             .size(24)
             .into(),
         Space::with_height(20).into(),
     ];
 
-    // Show status message if any
     if !password_generator.status_message.is_empty() {
-        let status_color = if password_generator.status_message.contains("loaded") || 
-                             password_generator.status_message.contains("Refreshed") {
+        let status_color = if password_generator.status_message.contains("successfully") {
             iced::Color::from_rgb(0.0, 0.6, 0.0)
         } else {
             iced::Color::from_rgb(0.8, 0.0, 0.0)
         };
-        
+
         content_items.push(
             text(&password_generator.status_message)
                 .size(14)
@@ -425,142 +4978,68 @@ fn view_passwords(password_generator: &PasswordGenerator) -> Element<Message> {
         content_items.push(Space::with_height(15).into());
     }
 
-    // Display passwords if any are loaded
-    if password_generator.loaded_passwords.is_empty() {
-        content_items.extend(vec![
-            text("No passwords found")
-                .size(16)
-                .into(),
-            Space::with_height(10).into(),
-            text("Save some passwords first!")
-                .size(14)
-                .into(),
-        ]);
-    } else {
-        // Create a scrollable list of passwords
-        let password_list: Vec<Element<Message>> = password_generator.loaded_passwords
-            .iter()
-            .map(|password| {
-                container(
-                    column![
-                        // Password name (title)
-                        text(&password.name)
-                            .size(18)
-                            .color(iced::Color::from_rgb(0.2, 0.6, 1.0)),
-                        
-                        Space::with_height(5),
-                        
-                        // Website (if provided)
-                        if !password.website.is_empty() {
-                            text(format!("Website: {}", password.website))
-                                .size(14)
-                        } else {
-                            text("")
-                                .size(14)
-                        },
-                        
-                        // Username (if provided)
-                        if !password.username.is_empty() {
-                            text(format!("Username: {}", password.username))
-                                .size(14)
-                        } else {
-                            text("")
-                                .size(14)
-                        },
-                        
-                        // Notes (if provided)
-                        if !password.notes.is_empty() {
-                            text(format!("Notes: {}", password.notes))
-                                .size(14)
-                        } else {
-                            text("")
-                                .size(14)
-                        },
-                        
-                        // Password (hidden for security)
-                        text(format!("Password: {}", "*".repeat(password.value.len())))
-                            .size(14)
-                            .color(iced::Color::from_rgb(0.6, 0.6, 0.6)),
-                        
-                        // Created date
-                        text(format!("Created: {}", password.created_at))
-                            .size(12)
-                            .color(iced::Color::from_rgb(0.5, 0.5, 0.5)),
-                    ]
-                    .spacing(3)
-                )
-                .padding(15)
-                .width(Fill)
-                .into()
-            })
-            .collect();
-
-        content_items.push(
-            scrollable(
-                column(password_list)
-                    .spacing(10)
-            ).height(300)
-             .width(Fill)
-             .into()
-        );
-    }
-
-    let reload_svg = svg::Handle::from_path("assets/reload.svg"); 
-    let back_svg = svg::Handle::from_path("assets/back.svg");
-
-    // Navigation buttons
     content_items.extend(vec![
-        Space::with_height(40).into(),
+        form_field("Password history length", "10", &password_generator.max_history_input, Message::MaxHistoryChanged),
+        Space::with_height(10).into(),
+        if password_generator.history_key.is_none() {
+            history_unlock(password_generator)
+        } else {
+            text("Password history unlocked until Saltr closes")
+                .size(12)
+                .color(iced::Color::from_rgb(0.5, 0.5, 0.5))
+                .into()
+        },
+        Space::with_height(15).into(),
+        form_field("Purge trash after (days, 0 = never)", "30", &password_generator.trash_retention_input, Message::TrashRetentionChanged),
+        Space::with_height(15).into(),
+        form_field("Flag passwords older than (months, 0 = never)", "12", &password_generator.audit_max_age_input, Message::AuditMaxAgeChanged),
+        Space::with_height(15).into(),
+        form_field("Pwned Passwords file or folder (optional)", "/path/to/pwnedpasswords.txt", &password_generator.hibp_path_input, Message::HibpPathChanged),
+        Space::with_height(5).into(),
+        text("SHA-1 or NTLM list from haveibeenpwned.com, checked offline")
+            .size(12)
+            .color(iced::Color::from_rgb(0.5, 0.5, 0.5))
+            .into(),
+        Space::with_height(15).into(),
+        form_field("Email for plus aliases (optional)", "me@example.com", &password_generator.alias_email_input, Message::AliasEmailChanged),
+        Space::with_height(15).into(),
+        form_field("Catch-all domain (optional)", "example.com", &password_generator.catch_all_domain_input, Message::CatchAllDomainChanged),
+        Space::with_height(5).into(),
+        text("Adds alias buttons under the username on the add form")
+            .size(12)
+            .color(iced::Color::from_rgb(0.5, 0.5, 0.5))
+            .into(),
+        Space::with_height(15).into(),
+        form_field("Generator history size (0 = off)", "20", &password_generator.generator_history_size_input, Message::GeneratorHistorySizeChanged),
+        Space::with_height(15).into(),
+        form_field("Forget generated passwords after (minutes, 0 = on close)", "60", &password_generator.generator_history_minutes_input, Message::GeneratorHistoryMinutesChanged),
+        Space::with_height(5).into(),
+        text("The history is kept in memory only and is gone when Saltr closes")
+            .size(12)
+            .color(iced::Color::from_rgb(0.5, 0.5, 0.5))
+            .into(),
+        Space::with_height(30).into(),
+        preset_settings(password_generator),
+        Space::with_height(30).into(),
         row![
-            button(
-                container(
-                    svg::Svg::new(back_svg).width(20).height(20)
-                )
-            )
+            button("Back to Generator")
                 .on_press(Message::NavigateTo(Pages::Current))
                 .padding([10, 20]),
             Space::with_width(15),
-            button(
-                container(
-                    svg::Svg::new(reload_svg).width(20).height(20)
-                )
-            )
-                .on_press(Message::LoadPasswordsFromFile)
+            button("Save Settings")
+                .on_press(Message::SaveSettings)
                 .padding([10, 20]),
         ]
         .spacing(10)
         .into(),
-        
     ]);
 
     let content = column(content_items)
         .spacing(0)
-        .align_x(iced::Alignment::Center);
-
-    container(content)
-        .padding(40)
         .width(Fill)
-        .height(Fill)
-        .into()
-}
-
-// Settings page (unchanged)
-fn view_settings(_password_generator: &PasswordGenerator) -> Element<Message> { 
-    let content = column![
-        text("Settings")
-            .size(24),
-        Space::with_height(20),
-        text("Configure your password generator")
-            .size(16),
-        Space::with_height(30),
-        button("Back to Generator")
-            .on_press(Message::NavigateTo(Pages::Current))
-            .padding([10, 20]),
-    ]
-    .spacing(10)
-    .align_x(iced::Alignment::Center);
+        .align_x(iced::Alignment::Center);
 
-    container(content)
+    container(scrollable(content))
         .padding(40)
         .width(Fill)
         .height(Fill)
@@ -568,11 +5047,22 @@ fn view_settings(_password_generator: &PasswordGenerator) -> Element<Message> {
 }
 
 // Main view function - acts as a router
-fn view(password_generator: &PasswordGenerator) -> Element<Message> {
+fn view(password_generator: &PasswordGenerator) -> Element<'_, Message> {
     match password_generator.current_page {
         Pages::Current => view_current(password_generator),
         Pages::AddDetails => view_add_details(password_generator),
         Pages::ViewPasswords => view_passwords(password_generator),
+        Pages::EntryDetails(index) => view_entry_details(password_generator, index),
+        Pages::ImportOtp => view_import_otp(password_generator),
+        Pages::ImportCsv => view_import_csv(password_generator),
+        Pages::KeePass => view_keepass(password_generator),
+        Pages::Bitwarden => view_bitwarden(password_generator),
+        Pages::Backup => view_backup(password_generator),
+        Pages::Export => view_export(password_generator),
+        Pages::Batch => view_batch(password_generator),
+        Pages::Security => view_security(password_generator),
+        Pages::GeneratorHistory => view_generator_history(password_generator),
+        Pages::Trash => view_trash(password_generator),
         Pages::Settings => view_settings(password_generator),
     }
 }
\ No newline at end of file
@@ -0,0 +1,407 @@
+// password_rules.rs - Per-site password requirements, in the format of Apple's
+// open-source password-rules.json ("minlength: 8; maxlength: 16; required: lower;
+// required: digit; allowed: [-!@#];"). A small set of sites is built in; a
+// password-rules.json next to passwords.json (for example Apple's full list)
+// takes its place.
+use rand::seq::SliceRandom;
+use rand::Rng;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+pub const RULES_FILE: &str = "password-rules.json";
+const BUILT_IN_RULES: &str = include_str!("../assets/password-rules.json");
+
+// Used when a site limits the length but not the characters
+const DEFAULT_CHARACTERS: &str = "abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789";
+const SPECIAL_CHARACTERS: &str = "-~!@#$%^&*_+=`|(){}[:;\"'<>,.?]/\\";
+// How many passwords `generate` tries before deciding the rules can't be met
+const MAX_ATTEMPTS: usize = 1_000;
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PasswordRules {
+    pub min_length: Option<usize>,
+    pub max_length: Option<usize>,
+    // Most identical characters allowed in a row
+    pub max_consecutive: Option<usize>,
+    // Each set needs at least one character from it
+    pub required: Vec<Vec<char>>,
+    // Characters that may be used; the required sets are always allowed too
+    pub allowed: Vec<char>,
+    // Set by "allowed: unicode": anything goes, `allowed` only guides the generator
+    pub any_character: bool,
+}
+
+#[derive(Deserialize)]
+struct RuleEntry {
+    #[serde(rename = "password-rules")]
+    password_rules: String,
+}
+
+// Characters for a named class, or None for "unicode", which can't be listed
+fn class_characters(name: &str) -> Result<Option<Vec<char>>, String> {
+    Ok(Some(match name {
+        "lower" => ('a'..='z').collect(),
+        "upper" => ('A'..='Z').collect(),
+        "digit" => ('0'..='9').collect(),
+        "special" => SPECIAL_CHARACTERS.chars().collect(),
+        "ascii-printable" => ('!'..='~').collect(),
+        "unicode" => return Ok(None),
+        other => return Err(format!("Unknown character class '{}'", other)),
+    }))
+}
+
+// Splits on `separator`, except inside [...] custom character sets, which may
+// contain ';' and ','. A ']' right after the opening '[' (or "[-") is a
+// literal, as in Apple's format.
+fn split_outside_brackets(input: &str, separator: char) -> Vec<String> {
+    let mut parts = Vec::new();
+    let mut current = String::new();
+    let mut in_set = false;
+    let mut set_len = 0;
+    for c in input.chars() {
+        if in_set {
+            let literal_bracket = c == ']' && (set_len == 0 || (set_len == 1 && current.ends_with('-')));
+            if c == ']' && !literal_bracket {
+                in_set = false;
+            }
+            set_len += 1;
+            current.push(c);
+        } else if c == '[' {
+            in_set = true;
+            set_len = 0;
+            current.push(c);
+        } else if c == separator {
+            parts.push(std::mem::take(&mut current));
+        } else {
+            current.push(c);
+        }
+    }
+    parts.push(current);
+    parts.into_iter().map(|part| part.trim().to_string()).filter(|part| !part.is_empty()).collect()
+}
+
+// "lower, [-_]" -> the characters of every class listed, and whether
+// "unicode" was one of them
+fn parse_classes(value: &str) -> Result<(Vec<char>, bool), String> {
+    let mut characters = Vec::new();
+    let mut unicode = false;
+    for class in split_outside_brackets(value, ',') {
+        if let Some(set) = class.strip_prefix('[').and_then(|rest| rest.strip_suffix(']')) {
+            characters.extend(set.chars());
+        } else {
+            match class_characters(&class.to_ascii_lowercase())? {
+                Some(class_chars) => characters.extend(class_chars),
+                None => unicode = true,
+            }
+        }
+    }
+    characters.sort();
+    characters.dedup();
+    Ok((characters, unicode))
+}
+
+pub fn parse_rules(rules: &str) -> Result<PasswordRules, String> {
+    let mut parsed = PasswordRules::default();
+    for property in split_outside_brackets(rules, ';') {
+        let (name, value) = property
+            .split_once(':')
+            .ok_or_else(|| format!("Rule '{}' has no value", property))?;
+        let value = value.trim();
+        let number = || value.parse::<usize>().map_err(|_| format!("'{}' is not a number", value));
+        match name.trim().to_ascii_lowercase().as_str() {
+            "minlength" => parsed.min_length = Some(number()?),
+            "maxlength" => parsed.max_length = Some(number()?),
+            "max-consecutive" => parsed.max_consecutive = Some(number()?),
+            "required" => {
+                let (set, _) = parse_classes(value)?;
+                if !set.is_empty() {
+                    parsed.allowed.extend(&set);
+                    parsed.required.push(set);
+                }
+            }
+            "allowed" => {
+                let (set, unicode) = parse_classes(value)?;
+                parsed.allowed.extend(set);
+                parsed.any_character |= unicode;
+            }
+            other => return Err(format!("Unknown rule '{}'", other)),
+        }
+    }
+    // Rules no password could meet would leave the generator trying forever
+    if parsed.max_consecutive == Some(0) {
+        return Err("max-consecutive must be at least 1".to_string());
+    }
+    if let (Some(min), Some(max)) = (parsed.min_length, parsed.max_length)
+        && min > max
+    {
+        return Err(format!("minlength {} is more than maxlength {}", min, max));
+    }
+    parsed.allowed.sort();
+    parsed.allowed.dedup();
+    Ok(parsed)
+}
+
+impl PasswordRules {
+    // Characters the generator picks from
+    fn characters(&self) -> Vec<char> {
+        if self.allowed.is_empty() {
+            DEFAULT_CHARACTERS.chars().collect()
+        } else {
+            self.allowed.clone()
+        }
+    }
+
+    // Closest length to `preferred` the site accepts
+    pub fn length_for(&self, preferred: usize) -> usize {
+        let mut length = preferred;
+        if let Some(max) = self.max_length {
+            length = length.min(max);
+        }
+        if let Some(min) = self.min_length {
+            length = length.max(min);
+        }
+        // Room for one character from each required set
+        length.max(self.required.len()).max(1)
+    }
+
+    // What's wrong with a password under these rules; empty when it passes
+    pub fn problems(&self, password: &str) -> Vec<String> {
+        let mut problems = Vec::new();
+        let length = password.chars().count();
+        if let Some(min) = self.min_length
+            && length < min
+        {
+            problems.push(format!("Needs at least {} characters", min));
+        }
+        if let Some(max) = self.max_length
+            && length > max
+        {
+            problems.push(format!("Can't be longer than {} characters", max));
+        }
+        for set in &self.required {
+            if !password.chars().any(|c| set.contains(&c)) {
+                problems.push(format!("Needs one of: {}", describe_set(set)));
+            }
+        }
+        if !self.allowed.is_empty() && !self.any_character {
+            let mut not_allowed: Vec<char> = password.chars().filter(|c| !self.allowed.contains(c)).collect();
+            not_allowed.sort();
+            not_allowed.dedup();
+            if !not_allowed.is_empty() {
+                problems.push(format!("Not accepted: {}", not_allowed.into_iter().collect::<String>()));
+            }
+        }
+        if let Some(max) = self.max_consecutive
+            && longest_run(password) > max
+        {
+            problems.push(format!("No more than {} identical characters in a row", max));
+        }
+        problems
+    }
+
+    // One-line summary for the add form
+    pub fn describe(&self) -> String {
+        let mut parts = Vec::new();
+        match (self.min_length, self.max_length) {
+            (Some(min), Some(max)) => parts.push(format!("{}-{} characters", min, max)),
+            (Some(min), None) => parts.push(format!("at least {} characters", min)),
+            (None, Some(max)) => parts.push(format!("up to {} characters", max)),
+            (None, None) => {}
+        }
+        for set in &self.required {
+            parts.push(format!("needs {}", describe_set(set)));
+        }
+        let symbols: String = self.allowed.iter().filter(|c| !c.is_ascii_alphanumeric()).collect();
+        if self.any_character || SPECIAL_CHARACTERS.chars().all(|c| symbols.contains(c)) {
+            // Any symbol is fine, nothing worth listing
+        } else if !self.allowed.is_empty() {
+            parts.push(if symbols.is_empty() { "no symbols".to_string() } else { format!("symbols {}", symbols) });
+        }
+        if let Some(max) = self.max_consecutive {
+            parts.push(format!("max {} repeats in a row", max));
+        }
+        parts.join(", ")
+    }
+}
+
+// "a-z", "A-Z", "0-9" for the usual classes, otherwise the characters themselves
+fn describe_set(set: &[char]) -> String {
+    let mut names = Vec::new();
+    let mut others = String::new();
+    for (name, range) in [("a-z", 'a'..='z'), ("A-Z", 'A'..='Z'), ("0-9", '0'..='9')] {
+        if range.clone().all(|c| set.contains(&c)) {
+            names.push(name.to_string());
+        }
+    }
+    if SPECIAL_CHARACTERS.chars().all(|c| set.contains(&c)) {
+        names.push("a symbol".to_string());
+    }
+    for c in set {
+        if c.is_ascii_punctuation() && names.iter().any(|name| name == "a symbol") {
+            continue;
+        }
+        if !names.iter().any(|name| match name.as_str() {
+            "a-z" => c.is_ascii_lowercase(),
+            "A-Z" => c.is_ascii_uppercase(),
+            _ => c.is_ascii_digit(),
+        }) {
+            others.push(*c);
+        }
+    }
+    if !others.is_empty() {
+        names.push(others);
+    }
+    names.join(" or ")
+}
+
+fn longest_run(password: &str) -> usize {
+    let mut longest = 0;
+    let mut run = 0;
+    let mut previous = None;
+    for c in password.chars() {
+        run = if Some(c) == previous { run + 1 } else { 1 };
+        longest = longest.max(run);
+        previous = Some(c);
+    }
+    longest
+}
+
+// A random password that meets the rules, as close to `preferred_length` as allowed
+pub fn generate<R: Rng + ?Sized>(rng: &mut R, rules: &PasswordRules, preferred_length: usize) -> Result<String, String> {
+    let length = rules.length_for(preferred_length);
+    let characters = rules.characters();
+
+    // Rare clashes with max-consecutive are simply generated again. Rules that
+    // can't be met, like one allowed character and max-consecutive: 1, never
+    // pass, so give up after a while.
+    for _ in 0..MAX_ATTEMPTS {
+        let mut password: Vec<char> = rules
+            .required
+            .iter()
+            .map(|set| set[rng.random_range(0..set.len())])
+            .collect();
+        while password.len() < length {
+            password.push(characters[rng.random_range(0..characters.len())]);
+        }
+        password.shuffle(rng);
+        let password: String = password.into_iter().collect();
+        if rules.max_consecutive.is_none_or(|max| longest_run(&password) <= max) {
+            return Ok(password);
+        }
+    }
+    Err("Could not make a password that meets the site's rules; they may be impossible to meet".to_string())
+}
+
+// Rules by domain: the user's password-rules.json if there is one, otherwise the built-in list
+fn rules_database() -> &'static HashMap<String, String> {
+    static DATABASE: OnceLock<HashMap<String, String>> = OnceLock::new();
+    DATABASE.get_or_init(|| {
+        let user_rules = std::fs::read_to_string(RULES_FILE).ok();
+        let json = user_rules.as_deref().unwrap_or(BUILT_IN_RULES);
+        let entries: HashMap<String, RuleEntry> = serde_json::from_str(json).unwrap_or_else(|e| {
+            println!("Error reading password rules, using the built-in list: {}", e);
+            serde_json::from_str(BUILT_IN_RULES).unwrap_or_default()
+        });
+        entries
+            .into_iter()
+            .map(|(domain, entry)| (domain.to_ascii_lowercase(), entry.password_rules))
+            .collect()
+    })
+}
+
+// "https://www.Login.Example.com/path" -> "login.example.com"
+fn host_of(website: &str) -> String {
+    let website = website.trim();
+    let without_scheme = website.split_once("://").map(|(_, rest)| rest).unwrap_or(website);
+    let host = without_scheme.split(['/', '?', '#']).next().unwrap_or("");
+    let host = host.rsplit_once('@').map(|(_, host)| host).unwrap_or(host);
+    let host = host.split(':').next().unwrap_or("");
+    host.trim_start_matches("www.").to_ascii_lowercase()
+}
+
+// Finds rules for a website, trying the full host and then each parent
+// domain, so "login.example.com" also matches rules for "example.com".
+// Returns the matching domain with its rules.
+pub fn rules_for_website(website: &str) -> Option<(String, PasswordRules)> {
+    let host = host_of(website);
+    let database = rules_database();
+    let mut domain = host.as_str();
+    loop {
+        if let Some(rules) = database.get(domain) {
+            return match parse_rules(rules) {
+                Ok(rules) => Some((domain.to_string(), rules)),
+                Err(e) => {
+                    println!("Bad password rules for {}: {}", domain, e);
+                    None
+                }
+            };
+        }
+        // Stop before trying a bare top-level domain like "com"
+        match domain.split_once('.') {
+            Some((_, parent)) if parent.contains('.') => domain = parent,
+            _ => return None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::genr::tests::{assert_uniform, count_chars, seeded};
+
+    #[test]
+    fn generated_passwords_meet_every_bundled_site_rule() {
+        let database: HashMap<String, RuleEntry> = serde_json::from_str(BUILT_IN_RULES).unwrap();
+        let mut rng = seeded(10);
+        for (domain, entry) in database {
+            let rules = parse_rules(&entry.password_rules).unwrap();
+            for preferred in [4, 16, 40] {
+                for _ in 0..200 {
+                    let password = generate(&mut rng, &rules, preferred).unwrap();
+                    assert_eq!(rules.problems(&password), Vec::<String>::new(), "{}: {}", domain, password);
+                    assert_eq!(password.chars().count(), rules.length_for(preferred));
+                }
+            }
+        }
+    }
+
+    // The character guaranteed for each required class must not end up in a
+    // fixed place, or make its class's own characters uneven
+    #[test]
+    fn required_classes_do_not_bias_output() {
+        let rules = parse_rules("minlength: 10; maxlength: 10; required: digit; required: upper; allowed: lower;").unwrap();
+        let mut rng = seeded(11);
+        let mut digit_positions = vec![0; 10];
+        let mut upper_positions = vec![0; 10];
+        let (mut lower, mut digits, mut upper) = (vec![0; 26], vec![0; 10], vec![0; 26]);
+        for _ in 0..20_000 {
+            let password = generate(&mut rng, &rules, 10).unwrap();
+            for (i, c) in password.chars().enumerate() {
+                if c.is_ascii_digit() {
+                    digit_positions[i] += 1;
+                    count_chars(&c.to_string(), "0123456789", &mut digits);
+                } else if c.is_ascii_uppercase() {
+                    upper_positions[i] += 1;
+                    count_chars(&c.to_string(), "ABCDEFGHIJKLMNOPQRSTUVWXYZ", &mut upper);
+                } else {
+                    count_chars(&c.to_string(), "abcdefghijklmnopqrstuvwxyz", &mut lower);
+                }
+            }
+        }
+        assert_uniform(&digit_positions, "digit positions");
+        assert_uniform(&upper_positions, "uppercase positions");
+        assert_uniform(&lower, "lowercase characters");
+        assert_uniform(&digits, "digits");
+        assert_uniform(&upper, "uppercase characters");
+    }
+
+    #[test]
+    fn impossible_rules_are_refused() {
+        assert!(parse_rules("max-consecutive: 0;").is_err());
+        assert!(parse_rules("minlength: 20; maxlength: 10;").is_err());
+        // Only caught by trying: every password would be "aa..."
+        let rules = parse_rules("minlength: 4; allowed: [a]; max-consecutive: 1;").unwrap();
+        assert!(generate(&mut seeded(12), &rules, 16).is_err());
+    }
+}
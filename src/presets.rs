@@ -0,0 +1,140 @@
+// presets.rs - Named generator settings, e.g. "PIN: 6 digits", picked from a
+// dropdown on the generator page. They are saved with the rest of the settings
+// and can be exported to a file and imported elsewhere to share them.
+use serde::{Serialize, Deserialize};
+
+use crate::genr;
+
+// Written into exported files so a future format can tell them apart
+const PRESETS_FILE_VERSION: u32 = 1;
+pub const MAX_RANDOM_LENGTH: usize = 256;
+
+// What the generator is set to. Derived and dice passwords need secrets or
+// rolls every time, so they can't be presets.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(tag = "mode", rename_all = "lowercase")]
+pub enum PresetPolicy {
+    Random { length: usize },
+    Pattern { pattern: String },
+    Pronounceable { length: usize, digits: usize, symbols: usize },
+    Passphrase { words: usize, separator: String },
+}
+
+impl PresetPolicy {
+    pub fn check(&self) -> Result<(), String> {
+        match self {
+            PresetPolicy::Random { length } => {
+                if !(1..=MAX_RANDOM_LENGTH).contains(length) {
+                    return Err(format!("Length must be between 1 and {}", MAX_RANDOM_LENGTH));
+                }
+                Ok(())
+            }
+            PresetPolicy::Pattern { pattern } => genr::parse_pattern(pattern).map(|_| ()),
+            PresetPolicy::Pronounceable { length, digits, symbols } => genr::PronounceableOptions {
+                length: *length,
+                digits: *digits,
+                symbols: *symbols,
+            }
+            .check(),
+            PresetPolicy::Passphrase { words, .. } => {
+                if !(genr::MIN_PASSPHRASE_WORDS..=genr::MAX_PASSPHRASE_WORDS).contains(words) {
+                    return Err(format!(
+                        "Passphrases need between {} and {} words",
+                        genr::MIN_PASSPHRASE_WORDS, genr::MAX_PASSPHRASE_WORDS
+                    ));
+                }
+                Ok(())
+            }
+        }
+    }
+
+    // e.g. "Pattern \d{6}" or "Passphrase, 5 words joined by "-""
+    pub fn describe(&self) -> String {
+        match self {
+            PresetPolicy::Random { length } => format!("Random, {} letters and digits", length),
+            PresetPolicy::Pattern { pattern } => format!("Pattern {}", pattern),
+            PresetPolicy::Pronounceable { length, digits, symbols } => format!(
+                "Pronounceable, {} characters with {} digits and {} symbols",
+                length, digits, symbols
+            ),
+            PresetPolicy::Passphrase { words, separator } => {
+                format!("Passphrase, {} words joined by \"{}\"", words, separator)
+            }
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct GeneratorPreset {
+    pub name: String,
+    #[serde(flatten)]
+    pub policy: PresetPolicy,
+}
+
+// What a new settings file starts with
+pub fn default_presets() -> Vec<GeneratorPreset> {
+    vec![
+        GeneratorPreset {
+            name: "Banking".to_string(),
+            policy: PresetPolicy::Random { length: 20 },
+        },
+        GeneratorPreset {
+            name: "WPA key".to_string(),
+            policy: PresetPolicy::Random { length: crate::wifi::WPA_KEY_LENGTH },
+        },
+        GeneratorPreset {
+            name: "PIN".to_string(),
+            policy: PresetPolicy::Pattern { pattern: r"\d{6}".to_string() },
+        },
+    ]
+}
+
+#[derive(Serialize, Deserialize)]
+struct PresetsFile {
+    version: u32,
+    presets: Vec<GeneratorPreset>,
+}
+
+// Adds presets, replacing ones with the same name. Returns (added, replaced).
+pub fn merge_presets(presets: &mut Vec<GeneratorPreset>, new_presets: Vec<GeneratorPreset>) -> (usize, usize) {
+    let (mut added, mut replaced) = (0, 0);
+    for preset in new_presets {
+        match presets.iter_mut().find(|existing| existing.name.eq_ignore_ascii_case(&preset.name)) {
+            Some(existing) => {
+                *existing = preset;
+                replaced += 1;
+            }
+            None => {
+                presets.push(preset);
+                added += 1;
+            }
+        }
+    }
+    (added, replaced)
+}
+
+pub fn export_presets(presets: &[GeneratorPreset], file_path: &str) -> Result<(), String> {
+    let file = PresetsFile {
+        version: PRESETS_FILE_VERSION,
+        presets: presets.to_vec(),
+    };
+    let json_data = serde_json::to_string_pretty(&file).map_err(|e| format!("Could not write presets: {}", e))?;
+    std::fs::write(file_path, json_data).map_err(|e| format!("Could not write {}: {}", file_path, e))
+}
+
+// Reads an exported presets file, checking every preset before any is used
+pub fn import_presets(file_path: &str) -> Result<Vec<GeneratorPreset>, String> {
+    let contents = std::fs::read_to_string(file_path).map_err(|e| format!("Could not read {}: {}", file_path, e))?;
+    let file: PresetsFile =
+        serde_json::from_str(&contents).map_err(|e| format!("{} is not a presets file: {}", file_path, e))?;
+    if file.version != PRESETS_FILE_VERSION {
+        return Err(format!("Presets file version {} is not supported", file.version));
+    }
+    for preset in &file.presets {
+        if preset.name.trim().is_empty() {
+            return Err("A preset in the file has no name".to_string());
+        }
+        preset.policy.check().map_err(|e| format!("Preset '{}': {}", preset.name, e))?;
+    }
+    Ok(file.presets)
+}
@@ -0,0 +1,909 @@
+// qr.rs - A small offline QR code reader used to import one-time code secrets.
+//
+// It handles what authenticator exports and screenshots look like in practice:
+// a QR code that is seen straight on (any rotation, no perspective distortion).
+// Finder patterns are located on a binarized image, the module grid is sampled
+// through an affine transform and the data is put through Reed-Solomon error
+// correction before the segments are decoded.
+
+// Otsu's method: the threshold that best separates dark and light pixels
+fn otsu_threshold(luma: &[u8]) -> u8 {
+    let mut histogram = [0usize; 256];
+    for &value in luma {
+        histogram[value as usize] += 1;
+    }
+
+    let total = luma.len() as f64;
+    let sum_all: f64 = histogram.iter().enumerate().map(|(i, &c)| i as f64 * c as f64).sum();
+    let mut sum_dark = 0.0;
+    let mut weight_dark = 0.0;
+    let mut best = (0.0, 127u8);
+
+    for (threshold, &count) in histogram.iter().enumerate() {
+        weight_dark += count as f64;
+        if weight_dark == 0.0 {
+            continue;
+        }
+        let weight_light = total - weight_dark;
+        if weight_light == 0.0 {
+            break;
+        }
+        sum_dark += threshold as f64 * count as f64;
+        let mean_dark = sum_dark / weight_dark;
+        let mean_light = (sum_all - sum_dark) / weight_light;
+        let variance = weight_dark * weight_light * (mean_dark - mean_light).powi(2);
+        if variance > best.0 {
+            best = (variance, threshold as u8);
+        }
+    }
+    best.1
+}
+
+struct BitImage {
+    width: usize,
+    height: usize,
+    dark: Vec<bool>,
+}
+
+impl BitImage {
+    fn new(width: usize, height: usize, luma: &[u8]) -> Self {
+        let threshold = otsu_threshold(luma);
+        Self {
+            width,
+            height,
+            dark: luma.iter().map(|&v| v <= threshold).collect(),
+        }
+    }
+
+    fn is_dark(&self, x: usize, y: usize) -> bool {
+        self.dark[y * self.width + x]
+    }
+
+    // Pixel lookup for sampling, anything outside the image counts as light
+    fn sample(&self, x: f64, y: f64) -> bool {
+        if x < 0.0 || y < 0.0 {
+            return false;
+        }
+        let (x, y) = (x as usize, y as usize);
+        x < self.width && y < self.height && self.is_dark(x, y)
+    }
+}
+
+#[derive(Clone, Copy)]
+struct FinderCandidate {
+    x: f64,
+    y: f64,
+    module: f64,
+    count: usize,
+}
+
+// A finder pattern crosses any line through its centre as dark/light/dark/light/dark
+// runs in a 1:1:3:1:1 ratio
+fn finder_ratio_ok(counts: &[usize; 5]) -> bool {
+    let total: usize = counts.iter().sum();
+    if total < 7 || counts.contains(&0) {
+        return false;
+    }
+    let module = total as f64 / 7.0;
+    let max_variance = module / 2.0;
+    (module - counts[0] as f64).abs() < max_variance
+        && (module - counts[1] as f64).abs() < max_variance
+        && (3.0 * module - counts[2] as f64).abs() < 3.0 * max_variance
+        && (module - counts[3] as f64).abs() < max_variance
+        && (module - counts[4] as f64).abs() < max_variance
+}
+
+// Walks outwards from (x, y) along (dx, dy) and returns the run lengths of the pattern.
+// Returns the 5 counts and the coordinate just past the far end.
+fn cross_check(image: &BitImage, x: usize, y: usize, dx: isize, dy: isize, max_count: usize) -> Option<f64> {
+    let get = |step: isize| -> Option<bool> {
+        let px = x as isize + dx * step;
+        let py = y as isize + dy * step;
+        if px < 0 || py < 0 || px as usize >= image.width || py as usize >= image.height {
+            None
+        } else {
+            Some(image.is_dark(px as usize, py as usize))
+        }
+    };
+
+    let mut counts = [0usize; 5];
+
+    // Backwards from the centre
+    let mut step = 0isize;
+    while get(step) == Some(true) {
+        counts[2] += 1;
+        step -= 1;
+    }
+    while get(step) == Some(false) && counts[1] <= max_count {
+        counts[1] += 1;
+        step -= 1;
+    }
+    while get(step) == Some(true) && counts[0] <= max_count {
+        counts[0] += 1;
+        step -= 1;
+    }
+
+    // Forwards from the centre
+    step = 1;
+    while get(step) == Some(true) {
+        counts[2] += 1;
+        step += 1;
+    }
+    while get(step) == Some(false) && counts[3] <= max_count {
+        counts[3] += 1;
+        step += 1;
+    }
+    while get(step) == Some(true) && counts[4] <= max_count {
+        counts[4] += 1;
+        step += 1;
+    }
+
+    if !finder_ratio_ok(&counts) {
+        return None;
+    }
+
+    // Offset of the pattern centre from (x, y) along the scan direction
+    let end = step as f64;
+    Some(end - counts[4] as f64 - counts[3] as f64 - counts[2] as f64 / 2.0)
+}
+
+fn find_finder_patterns(image: &BitImage) -> Vec<FinderCandidate> {
+    let mut candidates: Vec<FinderCandidate> = Vec::new();
+
+    for y in 0..image.height {
+        // Run-length encode the row
+        let mut runs: Vec<(bool, usize, usize)> = Vec::new();
+        for x in 0..image.width {
+            let dark = image.is_dark(x, y);
+            match runs.last_mut() {
+                Some(run) if run.0 == dark => run.2 += 1,
+                _ => runs.push((dark, x, 1)),
+            }
+        }
+
+        for window in runs.windows(5) {
+            if !window[0].0 {
+                continue;
+            }
+            let counts = [window[0].2, window[1].2, window[2].2, window[3].2, window[4].2];
+            if !finder_ratio_ok(&counts) {
+                continue;
+            }
+            let total: usize = counts.iter().sum();
+            let centre_x = window[2].1 + window[2].2 / 2;
+
+            let Some(offset_y) = cross_check(image, centre_x, y, 0, 1, total) else {
+                continue;
+            };
+            let centre_y = y as f64 + offset_y;
+            if centre_y < 0.0 || centre_y as usize >= image.height {
+                continue;
+            }
+            let Some(offset_x) = cross_check(image, centre_x, centre_y as usize, 1, 0, total) else {
+                continue;
+            };
+            let centre_x = centre_x as f64 + offset_x;
+            let module = total as f64 / 7.0;
+
+            // Merge with a candidate we've already seen on a previous row
+            match candidates.iter_mut().find(|c| {
+                (c.x - centre_x).abs() <= c.module * 2.0
+                    && (c.y - centre_y).abs() <= c.module * 2.0
+                    && (c.module / module).max(module / c.module) < 1.5
+            }) {
+                Some(existing) => {
+                    let n = existing.count as f64;
+                    existing.x = (existing.x * n + centre_x) / (n + 1.0);
+                    existing.y = (existing.y * n + centre_y) / (n + 1.0);
+                    existing.module = (existing.module * n + module) / (n + 1.0);
+                    existing.count += 1;
+                }
+                None => candidates.push(FinderCandidate {
+                    x: centre_x,
+                    y: centre_y,
+                    module,
+                    count: 1,
+                }),
+            }
+        }
+    }
+
+    candidates.sort_by_key(|c| std::cmp::Reverse(c.count));
+    candidates
+}
+
+fn distance(a: &FinderCandidate, b: &FinderCandidate) -> f64 {
+    ((a.x - b.x).powi(2) + (a.y - b.y).powi(2)).sqrt()
+}
+
+// Orders three finder patterns as (top-left, top-right, bottom-left) if they
+// look like the corners of one QR code. Also returns how far from ideal they are.
+fn order_triple(
+    a: FinderCandidate,
+    b: FinderCandidate,
+    c: FinderCandidate,
+) -> Option<(f64, [FinderCandidate; 3])> {
+    let modules = [a.module, b.module, c.module];
+    let max_module = modules.iter().cloned().fold(f64::MIN, f64::max);
+    let min_module = modules.iter().cloned().fold(f64::MAX, f64::min);
+    if max_module / min_module > 1.5 {
+        return None;
+    }
+
+    // The top-left corner is opposite the longest side
+    let ab = distance(&a, &b);
+    let bc = distance(&b, &c);
+    let ca = distance(&c, &a);
+    let (top_left, p, q, hypotenuse, leg1, leg2) = if bc >= ab && bc >= ca {
+        (a, b, c, bc, ab, ca)
+    } else if ca >= ab && ca >= bc {
+        (b, c, a, ca, ab, bc)
+    } else {
+        (c, a, b, ab, bc, ca)
+    };
+
+    // A line through the centre of a rotated finder pattern crosses more than
+    // 7 modules, so scale the module size back by the rotation of the symbol
+    let angle = (p.y - top_left.y).atan2(p.x - top_left.x);
+    let correction = angle.cos().abs().max(angle.sin().abs());
+    let (mut top_left, mut p, mut q) = (top_left, p, q);
+    for corner in [&mut top_left, &mut p, &mut q] {
+        corner.module *= correction;
+    }
+
+    let module = (a.module + b.module + c.module) / 3.0 * correction;
+    if leg1.min(leg2) < 13.0 * module {
+        return None;
+    }
+    let leg_error = (leg1 - leg2).abs() / leg1.max(leg2);
+    let hypotenuse_error = (hypotenuse - (leg1 * leg1 + leg2 * leg2).sqrt()).abs() / hypotenuse;
+    if leg_error > 0.2 || hypotenuse_error > 0.1 {
+        return None;
+    }
+
+    // In image coordinates (y pointing down) top-right follows top-left clockwise
+    let cross = (p.x - top_left.x) * (q.y - top_left.y) - (p.y - top_left.y) * (q.x - top_left.x);
+    let (top_right, bottom_left) = if cross > 0.0 { (p, q) } else { (q, p) };
+
+    let score = leg_error + hypotenuse_error + (max_module / min_module - 1.0);
+    Some((score, [top_left, top_right, bottom_left]))
+}
+
+// Data codewords per block for each version (1-40) and EC level (L, M, Q, H):
+// (bytes in group 1 blocks, number of group 1 blocks, bytes in group 2 blocks, number of group 2 blocks)
+// From ISO/IEC 18004 table 9.
+static DATA_BYTES_PER_BLOCK: [[(usize, usize, usize, usize); 4]; 40] = [
+    [(19, 1, 0, 0), (16, 1, 0, 0), (13, 1, 0, 0), (9, 1, 0, 0)],
+    [(34, 1, 0, 0), (28, 1, 0, 0), (22, 1, 0, 0), (16, 1, 0, 0)],
+    [(55, 1, 0, 0), (44, 1, 0, 0), (17, 2, 0, 0), (13, 2, 0, 0)],
+    [(80, 1, 0, 0), (32, 2, 0, 0), (24, 2, 0, 0), (9, 4, 0, 0)],
+    [(108, 1, 0, 0), (43, 2, 0, 0), (15, 2, 16, 2), (11, 2, 12, 2)],
+    [(68, 2, 0, 0), (27, 4, 0, 0), (19, 4, 0, 0), (15, 4, 0, 0)],
+    [(78, 2, 0, 0), (31, 4, 0, 0), (14, 2, 15, 4), (13, 4, 14, 1)],
+    [(97, 2, 0, 0), (38, 2, 39, 2), (18, 4, 19, 2), (14, 4, 15, 2)],
+    [(116, 2, 0, 0), (36, 3, 37, 2), (16, 4, 17, 4), (12, 4, 13, 4)],
+    [(68, 2, 69, 2), (43, 4, 44, 1), (19, 6, 20, 2), (15, 6, 16, 2)],
+    [(81, 4, 0, 0), (50, 1, 51, 4), (22, 4, 23, 4), (12, 3, 13, 8)],
+    [(92, 2, 93, 2), (36, 6, 37, 2), (20, 4, 21, 6), (14, 7, 15, 4)],
+    [(107, 4, 0, 0), (37, 8, 38, 1), (20, 8, 21, 4), (11, 12, 12, 4)],
+    [(115, 3, 116, 1), (40, 4, 41, 5), (16, 11, 17, 5), (12, 11, 13, 5)],
+    [(87, 5, 88, 1), (41, 5, 42, 5), (24, 5, 25, 7), (12, 11, 13, 7)],
+    [(98, 5, 99, 1), (45, 7, 46, 3), (19, 15, 20, 2), (15, 3, 16, 13)],
+    [(107, 1, 108, 5), (46, 10, 47, 1), (22, 1, 23, 15), (14, 2, 15, 17)],
+    [(120, 5, 121, 1), (43, 9, 44, 4), (22, 17, 23, 1), (14, 2, 15, 19)],
+    [(113, 3, 114, 4), (44, 3, 45, 11), (21, 17, 22, 4), (13, 9, 14, 16)],
+    [(107, 3, 108, 5), (41, 3, 42, 13), (24, 15, 25, 5), (15, 15, 16, 10)],
+    [(116, 4, 117, 4), (42, 17, 0, 0), (22, 17, 23, 6), (16, 19, 17, 6)],
+    [(111, 2, 112, 7), (46, 17, 0, 0), (24, 7, 25, 16), (13, 34, 0, 0)],
+    [(121, 4, 122, 5), (47, 4, 48, 14), (24, 11, 25, 14), (15, 16, 16, 14)],
+    [(117, 6, 118, 4), (45, 6, 46, 14), (24, 11, 25, 16), (16, 30, 17, 2)],
+    [(106, 8, 107, 4), (47, 8, 48, 13), (24, 7, 25, 22), (15, 22, 16, 13)],
+    [(114, 10, 115, 2), (46, 19, 47, 4), (22, 28, 23, 6), (16, 33, 17, 4)],
+    [(122, 8, 123, 4), (45, 22, 46, 3), (23, 8, 24, 26), (15, 12, 16, 28)],
+    [(117, 3, 118, 10), (45, 3, 46, 23), (24, 4, 25, 31), (15, 11, 16, 31)],
+    [(116, 7, 117, 7), (45, 21, 46, 7), (23, 1, 24, 37), (15, 19, 16, 26)],
+    [(115, 5, 116, 10), (47, 19, 48, 10), (24, 15, 25, 25), (15, 23, 16, 25)],
+    [(115, 13, 116, 3), (46, 2, 47, 29), (24, 42, 25, 1), (15, 23, 16, 28)],
+    [(115, 17, 0, 0), (46, 10, 47, 23), (24, 10, 25, 35), (15, 19, 16, 35)],
+    [(115, 17, 116, 1), (46, 14, 47, 21), (24, 29, 25, 19), (15, 11, 16, 46)],
+    [(115, 13, 116, 6), (46, 14, 47, 23), (24, 44, 25, 7), (16, 59, 17, 1)],
+    [(121, 12, 122, 7), (47, 12, 48, 26), (24, 39, 25, 14), (15, 22, 16, 41)],
+    [(121, 6, 122, 14), (47, 6, 48, 34), (24, 46, 25, 10), (15, 2, 16, 64)],
+    [(122, 17, 123, 4), (46, 29, 47, 14), (24, 49, 25, 10), (15, 24, 16, 46)],
+    [(122, 4, 123, 18), (46, 13, 47, 32), (24, 48, 25, 14), (15, 42, 16, 32)],
+    [(117, 20, 118, 4), (47, 40, 48, 7), (24, 43, 25, 22), (15, 10, 16, 67)],
+    [(118, 19, 119, 6), (47, 18, 48, 31), (24, 34, 25, 34), (15, 20, 16, 61)],
+];
+
+// Error correction codewords per block, same indexing as DATA_BYTES_PER_BLOCK
+static EC_BYTES_PER_BLOCK: [[usize; 4]; 40] = [
+    [7, 10, 13, 17], [10, 16, 22, 28], [15, 26, 18, 22], [20, 18, 26, 16], [26, 24, 18, 22],
+    [18, 16, 24, 28], [20, 18, 18, 26], [24, 22, 22, 26], [30, 22, 20, 24], [18, 26, 24, 28],
+    [20, 30, 28, 24], [24, 22, 26, 28], [26, 22, 24, 22], [30, 24, 20, 24], [22, 24, 30, 24],
+    [24, 28, 24, 30], [28, 28, 28, 28], [30, 26, 28, 28], [28, 26, 26, 26], [28, 26, 30, 28],
+    [28, 26, 28, 30], [28, 28, 30, 24], [30, 28, 30, 30], [30, 28, 30, 30], [26, 28, 30, 30],
+    [28, 28, 28, 30], [30, 28, 30, 30], [30, 28, 30, 30], [30, 28, 30, 30], [30, 28, 30, 30],
+    [30, 28, 30, 30], [30, 28, 30, 30], [30, 28, 30, 30], [30, 28, 30, 30], [30, 28, 30, 30],
+    [30, 28, 30, 30], [30, 28, 30, 30], [30, 28, 30, 30], [30, 28, 30, 30], [30, 28, 30, 30],
+];
+
+// Alignment pattern centres for versions 7-40; versions 2-6 use [6, size - 7]
+static ALIGNMENT_PATTERN_POSITIONS: [&[usize]; 34] = [
+    &[6, 22, 38], &[6, 24, 42], &[6, 26, 46], &[6, 28, 50], &[6, 30, 54], &[6, 32, 58], &[6, 34, 62],
+    &[6, 26, 46, 66], &[6, 26, 48, 70], &[6, 26, 50, 74], &[6, 30, 54, 78], &[6, 30, 56, 82],
+    &[6, 30, 58, 86], &[6, 34, 62, 90], &[6, 28, 50, 72, 94], &[6, 26, 50, 74, 98],
+    &[6, 30, 54, 78, 102], &[6, 28, 54, 80, 106], &[6, 32, 58, 84, 110], &[6, 30, 58, 86, 114],
+    &[6, 34, 62, 90, 118], &[6, 26, 50, 74, 98, 122], &[6, 30, 54, 78, 102, 126],
+    &[6, 26, 52, 78, 104, 130], &[6, 30, 56, 82, 108, 134], &[6, 34, 60, 86, 112, 138],
+    &[6, 30, 58, 86, 114, 142], &[6, 34, 62, 90, 118, 146], &[6, 30, 54, 78, 102, 126, 150],
+    &[6, 24, 50, 76, 102, 128, 154], &[6, 28, 54, 80, 106, 132, 158], &[6, 32, 58, 84, 110, 136, 162],
+    &[6, 26, 54, 82, 110, 138, 166], &[6, 30, 58, 86, 114, 142, 170],
+];
+
+// Version information words for versions 7-40
+static VERSION_INFOS: [u32; 34] = [
+    0x07c94, 0x085bc, 0x09a99, 0x0a4d3, 0x0bbf6, 0x0c762, 0x0d847, 0x0e60d, 0x0f928, 0x10b78, 0x1145d, 0x12a17,
+    0x13532, 0x149a6, 0x15683, 0x168c9, 0x177ec, 0x18ec4, 0x191e1, 0x1afab, 0x1b08e, 0x1cc1a, 0x1d33f, 0x1ed75,
+    0x1f250, 0x209d5, 0x216f0, 0x228ba, 0x2379f, 0x24b0b, 0x2542e, 0x26a64, 0x27541, 0x28c69,
+];
+
+// Masked format information words, indexed by (EC bits << 3) | mask
+static FORMAT_INFOS: [u16; 32] = [
+    0x5412, 0x5125, 0x5e7c, 0x5b4b, 0x45f9, 0x40ce, 0x4f97, 0x4aa0, 0x77c4, 0x72f3, 0x7daa, 0x789d, 0x662f, 0x6318,
+    0x6c41, 0x6976, 0x1689, 0x13be, 0x1ce7, 0x19d0, 0x0762, 0x0255, 0x0d0c, 0x083b, 0x355f, 0x3068, 0x3f31, 0x3a06,
+    0x24b4, 0x2183, 0x2eda, 0x2bed,
+];
+
+// Module grid sampled from the image; `modules[y][x]` is true for dark
+struct Grid {
+    size: usize,
+    modules: Vec<Vec<bool>>,
+}
+
+impl Grid {
+    fn sample(image: &BitImage, corners: &[FinderCandidate; 3], size: usize) -> Self {
+        let [top_left, top_right, bottom_left] = corners;
+        let span = (size - 7) as f64;
+        let mut modules = vec![vec![false; size]; size];
+
+        for (y, row) in modules.iter_mut().enumerate() {
+            for (x, module) in row.iter_mut().enumerate() {
+                // Finder centres sit in the middle of module 3 (and size - 4)
+                let u = (x as f64 - 3.0) / span;
+                let v = (y as f64 - 3.0) / span;
+                let px = top_left.x + u * (top_right.x - top_left.x) + v * (bottom_left.x - top_left.x);
+                let py = top_left.y + u * (top_right.y - top_left.y) + v * (bottom_left.y - top_left.y);
+                *module = image.sample(px, py);
+            }
+        }
+        Self { size, modules }
+    }
+
+    fn get(&self, x: usize, y: usize) -> bool {
+        self.modules[y][x]
+    }
+
+    fn read_version(&self) -> Option<usize> {
+        let mut bits = 0u32;
+        for i in 0..18 {
+            let dark = self.get(5 - i / 3, self.size - 9 - i % 3);
+            bits = (bits << 1) | dark as u32;
+        }
+        closest(&VERSION_INFOS, bits, 3).map(|i| i + 7)
+    }
+
+    // Returns (EC level index in L, M, Q, H order, mask pattern)
+    fn read_format(&self) -> Option<(usize, u8)> {
+        let size = self.size;
+        let main = [
+            (0, 8), (1, 8), (2, 8), (3, 8), (4, 8), (5, 8), (7, 8), (8, 8),
+            (8, 7), (8, 5), (8, 4), (8, 3), (8, 2), (8, 1), (8, 0),
+        ];
+        let mut side: Vec<(usize, usize)> = (1..=7).map(|i| (8, size - i)).collect();
+        side.extend((1..=8).rev().map(|i| (size - i, 8)));
+
+        [&main[..], &side[..]].iter().find_map(|coords| {
+            let bits = coords
+                .iter()
+                .fold(0u32, |acc, &(x, y)| (acc << 1) | self.get(x, y) as u32);
+            closest(&FORMAT_INFOS.map(u32::from), bits, 3).map(|index| {
+                // Format EC bits are 01 = L, 00 = M, 11 = Q, 10 = H
+                let ec_level = [1, 0, 3, 2][index >> 3];
+                (ec_level, (index & 7) as u8)
+            })
+        })
+    }
+}
+
+// Index of the table word closest to `bits`, if within `max_distance` bit errors
+fn closest(table: &[u32], bits: u32, max_distance: u32) -> Option<usize> {
+    table
+        .iter()
+        .enumerate()
+        .map(|(i, &word)| (i, (word ^ bits).count_ones()))
+        .min_by_key(|&(_, distance)| distance)
+        .filter(|&(_, distance)| distance <= max_distance)
+        .map(|(i, _)| i)
+}
+
+fn function_modules(version: usize) -> Vec<Vec<bool>> {
+    let size = version * 4 + 17;
+    let mut reserved = vec![vec![false; size]; size];
+    let mut fill = |x0: usize, y0: usize, width: usize, height: usize| {
+        for row in reserved.iter_mut().skip(y0).take(height) {
+            for cell in row.iter_mut().skip(x0).take(width) {
+                *cell = true;
+            }
+        }
+    };
+
+    // Finder patterns with separators and format information
+    fill(0, 0, 9, 9);
+    fill(size - 8, 0, 8, 9);
+    fill(0, size - 8, 9, 8);
+    // Timing patterns
+    fill(6, 0, 1, size);
+    fill(0, 6, size, 1);
+
+    if version >= 2 {
+        let positions: Vec<usize> = if version >= 7 {
+            ALIGNMENT_PATTERN_POSITIONS[version - 7].to_vec()
+        } else {
+            vec![6, size - 7]
+        };
+        let last = *positions.last().unwrap_or(&6);
+        for &cy in &positions {
+            for &cx in &positions {
+                // Skip the three corners taken by finder patterns
+                if (cx == 6 && (cy == 6 || cy == last)) || (cx == last && cy == 6) {
+                    continue;
+                }
+                fill(cx - 2, cy - 2, 5, 5);
+            }
+        }
+    }
+
+    if version >= 7 {
+        fill(0, size - 11, 6, 3);
+        fill(size - 11, 0, 3, 6);
+    }
+    reserved
+}
+
+fn mask_bit(mask: u8, x: usize, y: usize) -> bool {
+    match mask {
+        0 => (x + y).is_multiple_of(2),
+        1 => y.is_multiple_of(2),
+        2 => x.is_multiple_of(3),
+        3 => (x + y).is_multiple_of(3),
+        4 => (y / 2 + x / 3).is_multiple_of(2),
+        5 => (x * y) % 2 + (x * y) % 3 == 0,
+        6 => ((x * y) % 2 + (x * y) % 3).is_multiple_of(2),
+        _ => ((x + y) % 2 + (x * y) % 3).is_multiple_of(2),
+    }
+}
+
+// Reads the codewords in the standard zig-zag order, removing the mask
+fn read_codewords(grid: &Grid, version: usize, mask: u8) -> Vec<u8> {
+    let size = grid.size;
+    let reserved = function_modules(version);
+    let mut codewords = Vec::new();
+    let mut current = 0u8;
+    let mut bit_count = 0;
+
+    let mut right = size as isize - 1;
+    let mut upwards = true;
+    while right >= 1 {
+        // Column 6 is the vertical timing pattern
+        if right == 6 {
+            right = 5;
+        }
+        for i in 0..size {
+            let y = if upwards { size - 1 - i } else { i };
+            for dx in 0..2 {
+                let x = right as usize - dx;
+                if reserved[y][x] {
+                    continue;
+                }
+                let bit = grid.get(x, y) ^ mask_bit(mask, x, y);
+                current = (current << 1) | bit as u8;
+                bit_count += 1;
+                if bit_count == 8 {
+                    codewords.push(current);
+                    current = 0;
+                    bit_count = 0;
+                }
+            }
+        }
+        upwards = !upwards;
+        right -= 2;
+    }
+    codewords
+}
+
+// Arithmetic in GF(256) with the QR polynomial x^8 + x^4 + x^3 + x^2 + 1
+struct Galois {
+    exp: [u8; 512],
+    log: [u8; 256],
+}
+
+impl Galois {
+    fn new() -> Self {
+        let mut exp = [0u8; 512];
+        let mut log = [0u8; 256];
+        let mut value: u16 = 1;
+        for (i, slot) in exp.iter_mut().take(255).enumerate() {
+            *slot = value as u8;
+            log[value as usize] = i as u8;
+            value <<= 1;
+            if value & 0x100 != 0 {
+                value ^= 0x11d;
+            }
+        }
+        for i in 255..512 {
+            exp[i] = exp[i - 255];
+        }
+        Self { exp, log }
+    }
+
+    fn mul(&self, a: u8, b: u8) -> u8 {
+        if a == 0 || b == 0 {
+            0
+        } else {
+            self.exp[self.log[a as usize] as usize + self.log[b as usize] as usize]
+        }
+    }
+
+    fn div(&self, a: u8, b: u8) -> u8 {
+        if a == 0 {
+            0
+        } else {
+            self.exp[(self.log[a as usize] as usize + 255 - self.log[b as usize] as usize) % 255]
+        }
+    }
+
+    fn pow_alpha(&self, power: usize) -> u8 {
+        self.exp[power % 255]
+    }
+
+    // Evaluates a polynomial stored lowest degree first
+    fn eval(&self, poly: &[u8], x: u8) -> u8 {
+        poly.iter().rev().fold(0, |acc, &c| self.mul(acc, x) ^ c)
+    }
+}
+
+// Corrects a block (data followed by EC codewords) in place.
+// Uses Berlekamp-Massey for the error locator and Forney for the magnitudes.
+fn correct_block(gf: &Galois, block: &mut [u8], ec_len: usize) -> Option<()> {
+    let n = block.len();
+    // Syndromes S_j = r(alpha^j); the block is highest degree first
+    let syndromes: Vec<u8> = (0..ec_len)
+        .map(|j| {
+            let x = gf.pow_alpha(j);
+            block.iter().fold(0, |acc, &c| gf.mul(acc, x) ^ c)
+        })
+        .collect();
+    if syndromes.iter().all(|&s| s == 0) {
+        return Some(());
+    }
+
+    // Berlekamp-Massey, polynomials lowest degree first
+    let mut locator = vec![1u8];
+    let mut previous = vec![1u8];
+    let mut errors = 0usize;
+    let mut shift = 1usize;
+    let mut previous_discrepancy = 1u8;
+    for step in 0..ec_len {
+        let mut discrepancy = syndromes[step];
+        for i in 1..=errors.min(locator.len() - 1) {
+            discrepancy ^= gf.mul(locator[i], syndromes[step - i]);
+        }
+        if discrepancy == 0 {
+            shift += 1;
+            continue;
+        }
+        let scale = gf.div(discrepancy, previous_discrepancy);
+        let mut updated = locator.clone();
+        if updated.len() < previous.len() + shift {
+            updated.resize(previous.len() + shift, 0);
+        }
+        for (i, &c) in previous.iter().enumerate() {
+            updated[i + shift] ^= gf.mul(scale, c);
+        }
+        if 2 * errors <= step {
+            previous = locator;
+            errors = step + 1 - errors;
+            previous_discrepancy = discrepancy;
+            shift = 1;
+        } else {
+            shift += 1;
+        }
+        locator = updated;
+    }
+    while locator.len() > 1 && *locator.last().unwrap_or(&0) == 0 {
+        locator.pop();
+    }
+    if errors * 2 > ec_len || locator.len() - 1 != errors {
+        return None;
+    }
+
+    // Chien search: position p (power of x) is in error if locator(alpha^-p) == 0
+    let positions: Vec<usize> = (0..n)
+        .filter(|&p| gf.eval(&locator, gf.pow_alpha(255 - p % 255)) == 0)
+        .collect();
+    if positions.len() != errors {
+        return None;
+    }
+
+    // Error evaluator omega = S(x) * locator(x) mod x^ec_len
+    let mut omega = vec![0u8; ec_len];
+    for (i, &s) in syndromes.iter().enumerate() {
+        for (j, &l) in locator.iter().enumerate() {
+            if i + j < ec_len {
+                omega[i + j] ^= gf.mul(s, l);
+            }
+        }
+    }
+    // Formal derivative keeps the odd powers
+    let derivative: Vec<u8> = locator
+        .iter()
+        .enumerate()
+        .skip(1)
+        .map(|(i, &c)| if i % 2 == 1 { c } else { 0 })
+        .collect();
+
+    for &p in &positions {
+        let x = gf.pow_alpha(p);
+        let x_inverse = gf.pow_alpha(255 - p % 255);
+        let denominator = gf.eval(&derivative, x_inverse);
+        if denominator == 0 {
+            return None;
+        }
+        let magnitude = gf.mul(x, gf.div(gf.eval(&omega, x_inverse), denominator));
+        block[n - 1 - p] ^= magnitude;
+    }
+    Some(())
+}
+
+// Undoes the block interleaving, corrects each block and returns the data codewords
+fn correct_codewords(codewords: &[u8], version: usize, ec_level: usize) -> Option<Vec<u8>> {
+    let (short_len, short_count, long_len, long_count) = DATA_BYTES_PER_BLOCK[version - 1][ec_level];
+    let ec_len = EC_BYTES_PER_BLOCK[version - 1][ec_level];
+    let data_lengths: Vec<usize> = std::iter::repeat_n(short_len, short_count)
+        .chain(std::iter::repeat_n(long_len, long_count))
+        .collect();
+    let total: usize = data_lengths.iter().map(|len| len + ec_len).sum();
+    if codewords.len() < total {
+        return None;
+    }
+
+    let mut blocks: Vec<Vec<u8>> = data_lengths.iter().map(|&len| Vec::with_capacity(len + ec_len)).collect();
+    let mut position = 0;
+    let longest = short_len.max(long_len);
+    for i in 0..longest {
+        for (block, &len) in blocks.iter_mut().zip(&data_lengths) {
+            if i < len {
+                block.push(codewords[position]);
+                position += 1;
+            }
+        }
+    }
+    for _ in 0..ec_len {
+        for block in blocks.iter_mut() {
+            block.push(codewords[position]);
+            position += 1;
+        }
+    }
+
+    let gf = Galois::new();
+    let mut data = Vec::new();
+    for (block, &len) in blocks.iter_mut().zip(&data_lengths) {
+        correct_block(&gf, block, ec_len)?;
+        data.extend_from_slice(&block[..len]);
+    }
+    Some(data)
+}
+
+struct BitReader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl BitReader<'_> {
+    fn remaining(&self) -> usize {
+        self.bytes.len() * 8 - self.position
+    }
+
+    fn read(&mut self, count: usize) -> Option<u32> {
+        if count > self.remaining() {
+            return None;
+        }
+        let mut value = 0u32;
+        for _ in 0..count {
+            let byte = self.bytes[self.position / 8];
+            let bit = (byte >> (7 - self.position % 8)) & 1;
+            value = (value << 1) | bit as u32;
+            self.position += 1;
+        }
+        Some(value)
+    }
+}
+
+const ALPHANUMERIC: &[u8] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZ $%*+-./:";
+
+// Decodes numeric, alphanumeric and byte segments into text
+fn decode_segments(data: &[u8], version: usize) -> Option<String> {
+    let size_class = match version {
+        1..=9 => 0,
+        10..=26 => 1,
+        _ => 2,
+    };
+    let mut reader = BitReader { bytes: data, position: 0 };
+    let mut output: Vec<u8> = Vec::new();
+
+    while reader.remaining() >= 4 {
+        match reader.read(4)? {
+            0b0000 => break,
+            // Numeric
+            0b0001 => {
+                let mut count = reader.read([10, 12, 14][size_class])? as usize;
+                while count >= 3 {
+                    output.extend(format!("{:03}", reader.read(10)?).bytes());
+                    count -= 3;
+                }
+                if count == 2 {
+                    output.extend(format!("{:02}", reader.read(7)?).bytes());
+                } else if count == 1 {
+                    output.extend(format!("{}", reader.read(4)?).bytes());
+                }
+            }
+            // Alphanumeric
+            0b0010 => {
+                let mut count = reader.read([9, 11, 13][size_class])? as usize;
+                while count >= 2 {
+                    let pair = reader.read(11)? as usize;
+                    output.push(*ALPHANUMERIC.get(pair / 45)?);
+                    output.push(*ALPHANUMERIC.get(pair % 45)?);
+                    count -= 2;
+                }
+                if count == 1 {
+                    output.push(*ALPHANUMERIC.get(reader.read(6)? as usize)?);
+                }
+            }
+            // Byte
+            0b0100 => {
+                let count = reader.read([8, 16, 16][size_class])?;
+                for _ in 0..count {
+                    output.push(reader.read(8)? as u8);
+                }
+            }
+            // ECI designator, the text is treated as UTF-8 regardless
+            0b0111 => {
+                let first = reader.read(8)?;
+                if first & 0x80 == 0x80 {
+                    reader.read(if first & 0xc0 == 0x80 { 8 } else { 16 })?;
+                }
+            }
+            // Structured append header
+            0b0011 => {
+                reader.read(16)?;
+            }
+            // FNC1 markers
+            0b0101 => {}
+            0b1001 => {
+                reader.read(8)?;
+            }
+            _ => return None,
+        }
+    }
+    Some(String::from_utf8_lossy(&output).into_owned())
+}
+
+fn decode_grid(image: &BitImage, corners: &[FinderCandidate; 3]) -> Option<String> {
+    let [top_left, top_right, bottom_left] = corners;
+    let module = (top_left.module + top_right.module + bottom_left.module) / 3.0;
+    let estimate = (distance(top_left, top_right) + distance(top_left, bottom_left)) / 2.0 / module + 7.0;
+    let version_estimate = ((estimate - 17.0) / 4.0).round().clamp(1.0, 40.0) as usize;
+
+    let mut versions = vec![version_estimate];
+    for delta in 1..=2 {
+        if version_estimate > delta {
+            versions.push(version_estimate - delta);
+        }
+        if version_estimate + delta <= 40 {
+            versions.push(version_estimate + delta);
+        }
+    }
+
+    for mut version in versions {
+        let mut grid = Grid::sample(image, corners, version * 4 + 17);
+        // From version 7 on the symbol says which version it is
+        if version >= 7
+            && let Some(read) = grid.read_version()
+            && read != version
+        {
+            version = read;
+            grid = Grid::sample(image, corners, version * 4 + 17);
+        }
+
+        let Some((ec_level, mask)) = grid.read_format() else {
+            continue;
+        };
+        let codewords = read_codewords(&grid, version, mask);
+        if let Some(data) = correct_codewords(&codewords, version, ec_level)
+            && let Some(text) = decode_segments(&data, version)
+        {
+            return Some(text);
+        }
+    }
+    None
+}
+
+// Decodes every QR code found in an 8-bit grayscale image
+pub fn decode_qr_codes(width: usize, height: usize, luma: &[u8]) -> Vec<String> {
+    if width == 0 || height == 0 || luma.len() < width * height {
+        return Vec::new();
+    }
+    let image = BitImage::new(width, height, &luma[..width * height]);
+
+    // Patterns seen on several rows are the most trustworthy
+    let candidates = find_finder_patterns(&image);
+    let mut candidates: Vec<FinderCandidate> = if candidates.iter().filter(|c| c.count >= 2).count() >= 3 {
+        candidates.into_iter().filter(|c| c.count >= 2).collect()
+    } else {
+        candidates
+    };
+    candidates.truncate(30);
+
+    let mut triples = Vec::new();
+    for i in 0..candidates.len() {
+        for j in i + 1..candidates.len() {
+            for k in j + 1..candidates.len() {
+                if let Some((score, ordered)) = order_triple(candidates[i], candidates[j], candidates[k]) {
+                    triples.push((score, [i, j, k], ordered));
+                }
+            }
+        }
+    }
+    triples.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+    let mut used = vec![false; candidates.len()];
+    let mut results = Vec::new();
+    for (_, indices, ordered) in triples {
+        if indices.iter().any(|&i| used[i]) {
+            continue;
+        }
+        if let Some(text) = decode_grid(&image, &ordered) {
+            for i in indices {
+                used[i] = true;
+            }
+            if !results.contains(&text) {
+                results.push(text);
+            }
+        }
+    }
+    results
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use qrcode::{EcLevel, QrCode};
+
+    #[test]
+    fn reads_back_generated_codes() {
+        let characters = b"otpauth://totp/Example:alice?secret=JBSWY3DPEHPK3PXP&issuer=Example%20Co ";
+        for level in [EcLevel::L, EcLevel::M, EcLevel::Q, EcLevel::H] {
+            for length in [5, 40, 150, 400, 900] {
+                let text: String = (0..length).map(|i| characters[(i * 7) % characters.len()] as char).collect();
+                let image = QrCode::with_error_correction_level(text.as_bytes(), level)
+                    .unwrap()
+                    .render::<image::Luma<u8>>()
+                    .module_dimensions(3, 3)
+                    .build();
+                let decoded = decode_qr_codes(image.width() as usize, image.height() as usize, image.as_raw());
+                assert_eq!(decoded, vec![text], "{:?} level, {} bytes", level, length);
+            }
+        }
+    }
+}
@@ -0,0 +1,74 @@
+// settings.rs - User preferences, stored next to the vault in settings.json
+use serde::{Serialize, Deserialize};
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::Path;
+
+pub const SETTINGS_FILE: &str = "settings.json";
+
+// Missing fields fall back to their defaults, so older settings files keep loading
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct Settings {
+    // How many previous passwords to keep on each entry
+    pub max_password_history: usize,
+    // Trashed entries older than this many days are purged on startup; 0 keeps them forever
+    pub trash_retention_days: u32,
+    // The Security page flags passwords not changed in this many months; 0 turns it off
+    pub audit_max_age_months: u32,
+    // Downloaded Pwned Passwords file or folder to check passwords against; empty turns it off
+    pub hibp_path: String,
+    // Address that "Plus alias" on the add form tags, e.g. me@example.com; empty hides the button
+    pub alias_email: String,
+    // Domain that accepts mail for any address, for "Catch-all alias"; empty hides the button
+    pub catch_all_domain: String,
+    // How many recently generated passwords the generator history keeps; 0 turns it off
+    pub generator_history_size: usize,
+    // Generated passwords are dropped from the history after this many minutes; 0 keeps them until the app closes
+    pub generator_history_minutes: u32,
+    // Named generator settings offered on the generator page
+    pub presets: Vec<crate::presets::GeneratorPreset>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            max_password_history: 10,
+            trash_retention_days: 30,
+            audit_max_age_months: 12,
+            hibp_path: String::new(),
+            alias_email: String::new(),
+            catch_all_domain: String::new(),
+            generator_history_size: 20,
+            generator_history_minutes: 60,
+            presets: crate::presets::default_presets(),
+        }
+    }
+}
+
+pub fn load_settings(file_path: &str) -> Result<Settings, std::io::Error> {
+    if !Path::new(file_path).exists() {
+        return Ok(Settings::default());
+    }
+
+    let mut file = File::open(file_path)?;
+    let mut contents = String::new();
+    file.read_to_string(&mut contents)?;
+
+    if contents.trim().is_empty() {
+        return Ok(Settings::default());
+    }
+
+    serde_json::from_str(&contents)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+}
+
+pub fn save_settings(settings: &Settings, file_path: &str) -> Result<(), std::io::Error> {
+    let json_data = serde_json::to_string_pretty(settings)
+        .map_err(std::io::Error::other)?;
+
+    let mut file = File::create(file_path)?;
+    file.write_all(json_data.as_bytes())?;
+    file.flush()?;
+    Ok(())
+}
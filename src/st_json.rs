@@ -3,6 +3,9 @@ use std::fs::{File};
 use std::io::{Write, Read};
 use std::path::Path;
 
+// Format of the timestamps stored in the vault
+pub const TIMESTAMP_FORMAT: &str = "%Y-%m-%d %H:%M:%S UTC";
+
 #[derive(Serialize, Deserialize, Clone)]
 pub struct PasswordDetails {
     pub name: String,
@@ -11,18 +14,213 @@ pub struct PasswordDetails {
     pub username: String,
     pub notes: String,
     pub created_at: String,
+    // Entries saved before entry types existed have no "kind" and load as logins
+    #[serde(default)]
+    pub kind: EntryKind,
+    // Optional one-time code settings (TOTP/HOTP)
+    #[serde(default)]
+    pub otp: Option<crate::otp::OtpConfig>,
+    // When the password was last changed; empty if it never was
+    #[serde(default)]
+    pub updated_at: String,
+    // Previous values of `value`, newest first, sealed with the history password
+    #[serde(default)]
+    pub history: Vec<PasswordHistoryEntry>,
+    // Folder path such as "Work/Servers"; empty for the top level
+    #[serde(default)]
+    pub folder: String,
+    // Extra named fields, e.g. from KeePass entries
+    #[serde(default)]
+    pub custom_fields: Vec<CustomField>,
+    // Remind to change the password every this many days; 0 means never
+    #[serde(default)]
+    pub rotation_days: u32,
+    // Set for derived passwords: `value` stays empty and the password is worked
+    // out again from the master password whenever it's needed
+    #[serde(default)]
+    pub derivation: Option<crate::derive::DerivationProfile>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct CustomField {
+    pub name: String,
+    pub value: String,
+    // Hidden in the UI and stored as a protected value in KeePass exports
+    #[serde(default)]
+    pub protected: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct PasswordHistoryEntry {
+    // The old password sealed with the history key, base64; see history_key.rs
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub sealed: String,
+    // The old password in plain text, only while it isn't sealed: in imports and
+    // exports, and in vaults saved before the history was encrypted
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub value: String,
+    pub changed_at: String,
+}
+
+// The type of an entry, serialized with a "type" tag.
+// `value` holds the password for logins, the key/token for API keys and the
+// passphrase for Wi-Fi networks; secure notes keep their text in `notes`,
+// cards and identities keep their fields here.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum EntryKind {
+    #[default]
+    Login,
+    SecureNote,
+    Card {
+        cardholder: String,
+        number: String,
+        expiry: String,
+        cvv: String,
+    },
+    Identity {
+        full_name: String,
+        address: String,
+        phone: String,
+        email: String,
+    },
+    ApiKey {
+        expires_at: String,
+    },
+    Wifi {
+        ssid: String,
+        security: crate::wifi::WifiSecurity,
+        hidden: bool,
+    },
 }
 
-#[derive(Serialize, Deserialize)]
+impl EntryKind {
+    pub fn label(&self) -> &'static str {
+        match self {
+            EntryKind::Login => "Login",
+            EntryKind::SecureNote => "Secure Note",
+            EntryKind::Card { .. } => "Payment Card",
+            EntryKind::Identity { .. } => "Identity",
+            EntryKind::ApiKey { .. } => "API Key",
+            EntryKind::Wifi { .. } => "Wi-Fi Network",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Default)]
 pub struct PasswordDatabase {
     pub passwords: Vec<PasswordDetails>,
+    // Deleted entries, kept until they are restored or purged
+    #[serde(default)]
+    pub trash: Vec<TrashedEntry>,
+    // Set once a history password has been chosen
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub history_lock: Option<crate::history_key::HistoryLock>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct TrashedEntry {
+    pub entry: PasswordDetails,
+    pub deleted_at: String,
+}
+
+// Luhn checksum used to catch typos in card numbers.
+// Spaces and dashes are ignored; anything else that isn't a digit fails.
+pub fn luhn_valid(number: &str) -> bool {
+    let digits: Vec<u32> = number
+        .chars()
+        .filter(|c| *c != ' ' && *c != '-')
+        .map(|c| c.to_digit(10))
+        .collect::<Option<Vec<u32>>>()
+        .unwrap_or_default();
+
+    if digits.len() < 12 || digits.len() > 19 {
+        return false;
+    }
+
+    let sum: u32 = digits
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(i, d)| {
+            if i % 2 == 1 {
+                let doubled = d * 2;
+                if doubled > 9 { doubled - 9 } else { doubled }
+            } else {
+                *d
+            }
+        })
+        .sum();
+
+    sum.is_multiple_of(10)
 }
 
-impl Default for PasswordDatabase {
-    fn default() -> Self {
-        Self {
-            passwords: Vec::new(),
+// Checks a card expiry in MM/YY form
+pub fn card_expiry_valid(expiry: &str) -> bool {
+    let Some((month, year)) = expiry.trim().split_once('/') else {
+        return false;
+    };
+    let month_ok = matches!(month.parse::<u32>(), Ok(1..=12)) && month.len() == 2;
+    let year_ok = year.len() == 2 && year.chars().all(|c| c.is_ascii_digit());
+    month_ok && year_ok
+}
+
+// Checks a YYYY-MM-DD date, used for API key expiry dates
+pub fn date_valid(date: &str) -> bool {
+    chrono::NaiveDate::parse_from_str(date.trim(), "%Y-%m-%d").is_ok()
+}
+
+// Current time for created_at, updated_at and history entries
+pub fn timestamp_now() -> String {
+    chrono::Utc::now().format(TIMESTAMP_FORMAT).to_string()
+}
+
+// Sets a new password on an entry, keeping the old one in its history sealed
+// with `history_key`. Only the newest `max_history` previous values are kept.
+// Returns false if the password was already `new_value`, so nothing changed.
+pub fn change_password(
+    entry: &mut PasswordDetails,
+    new_value: &str,
+    max_history: usize,
+    history_key: Option<&crate::history_key::HistoryKey>,
+) -> Result<bool, String> {
+    if entry.value == new_value {
+        return Ok(false);
+    }
+
+    if !entry.value.is_empty() && max_history > 0 {
+        let key = history_key.ok_or_else(|| "Unlock the password history first so the old password can be kept".to_string())?;
+        entry.history.insert(0, key.seal(&entry.value, &timestamp_now()));
+    }
+    entry.history.truncate(max_history);
+    entry.value = new_value.to_string();
+    Ok(true)
+}
+
+// KeePass and Bitwarden have no rotation reminder, so exports keep it in a
+// custom field with this name and imports read it back
+pub const ROTATION_FIELD: &str = "Saltr Rotation Days";
+
+pub fn rotation_field(rotation_days: u32) -> CustomField {
+    CustomField {
+        name: ROTATION_FIELD.to_string(),
+        value: rotation_days.to_string(),
+        protected: false,
+    }
+}
+
+// Takes the rotation field out of imported custom fields; 0 if there isn't one.
+// A value that isn't a number is left as an ordinary field.
+pub fn take_rotation_days(fields: &mut Vec<CustomField>) -> u32 {
+    let Some(position) = fields.iter().position(|f| f.name == ROTATION_FIELD) else {
+        return 0;
+    };
+    match fields[position].value.trim().parse() {
+        Ok(days) => {
+            fields.remove(position);
+            days
         }
+        Err(_) => 0,
     }
 }
 
@@ -34,15 +232,21 @@ pub fn create_password_details(
     username: &str,
     notes: &str,
 ) -> PasswordDetails {
-    use chrono::Utc;
-    
     PasswordDetails {
         name: name.to_string(),
         value: password.to_string(),
         website: website.to_string(),
         username: username.to_string(),
         notes: notes.to_string(),
-        created_at: Utc::now().format("%Y-%m-%d %H:%M:%S UTC").to_string(),
+        created_at: timestamp_now(),
+        kind: EntryKind::Login,
+        otp: None,
+        updated_at: String::new(),
+        history: Vec::new(),
+        folder: String::new(),
+        custom_fields: Vec::new(),
+        rotation_days: 0,
+        derivation: None,
     }
 }
 
@@ -69,7 +273,7 @@ pub fn load_password_database(file_path: &str) -> Result<PasswordDatabase, std::
 // Function to save password database to JSON file
 pub fn save_password_database(database: &PasswordDatabase, file_path: &str) -> Result<(), std::io::Error> {
     let json_data = serde_json::to_string_pretty(database)
-        .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))?;
+        .map_err(std::io::Error::other)?;
 
     let mut file = File::create(file_path)?;
     file.write_all(json_data.as_bytes())?;
@@ -79,18 +283,13 @@ pub fn save_password_database(database: &PasswordDatabase, file_path: &str) -> R
 
 // Main function to save password details to JSON (integrates with your UI)
 pub fn save_password_details_to_json(
-    name: &str,
-    password: &str,
-    website: &str,
-    username: &str,
-    notes: &str,
+    password_details: PasswordDetails,
     file_path: &str,
 ) -> Result<(), std::io::Error> {
     // Load existing database
     let mut database = load_password_database(file_path)?;
     
-    // Create new password entry
-    let password_details = create_password_details(name, password, website, username, notes);
+    let name = password_details.name.clone();
     
     // Add to database
     database.passwords.push(password_details);
@@ -102,6 +301,131 @@ pub fn save_password_details_to_json(
     Ok(())
 }
 
+// Function to overwrite the entry at `index` (same order as the "View Passwords" list)
+pub fn replace_password_at(
+    index: usize,
+    password_details: PasswordDetails,
+    file_path: &str,
+) -> Result<(), std::io::Error> {
+    let mut database = load_password_database(file_path)?;
+
+    let slot = database.passwords.get_mut(index).ok_or_else(|| {
+        std::io::Error::new(std::io::ErrorKind::NotFound, format!("No entry at position {}", index))
+    })?;
+    *slot = password_details;
+
+    save_password_database(&database, file_path)
+}
+
+// Function to move the entry at `index` into the trash instead of dropping it
+pub fn move_to_trash(index: usize, file_path: &str) -> Result<(), std::io::Error> {
+    let mut database = load_password_database(file_path)?;
+
+    if index >= database.passwords.len() {
+        return Err(std::io::Error::new(std::io::ErrorKind::NotFound, format!("No entry at position {}", index)));
+    }
+    let entry = database.passwords.remove(index);
+    database.trash.push(TrashedEntry {
+        entry,
+        deleted_at: timestamp_now(),
+    });
+
+    save_password_database(&database, file_path)
+}
+
+// Function to put a trashed entry back at the end of the password list
+pub fn restore_from_trash(index: usize, file_path: &str) -> Result<(), std::io::Error> {
+    let mut database = load_password_database(file_path)?;
+
+    if index >= database.trash.len() {
+        return Err(std::io::Error::new(std::io::ErrorKind::NotFound, format!("No trash item at position {}", index)));
+    }
+    let trashed = database.trash.remove(index);
+    database.passwords.push(trashed.entry);
+
+    save_password_database(&database, file_path)
+}
+
+// Function to permanently delete one trashed entry
+pub fn purge_from_trash(index: usize, file_path: &str) -> Result<(), std::io::Error> {
+    let mut database = load_password_database(file_path)?;
+
+    if index >= database.trash.len() {
+        return Err(std::io::Error::new(std::io::ErrorKind::NotFound, format!("No trash item at position {}", index)));
+    }
+    database.trash.remove(index);
+
+    save_password_database(&database, file_path)
+}
+
+// Function to permanently delete everything in the trash
+pub fn empty_trash(file_path: &str) -> Result<(), std::io::Error> {
+    let mut database = load_password_database(file_path)?;
+    database.trash.clear();
+    save_password_database(&database, file_path)
+}
+
+// Function to purge trashed entries older than `retention_days` (0 keeps them forever)
+// Returns how many entries were purged
+pub fn purge_expired_trash(retention_days: u32, file_path: &str) -> Result<usize, std::io::Error> {
+    if retention_days == 0 {
+        return Ok(0);
+    }
+    let mut database = load_password_database(file_path)?;
+    let cutoff = chrono::Utc::now().naive_utc() - chrono::Duration::days(retention_days as i64);
+
+    let initial_len = database.trash.len();
+    // Items whose date can't be read are kept rather than purged
+    database.trash.retain(|trashed| {
+        match chrono::NaiveDateTime::parse_from_str(&trashed.deleted_at, TIMESTAMP_FORMAT) {
+            Ok(deleted_at) => deleted_at > cutoff,
+            Err(_) => true,
+        }
+    });
+
+    let purged = initial_len - database.trash.len();
+    if purged > 0 {
+        save_password_database(&database, file_path)?;
+    }
+    Ok(purged)
+}
+
+// Function to add imported entries to the vault, leaving out exact duplicates
+// of existing entries. Returns (added, duplicates).
+pub fn import_entries(entries: Vec<PasswordDetails>, file_path: &str) -> Result<(usize, usize), std::io::Error> {
+    let mut database = load_password_database(file_path)?;
+    let (mut added, mut duplicates) = (0, 0);
+
+    for entry in entries {
+        let exists = database.passwords.iter().any(|existing| {
+            existing.name == entry.name
+                && existing.username == entry.username
+                && existing.website == entry.website
+                && existing.value == entry.value
+                && existing.kind == entry.kind
+        });
+        if exists {
+            duplicates += 1;
+        } else {
+            database.passwords.push(entry);
+            added += 1;
+        }
+    }
+
+    save_password_database(&database, file_path)?;
+    Ok((added, duplicates))
+}
+
+// Function to add imported entries straight into the trash
+pub fn import_trash(trash: Vec<TrashedEntry>, file_path: &str) -> Result<(), std::io::Error> {
+    if trash.is_empty() {
+        return Ok(());
+    }
+    let mut database = load_password_database(file_path)?;
+    database.trash.extend(trash);
+    save_password_database(&database, file_path)
+}
+
 // Function to get all saved passwords (useful for the "View Passwords" page)
 // Returns a vector of PasswordDetails
 //Disabled as it is not used in the current implementation
@@ -0,0 +1,218 @@
+// wifi.rs - Wi-Fi network entries and their "WIFI:" QR codes. Phone cameras
+// join the network when they scan one, so a printed code can be stuck on the
+// router or handed to guests. The format is
+//   WIFI:T:WPA;S:<network name>;P:<passphrase>;H:true;;
+// where \ ; , " and : in the name or passphrase are escaped with a backslash.
+use qrcode::QrCode;
+use serde::{Serialize, Deserialize};
+
+// The longest WPA passphrase; the "WPA key" preset makes keys this long
+pub const WPA_KEY_LENGTH: usize = 63;
+const MAX_SSID_BYTES: usize = 32;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum WifiSecurity {
+    // WPA, WPA2 and WPA3 personal all use the same kind of passphrase
+    #[default]
+    Wpa,
+    Wep,
+    Open,
+}
+
+impl WifiSecurity {
+    pub const ALL: [WifiSecurity; 3] = [WifiSecurity::Wpa, WifiSecurity::Wep, WifiSecurity::Open];
+
+    // How the security type is written in a WIFI: QR code
+    fn qr_type(self) -> &'static str {
+        match self {
+            WifiSecurity::Wpa => "WPA",
+            WifiSecurity::Wep => "WEP",
+            WifiSecurity::Open => "nopass",
+        }
+    }
+
+    // The name used in KeePass and Bitwarden fields, read back by `from_name`
+    pub fn name(self) -> &'static str {
+        match self {
+            WifiSecurity::Wpa => "WPA",
+            WifiSecurity::Wep => "WEP",
+            WifiSecurity::Open => "None",
+        }
+    }
+
+    pub fn from_name(name: &str) -> WifiSecurity {
+        match name.trim().to_ascii_uppercase().as_str() {
+            "WEP" => WifiSecurity::Wep,
+            "NONE" | "NOPASS" | "OPEN" => WifiSecurity::Open,
+            _ => WifiSecurity::Wpa,
+        }
+    }
+}
+
+impl std::fmt::Display for WifiSecurity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            WifiSecurity::Wpa => "WPA/WPA2/WPA3",
+            WifiSecurity::Wep => "WEP",
+            WifiSecurity::Open => "None (open)",
+        })
+    }
+}
+
+// Checks the network name and that the passphrase is one the security type allows
+pub fn check_network(ssid: &str, security: WifiSecurity, passphrase: &str) -> Result<(), String> {
+    if ssid.is_empty() {
+        return Err("Enter the network name (SSID)".to_string());
+    }
+    if ssid.len() > MAX_SSID_BYTES {
+        return Err(format!("Network names are at most {} bytes long", MAX_SSID_BYTES));
+    }
+    let is_hex = |text: &str| text.chars().all(|c| c.is_ascii_hexdigit());
+    let is_printable = |text: &str| text.chars().all(|c| (' '..='~').contains(&c));
+    match security {
+        WifiSecurity::Wpa => {
+            let valid = (8..=WPA_KEY_LENGTH).contains(&passphrase.len()) && is_printable(passphrase)
+                || passphrase.len() == 64 && is_hex(passphrase);
+            if !valid {
+                return Err("WPA passphrases are 8 to 63 printable ASCII characters (or 64 hex digits)".to_string());
+            }
+        }
+        WifiSecurity::Wep => {
+            let valid = matches!(passphrase.len(), 5 | 13) && is_printable(passphrase)
+                || matches!(passphrase.len(), 10 | 26) && is_hex(passphrase);
+            if !valid {
+                return Err("WEP keys are 5 or 13 characters, or 10 or 26 hex digits".to_string());
+            }
+        }
+        WifiSecurity::Open => {
+            if !passphrase.is_empty() {
+                return Err("Open networks have no passphrase; leave it empty".to_string());
+            }
+        }
+    }
+    Ok(())
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '\\' | ';' | ',' | '"' | ':') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+// The text that goes in the QR code
+pub fn qr_payload(ssid: &str, security: WifiSecurity, passphrase: &str, hidden: bool) -> String {
+    let mut payload = format!("WIFI:T:{};S:{};", security.qr_type(), escape(ssid));
+    if security != WifiSecurity::Open {
+        payload.push_str(&format!("P:{};", escape(passphrase)));
+    }
+    if hidden {
+        payload.push_str("H:true;");
+    }
+    payload.push(';');
+    payload
+}
+
+fn qr_code(payload: &str) -> Result<QrCode, String> {
+    QrCode::new(payload.as_bytes()).map_err(|e| format!("Could not make a QR code: {}", e))
+}
+
+// SVG markup of the QR code, for showing in the app
+pub fn qr_svg(payload: &str) -> Result<String, String> {
+    Ok(qr_code(payload)?
+        .render::<qrcode::render::svg::Color>()
+        .min_dimensions(200, 200)
+        .build())
+}
+
+// Saves the QR code for printing; the file's extension picks PNG or SVG
+pub fn save_qr(payload: &str, file_path: &str) -> Result<(), String> {
+    let extension = std::path::Path::new(file_path)
+        .extension()
+        .map(|extension| extension.to_string_lossy().to_ascii_lowercase());
+    match extension.as_deref() {
+        Some("svg") => {
+            let svg = qr_code(payload)?
+                .render::<qrcode::render::svg::Color>()
+                .min_dimensions(400, 400)
+                .build();
+            std::fs::write(file_path, svg).map_err(|e| format!("Could not write {}: {}", file_path, e))
+        }
+        Some("png") => qr_code(payload)?
+            .render::<image::Luma<u8>>()
+            .min_dimensions(600, 600)
+            .build()
+            .save(file_path)
+            .map_err(|e| format!("Could not write {}: {}", file_path, e)),
+        _ => Err("Save the QR code as a .png or .svg file".to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn payload_escapes_special_characters() {
+        assert_eq!(
+            qr_payload(r#"a\b;c,d"e:f"#, WifiSecurity::Wpa, r#"p;a:s,s"w\d!"#, false),
+            r#"WIFI:T:WPA;S:a\\b\;c\,d\"e\:f;P:p\;a\:s\,s\"w\\d!;;"#
+        );
+        assert_eq!(qr_payload("Guest", WifiSecurity::Open, "ignored", false), "WIFI:T:nopass;S:Guest;;");
+        assert_eq!(qr_payload("Home", WifiSecurity::Wep, "abcde", true), "WIFI:T:WEP;S:Home;P:abcde;H:true;;");
+    }
+
+    #[test]
+    fn passphrases_are_checked_for_the_security_type() {
+        let wpa = |passphrase: &str| check_network("Home", WifiSecurity::Wpa, passphrase).is_ok();
+        assert!(!wpa("1234567"));
+        assert!(wpa("12345678"));
+        assert!(wpa(&"x".repeat(WPA_KEY_LENGTH)));
+        assert!(!wpa(&"x".repeat(64)));
+        assert!(wpa(&"ab01".repeat(16)));
+        assert!(!wpa("pass\u{e9}word"));
+
+        let wep = |passphrase: &str| check_network("Home", WifiSecurity::Wep, passphrase).is_ok();
+        assert!(wep("abcde"));
+        assert!(wep("abcdefghijklm"));
+        assert!(wep("0123456789"));
+        assert!(!wep("012345678z"));
+        assert!(wep(&"a1".repeat(13)));
+        assert!(!wep("abcdef"));
+
+        assert!(check_network("Guest", WifiSecurity::Open, "").is_ok());
+        assert!(check_network("Guest", WifiSecurity::Open, "password").is_err());
+        assert!(check_network("", WifiSecurity::Open, "").is_err());
+        assert!(check_network(&"n".repeat(MAX_SSID_BYTES + 1), WifiSecurity::Open, "").is_err());
+    }
+
+    #[test]
+    fn saved_codes_scan_back_to_the_payload() {
+        let payload = qr_payload("Home; 5GHz", WifiSecurity::Wpa, "correct horse battery", true);
+        let path = |extension: &str| {
+            std::env::temp_dir()
+                .join(format!("saltr-wifi-test-{}.{}", std::process::id(), extension))
+                .to_string_lossy()
+                .to_string()
+        };
+
+        save_qr(&payload, &path("png")).unwrap();
+        let image = image::open(path("png")).unwrap().to_luma8();
+        std::fs::remove_file(path("png")).unwrap();
+        let decoded = crate::qr::decode_qr_codes(image.width() as usize, image.height() as usize, image.as_raw());
+        assert_eq!(decoded, vec![payload.clone()]);
+
+        save_qr(&payload, &path("SVG")).unwrap();
+        let svg = std::fs::read_to_string(path("SVG")).unwrap();
+        std::fs::remove_file(path("SVG")).unwrap();
+        assert!(svg.contains("<svg"));
+
+        assert!(save_qr(&payload, &path("txt")).is_err());
+        assert!(!std::path::Path::new(&path("txt")).exists());
+    }
+}